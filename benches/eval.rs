@@ -0,0 +1,86 @@
+use euphie::{env::*, eval::*, parse::*, tokenize::*, vm::*};
+use std::time::Instant;
+
+const FIB: &str = "
+(let ()
+  (def fib (lambda (n) (if (< n 2) n (+ (fib (- n 1)) (fib (- n 2))))))
+  (fib 20))";
+
+const LISTS: &str = "
+(let ()
+  (def range (lambda (n acc) (if (= n 0) acc (range (- n 1) `(,n ,@acc)))))
+  (def sum (lambda (l acc) (if (= (len l) 1) (+ acc (car l)) (sum (cdr l) (+ acc (car l))))))
+  (def squares (lambda (l) (if (= (len l) 1) `(,(* (car l) (car l))) `(,(* (car l) (car l)) ,@(squares (cdr l))))))
+  (sum (squares (range 200 '())) 0))";
+
+// a big quoted list that's referenced over and over, which shows how expensive it is to copy a value
+fn quoted_data() -> String {
+    let items: Vec<String> = (0..10000).map(|n| n.to_string()).collect();
+    format!(
+        "
+(let ((data '({}))
+      (text \"{}\"))
+  (def count (lambda (n acc) (if (= n 0) acc (count (- n 1) (+ acc (len data))))))
+  (def strings (lambda (n) (if (= n 0) text (let ((copy text)) (strings (- n 1))))))
+  (strings 1000)
+  (count 1000 0))",
+        items.join(" "),
+        "x".repeat(10000)
+    )
+}
+
+// walks a big list with cdr, which is quadratic if every cdr copies the rest of the list
+fn long_list() -> String {
+    let items: Vec<String> = (0..5000).map(|n| n.to_string()).collect();
+    format!(
+        "
+(let ((data '({})))
+  (def walk (lambda (l n) (if (= (len l) 1) n (walk (cdr l) (+ n (car l))))))
+  (walk data 0))",
+        items.join(" ")
+    )
+}
+
+fn read(code: &str) -> Value {
+    let mut tokens = tokenize(String::from(code)).unwrap();
+    tokens.reverse();
+    parse(&mut tokens).unwrap()
+}
+
+fn bench(name: &str, iterations: u32, mut f: impl FnMut() -> Value) {
+    // run it once first, so the result can be shown and caches are warm
+    let result = f();
+
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    let elapsed = start.elapsed() / iterations;
+
+    println!("{:<24} {:>12?}   (result: {:?})", name, elapsed, result);
+}
+
+fn main() {
+    let quoted = quoted_data();
+    let long = long_list();
+    for (name, code) in [
+        ("fib", FIB),
+        ("lists", LISTS),
+        ("quoted", quoted.as_str()),
+        ("cdr", long.as_str()),
+    ] {
+        let tree = read(code);
+
+        bench(&format!("{} (evaluator)", name), 10, || {
+            let mut env = Env::new();
+            eval_toplevel(&tree, &mut env).unwrap()
+        });
+
+        let mut env = Env::new();
+        let expanded = expand_value(&tree, &mut env).unwrap();
+        let function = compile(&expanded, &env).unwrap();
+        bench(&format!("{} (vm)", name), 10, || {
+            Vm::new().run(function.clone()).unwrap()
+        });
+    }
+}
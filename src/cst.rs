@@ -0,0 +1,175 @@
+use crate::tokenize::{tokenize, Token, TokenType};
+use std::fmt::Display;
+
+/// Something in the code between two tokens, which the parser skips.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trivia {
+    Whitespace(String),
+    // from the ; up to (but not including) the end of the line
+    Comment(String),
+}
+
+/// A form exactly as it's written in the code.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    // everything but a list (including its prefix)
+    Atom(String),
+    List {
+        prefix: String,
+        items: Vec<Item>,
+        // the trivia before the closing parenthesis
+        end: Vec<Trivia>,
+    },
+}
+
+/// A node along with the trivia before it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub trivia: Vec<Trivia>,
+    pub node: Node,
+}
+
+/// A concrete syntax tree of a whole file, which keeps everything in the code (whitespace and comments
+/// included), so printing it gives back exactly the same code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cst {
+    pub items: Vec<Item>,
+    // the trivia at the end of the file
+    pub end: Vec<Trivia>,
+}
+
+// splits the code between two tokens into whitespace and comments
+fn trivia(code: &str) -> Vec<Trivia> {
+    let mut trivia = vec![];
+    let mut rest = code;
+    while let Some(c) = rest.chars().next() {
+        let length = if c == ';' {
+            rest.find('\n').unwrap_or(rest.len())
+        } else {
+            rest.find(|c: char| !c.is_whitespace())
+                .unwrap_or(rest.len())
+        };
+
+        let text = String::from(&rest[..length]);
+        trivia.push(if c == ';' {
+            Trivia::Comment(text)
+        } else {
+            Trivia::Whitespace(text)
+        });
+        rest = &rest[length..];
+    }
+    trivia
+}
+
+struct Builder<'a> {
+    code: &'a str,
+    tokens: std::vec::IntoIter<Token>,
+    // where the previous token ended
+    offset: usize,
+}
+
+impl Builder<'_> {
+    // returns the next token and the trivia before it
+    fn next(&mut self) -> Option<(Vec<Trivia>, Token)> {
+        let token = self.tokens.next()?;
+        let trivia = trivia(&self.code[self.offset..token.start]);
+        self.offset = token.end;
+        Some((trivia, token))
+    }
+
+    fn node(&mut self, token: Token) -> Result<Node, String> {
+        let text = &self.code[token.start..token.end];
+        match token.t {
+            TokenType::StartParen => {
+                let mut items = vec![];
+                loop {
+                    match self.next() {
+                        None => return Err(String::from("Expected ')' at end of file")),
+                        Some((end, token)) if token.t == TokenType::EndParen => {
+                            return Ok(Node::List {
+                                prefix: String::from(&text[..text.len() - 1]),
+                                items,
+                                end,
+                            });
+                        }
+                        Some((trivia, token)) => items.push(Item {
+                            trivia,
+                            node: self.node(token)?,
+                        }),
+                    }
+                }
+            }
+            TokenType::EndParen => Err(String::from("Unexpected ')'")),
+            _ => Ok(Node::Atom(String::from(text))),
+        }
+    }
+}
+
+/// Reads the code into a concrete syntax tree. The tokens are read the same way as by [`tokenize`].
+pub fn parse_cst(code: &str) -> Result<Cst, String> {
+    let mut builder = Builder {
+        code,
+        tokens: tokenize(String::from(code))?.into_iter(),
+        offset: 0,
+    };
+
+    let mut items = vec![];
+    while let Some((trivia, token)) = builder.next() {
+        items.push(Item {
+            trivia,
+            node: builder.node(token)?,
+        });
+    }
+
+    Ok(Cst {
+        items,
+        end: trivia(&code[builder.offset..]),
+    })
+}
+
+impl Display for Trivia {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Trivia::Whitespace(s) | Trivia::Comment(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl Display for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Node::Atom(s) => write!(f, "{}", s),
+            Node::List { prefix, items, end } => {
+                write!(f, "{}(", prefix)?;
+                for item in items {
+                    write!(f, "{}", item)?;
+                }
+                for trivia in end {
+                    write!(f, "{}", trivia)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl Display for Item {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for trivia in &self.trivia {
+            write!(f, "{}", trivia)?;
+        }
+        write!(f, "{}", self.node)
+    }
+}
+
+impl Display for Cst {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for item in &self.items {
+            write!(f, "{}", item)?;
+        }
+        for trivia in &self.end {
+            write!(f, "{}", trivia)?;
+        }
+        Ok(())
+    }
+}
@@ -1,35 +1,182 @@
-use crate::parse::*;
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use crate::{
+    gc, module::ModuleRegistry, native::*, parse::*, prelude, sandbox::Capabilities, symbol::Symbol,
+};
+use std::{cell::RefCell, collections::HashMap, fmt::Debug, rc::Rc};
 
+/// The root environment holds the globals in a hash table, while every other environment is a frame
+/// of local variables stored in a Vec, so they can also be accessed by their slot (see [`Env::get_at`]).
 #[derive(Debug, PartialEq, Default)]
 pub struct Env {
     parent: Option<Rc<RefCell<Env>>>,
-    bindings: HashMap<String, Value>,
+    globals: HashMap<Symbol, Value>,
+    names: Vec<Symbol>,
+    slots: Vec<Value>,
+    // copied from the root environment, so checking them doesn't have to walk up to it
+    capabilities: Rc<Capabilities>,
+    // only set in root environments
+    modules: Option<Rc<RefCell<ModuleRegistry>>>,
 }
 
 impl Env {
-    pub fn new() -> Self {
-        Default::default()
+    /// Creates a new root environment with access to every module, which imports modules from the current
+    /// directory (see [`crate::sandbox::EnvBuilder`]).
+    /// Environments are always created behind an Rc, so the garbage collector can keep track of them (see
+    /// [`gc::collect`]).
+    pub fn new() -> Rc<RefCell<Self>> {
+        Self::with_capabilities(Capabilities::all())
     }
 
-    pub fn extend(parent: Rc<RefCell<Self>>) -> Self {
-        Self {
-            bindings: HashMap::new(),
-            parent: Some(parent),
+    pub fn with_capabilities(capabilities: Capabilities) -> Rc<RefCell<Self>> {
+        Self::new_root(Rc::new(capabilities), Default::default())
+    }
+
+    /// Creates a new root environment, with everything the prelude defines unless it's turned off.
+    pub(crate) fn new_root(
+        capabilities: Rc<Capabilities>,
+        modules: Rc<RefCell<ModuleRegistry>>,
+    ) -> Rc<RefCell<Self>> {
+        let mut globals = HashMap::new();
+        if let Some(bindings) = prelude::bindings(&capabilities, &modules) {
+            globals.extend(bindings.iter().cloned());
+        }
+
+        let env = Rc::new(RefCell::new(Self {
+            globals,
+            capabilities,
+            modules: Some(modules),
+            ..Default::default()
+        }));
+        gc::track(&env);
+        env
+    }
+
+    /// Creates an empty root environment that shares the capabilities and loaded modules of this one's root,
+    /// for a module to be evaluated in.
+    pub fn fresh_root(&self) -> Option<Rc<RefCell<Self>>> {
+        Some(Self::new_root(self.capabilities.clone(), self.modules()?))
+    }
+
+    pub fn extend(parent: &Rc<RefCell<Self>>) -> Rc<RefCell<Self>> {
+        let env = Rc::new(RefCell::new(Self {
+            parent: Some(parent.clone()),
+            capabilities: parent.borrow().capabilities.clone(),
+            ..Default::default()
+        }));
+        gc::track(&env);
+        env
+    }
+
+    pub fn parent(&self) -> Option<Rc<RefCell<Env>>> {
+        self.parent.clone()
+    }
+
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// Returns the modules loaded by this environment's root. It's only missing in environments that were
+    /// cleared by the garbage collector.
+    pub fn modules(&self) -> Option<Rc<RefCell<ModuleRegistry>>> {
+        match &self.parent {
+            Some(parent) => parent.borrow().modules(),
+            None => self.modules.clone(),
         }
     }
 
-    pub fn get(&self, name: &str) -> Option<Value> {
-        match self.bindings.get(name) {
-            Some(value) => Some(value.clone()),
-            None => self
-                .parent
-                .as_ref()
-                .and_then(|o| o.borrow().get(name).clone()),
+    /// Returns the global variables, which are only in the root environment.
+    pub fn globals(&self) -> impl Iterator<Item = (Symbol, Value)> + '_ {
+        self.globals
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+    }
+
+    /// Returns every value stored in this environment (but not its parents).
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.globals.values().chain(self.slots.iter())
+    }
+
+    /// Returns the slot of a local variable in this frame (always None for the root environment).
+    pub fn slot(&self, name: &Symbol) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    pub fn get(&self, name: Symbol) -> Option<Value> {
+        if self.parent.is_none() {
+            return self.globals.get(&name).cloned();
+        }
+
+        match self.slot(&name) {
+            Some(slot) => Some(self.slots[slot].clone()),
+            None => self.parent.as_ref().and_then(|o| o.borrow().get(name)),
         }
     }
 
-    pub fn set(&mut self, name: &str, val: Value) {
-        self.bindings.insert(name.to_string(), val);
+    /// Returns whether the name is bound in a frame of local variables, rather than being global or unbound.
+    pub fn is_local(&self, name: Symbol) -> bool {
+        match &self.parent {
+            None => false,
+            Some(_) if self.slot(&name).is_some() => true,
+            Some(parent) => parent.borrow().is_local(name),
+        }
+    }
+
+    /// Returns the value in the given slot of the frame `depth` levels up from this one, or None if there's no
+    /// such slot (when the code was resolved for another environment).
+    pub fn get_at(&self, depth: usize, slot: usize) -> Option<Value> {
+        if depth == 0 {
+            return self.slots.get(slot).cloned();
+        }
+
+        self.parent.as_ref()?.borrow().get_at(depth - 1, slot)
+    }
+
+    /// Defines a function implemented in Rust, which gets the evaluated arguments.
+    pub fn register_fn(
+        &mut self,
+        name: &str,
+        function: impl Fn(&[Value]) -> Result<Value, String> + 'static,
+    ) {
+        let function = NativeFunction::new(name, function);
+        self.set(function.name.clone(), Value::Native(Rc::new(function)));
+    }
+
+    /// Defines a function implemented in Rust that takes typed arguments, e.g. `|a: f64, b: f64| a + b`. The
+    /// number and types of the arguments are checked before it's called (see [`FromValue`]).
+    pub fn register_typed_fn<Args, F: TypedFn<Args>>(&mut self, name: &str, function: F) {
+        let function = NativeFunction::typed(name, function);
+        self.set(function.name.clone(), Value::Native(Rc::new(function)));
+    }
+
+    pub fn set(&mut self, name: Symbol, val: Value) {
+        if self.parent.is_none() {
+            self.globals.insert(name, val);
+            return;
+        }
+
+        // setting an existing name reuses its slot, new names get added to the end, so slots never change
+        match self.slot(&name) {
+            Some(slot) => self.slots[slot] = val,
+            None => {
+                self.names.push(name);
+                self.slots.push(val);
+            }
+        }
+    }
+}
+
+/// The environment a lambda was defined in. It's compared by identity and isn't printed, since the
+/// environment usually contains the lambda itself.
+#[derive(Clone)]
+pub struct CapturedEnv(pub Rc<RefCell<Env>>);
+
+impl Debug for CapturedEnv {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<env>")
+    }
+}
+
+impl PartialEq for CapturedEnv {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
     }
 }
@@ -0,0 +1,397 @@
+use super::{
+    eval_value,
+    function::{apply_lambda, parse_params},
+    pattern::pattern_names,
+    syntax::expand_syntax,
+};
+use crate::{env::*, interpreter, parse::*, symbol::Symbol, util::fresh_symbol};
+use std::{cell::RefCell, rc::Rc};
+
+/// Expands the form once if it's a call to a macro, otherwise returns None.
+pub fn macroexpand_1(form: &Value, env: &mut Rc<RefCell<Env>>) -> Result<Option<Value>, String> {
+    let scope = env.clone();
+    expand_once(form, env, &|name| scope.borrow().is_local(name))
+}
+
+// is_local tells whether a name is a local variable where the form is, see expand_syntax
+fn expand_once(
+    form: &Value,
+    env: &mut Rc<RefCell<Env>>,
+    is_local: &dyn Fn(Symbol) -> bool,
+) -> Result<Option<Value>, String> {
+    let list = match form {
+        Value::List(l) if !l.is_empty() => l,
+        _ => return Ok(None),
+    };
+
+    let fun = match &list[0] {
+        Value::Symbol(s) => env.borrow().get(s.clone()),
+        _ => None,
+    };
+
+    match fun {
+        Some(Value::Lambda {
+            params,
+            body,
+            is_macro: true,
+            env: captured,
+        }) => Ok(Some(apply_lambda(&params, &body, &captured, &list[1..])?)),
+        Some(Value::Syntax {
+            literals,
+            rules,
+            env: definition,
+        }) => Ok(Some(expand_syntax(
+            &literals,
+            &rules,
+            &definition,
+            list,
+            is_local,
+        )?)),
+
+        _ => Ok(None),
+    }
+}
+
+/// Expands the form until it's no longer a call to a macro (but doesn't expand its subforms).
+pub fn macroexpand(form: &Value, env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    let mut form = form.clone();
+    while let Some(expanded) = macroexpand_1(&form, env)? {
+        form = expanded;
+    }
+
+    Ok(form)
+}
+
+/// Expands the form and all of its subforms, except for quoted ones.
+pub fn macroexpand_all(form: &Value, env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    match macroexpand(form, env)? {
+        Value::List(l) => {
+            if l.first() == Some(&Value::Symbol(Symbol::QUOTE)) {
+                return Ok(Value::List(l));
+            }
+
+            let mut list = vec![];
+            for item in l.iter() {
+                list.push(macroexpand_all(item, env)?);
+            }
+
+            Ok(Value::List(list.into()))
+        }
+
+        value => Ok(value),
+    }
+}
+
+fn eval_expand_argument(
+    name: &str,
+    list: &[Value],
+    env: &mut Rc<RefCell<Env>>,
+) -> Result<Value, String> {
+    if list.len() != 2 {
+        return Err(format!("\"{}\" requires 1 argument", name));
+    }
+
+    eval_value(&list[1], env)
+}
+
+pub fn eval_macro_expand_1(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    let form = eval_expand_argument("macroexpand-1", list, env)?;
+    Ok(macroexpand_1(&form, env)?.unwrap_or(form))
+}
+
+pub fn eval_macro_expand(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    let form = eval_expand_argument("macroexpand", list, env)?;
+    macroexpand(&form, env)
+}
+
+pub fn eval_macro_expand_all(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    let form = eval_expand_argument("macroexpand-all", list, env)?;
+    macroexpand_all(&form, env)
+}
+
+pub fn eval_gensym(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // gensym [prefix]
+    let prefix = match list.len() {
+        1 => String::from("g"),
+        2 => match eval_value(&list[1], env)? {
+            Value::String(s) => s.to_string(),
+            Value::Symbol(s) => s.to_string(),
+            _ => {
+                return Err(String::from(
+                    "The prefix for \"gensym\" must be a string or symbol",
+                ))
+            }
+        },
+        _ => return Err(String::from("\"gensym\" requires 0 or 1 arguments")),
+    };
+
+    Ok(Value::Symbol(fresh_symbol(&prefix)))
+}
+
+struct Expander<'a> {
+    env: &'a mut Rc<RefCell<Env>>,
+    // the names bound around the form that's being expanded (parameters, let bindings, pattern variables and
+    // names defined with def inside of other forms), which shadow macros with the same name
+    locals: Vec<Symbol>,
+}
+
+impl Expander<'_> {
+    // like macroexpand, but a call to a local is never a macro call
+    fn macroexpand(&mut self, form: &Value) -> Result<Value, String> {
+        let mut form = form.clone();
+        loop {
+            if let Value::List(l) = &form {
+                if matches!(l.first(), Some(Value::Symbol(s)) if self.locals.contains(s)) {
+                    return Ok(form);
+                }
+            }
+
+            let Self { env, locals } = self;
+            let scope = env.clone();
+            let is_local = |name| locals.contains(&name) || scope.borrow().is_local(name);
+            match expand_once(&form, env, &is_local)? {
+                Some(expanded) => form = expanded,
+                None => return Ok(form),
+            }
+        }
+    }
+
+    fn expand_all(&mut self, forms: &[Value]) -> Result<Vec<Value>, String> {
+        let mut expanded = vec![];
+        for form in forms {
+            expanded.push(self.expand(form, false)?);
+        }
+
+        Ok(expanded)
+    }
+
+    // expands the forms with the names bound, which are only bound until the end of the forms
+    fn expand_in_scope(
+        &mut self,
+        names: Vec<Symbol>,
+        forms: &[Value],
+    ) -> Result<Vec<Value>, String> {
+        let outer = self.locals.len();
+        self.locals.extend(names);
+        let expanded = self.expand_all(forms);
+        self.locals.truncate(outer);
+        expanded
+    }
+
+    fn expand_quasiquoted(&mut self, value: &Value, depth: usize) -> Result<Value, String> {
+        match value {
+            Value::List(l) if l.len() == 2 => match &l[0] {
+                Value::Symbol(
+                    Symbol::UNQUOTE | Symbol::SPLICE_UNQUOTE | Symbol::UNQUOTE_SPLICING,
+                ) => {
+                    // only unquotes belonging to the outermost quasiquote get evaluated, so only those are
+                    // expanded
+                    let inner = if depth == 1 {
+                        self.expand(&l[1], false)?
+                    } else {
+                        self.expand_quasiquoted(&l[1], depth - 1)?
+                    };
+                    Ok(Value::List(vec![l[0].clone(), inner].into()))
+                }
+                Value::Symbol(Symbol::QUASIQUOTE) => Ok(Value::List(
+                    vec![l[0].clone(), self.expand_quasiquoted(&l[1], depth + 1)?].into(),
+                )),
+                _ => Ok(Value::List(
+                    vec![
+                        self.expand_quasiquoted(&l[0], depth)?,
+                        self.expand_quasiquoted(&l[1], depth)?,
+                    ]
+                    .into(),
+                )),
+            },
+
+            Value::List(l) => {
+                let mut list = vec![];
+                for item in l.iter() {
+                    list.push(self.expand_quasiquoted(item, depth)?);
+                }
+                Ok(Value::List(list.into()))
+            }
+
+            _ => Ok(value.clone()),
+        }
+    }
+
+    /// Expands every macro call in a form, leaving quoted data, parameter lists, binding names and patterns
+    /// alone.
+    ///
+    /// Macros defined with def or define-syntax at the top level are also defined while expanding, so the
+    /// forms after them can use them. Anywhere else they're only defined if the code runs.
+    fn expand(&mut self, form: &Value, toplevel: bool) -> Result<Value, String> {
+        interpreter::nested(|| self.expand_list(form, toplevel))
+    }
+
+    fn expand_list(&mut self, form: &Value, toplevel: bool) -> Result<Value, String> {
+        let l = match self.macroexpand(form)? {
+            Value::List(l) if !l.is_empty() => l,
+            value => return Ok(value),
+        };
+
+        let head = match &l[0] {
+            Value::Symbol(s) if !self.locals.contains(s) => s.clone(),
+            _ => return Ok(Value::List(self.expand_all(&l)?.into())),
+        };
+
+        let mut list = vec![l[0].clone()];
+        match head {
+            // a module's body is expanded when it's evaluated, in the module's own environment
+            Symbol::QUOTE | Symbol::SYNTAX_RULES | Symbol::MODULE => return Ok(Value::List(l)),
+
+            Symbol::QUASIQUOTE if l.len() == 2 => list.push(self.expand_quasiquoted(&l[1], 1)?),
+
+            // (lambda params body)
+            Symbol::LAMBDA | Symbol::MACRO if l.len() >= 2 => {
+                let names = parse_params(&l[1]).map(|p| p.names()).unwrap_or_default();
+                list.push(l[1].clone());
+                list.extend(self.expand_in_scope(names, &l[2..])?);
+            }
+
+            // (let ((name value)...) body...), where let* and letrec bind the names while the values are
+            // expanded too
+            Symbol::LET | Symbol::LET_STAR | Symbol::LETREC if l.len() >= 2 => {
+                let outer = self.locals.len();
+                let bindings = match &l[1] {
+                    Value::List(bindings) => {
+                        let mut names = vec![];
+                        for binding in bindings.iter() {
+                            if let Value::List(b) = binding {
+                                if let Some(Value::Symbol(s)) = b.first() {
+                                    names.push(s.clone());
+                                }
+                            }
+                        }
+                        if head == Symbol::LETREC {
+                            self.locals.extend(names.iter().cloned());
+                        }
+
+                        let mut expanded = vec![];
+                        for binding in bindings.iter() {
+                            match binding {
+                                Value::List(b) if b.len() == 2 => {
+                                    expanded.push(Value::List(
+                                        vec![b[0].clone(), self.expand(&b[1], false)?].into(),
+                                    ));
+                                    if let (&Symbol::LET_STAR, Value::Symbol(s)) = (&head, &b[0]) {
+                                        self.locals.push(s.clone());
+                                    }
+                                }
+                                _ => expanded.push(binding.clone()),
+                            }
+                        }
+                        if head == Symbol::LET {
+                            self.locals.extend(names);
+                        }
+                        Value::List(expanded.into())
+                    }
+                    bindings => bindings.clone(),
+                };
+                list.push(bindings);
+                let body = self.expand_all(&l[2..]);
+                self.locals.truncate(outer);
+                list.extend(body?);
+            }
+
+            // (labels ((name params body)...) body...)
+            Symbol::LABELS if l.len() >= 2 => {
+                let outer = self.locals.len();
+                let definitions = match &l[1] {
+                    Value::List(definitions) => {
+                        for definition in definitions.iter() {
+                            if let Value::List(d) = definition {
+                                if let Some(Value::Symbol(s)) = d.first() {
+                                    self.locals.push(s.clone());
+                                }
+                            }
+                        }
+
+                        let mut expanded = vec![];
+                        for definition in definitions.iter() {
+                            match definition {
+                                Value::List(d) if d.len() == 3 => {
+                                    let params =
+                                        parse_params(&d[1]).map(|p| p.names()).unwrap_or_default();
+                                    let body = self.expand_in_scope(params, &d[2..])?;
+                                    expanded.push(Value::List(
+                                        vec![d[0].clone(), d[1].clone(), body[0].clone()].into(),
+                                    ));
+                                }
+                                _ => expanded.push(definition.clone()),
+                            }
+                        }
+                        Value::List(expanded.into())
+                    }
+                    definitions => definitions.clone(),
+                };
+                list.push(definitions);
+                let body = self.expand_all(&l[2..]);
+                self.locals.truncate(outer);
+                list.extend(body?);
+            }
+
+            // (match value (pattern [when guard] body...)...)
+            Symbol::MATCH if l.len() >= 2 => {
+                list.push(self.expand(&l[1], false)?);
+                for clause in &l[2..] {
+                    match clause {
+                        Value::List(c) if !c.is_empty() => {
+                            let mut expanded = vec![c[0].clone()];
+                            let mut rest = &c[1..];
+                            if rest.first() == Some(&Value::Symbol(Symbol::WHEN)) {
+                                expanded.push(rest[0].clone());
+                                rest = &rest[1..];
+                            }
+                            expanded.extend(self.expand_in_scope(pattern_names(&c[0]), rest)?);
+                            list.push(Value::List(expanded.into()));
+                        }
+                        _ => list.push(clause.clone()),
+                    }
+                }
+            }
+
+            // (def name value), which defines the macro right away if the value is one and it's at the top level
+            Symbol::DEF if l.len() == 3 => {
+                list.push(l[1].clone());
+                list.push(self.expand(&l[2], false)?);
+
+                if !toplevel {
+                    // the name is bound once the def runs, so from here on a call to it isn't expanded
+                    if let Value::Symbol(s) = &l[1] {
+                        self.locals.push(s.clone());
+                    }
+                } else if let Value::List(value) = &list[2] {
+                    if value.first() == Some(&Value::Symbol(Symbol::MACRO)) {
+                        eval_value(&Value::List(list.clone().into()), self.env)?;
+                    }
+                }
+            }
+
+            // (define-syntax name (syntax-rules ...)), which is defined right away at the top level
+            Symbol::DEFINE_SYNTAX => {
+                if toplevel {
+                    eval_value(&Value::List(l.clone()), self.env)?;
+                } else if let Some(Value::Symbol(s)) = l.get(1) {
+                    self.locals.push(s.clone());
+                }
+                return Ok(Value::List(l));
+            }
+
+            _ => list.extend(self.expand_all(&l[1..])?),
+        }
+
+        Ok(Value::List(list.into()))
+    }
+}
+
+/// Fully expands a top-level form, so evaluating it doesn't need to expand any macros known at this point.
+pub fn expand_toplevel(form: &Value, env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    Expander {
+        env,
+        locals: vec![],
+    }
+    .expand(form, true)
+}
@@ -0,0 +1,47 @@
+use super::eval_value;
+use crate::{env::*, parse::*};
+use std::{cell::RefCell, rc::Rc};
+
+pub fn eval_send(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // send object :method args..., which calls a method of a foreign object with the object and the arguments
+    if list.len() < 3 {
+        return Err(String::from(
+            "\"send\" requires an object and a method name",
+        ));
+    }
+
+    let object = eval_value(&list[1], env)?;
+    let foreign = match &object {
+        Value::Foreign(foreign) => foreign.clone(),
+        _ => {
+            return Err(String::from(
+                "Methods can only be called on foreign objects",
+            ))
+        }
+    };
+    let name = match eval_value(&list[2], env)? {
+        Value::Keyword(k) | Value::Symbol(k) => k,
+        _ => return Err(String::from("Method name must be a keyword or symbol")),
+    };
+    let Some(method) = foreign.type_.get_method(name.clone()) else {
+        return Err(format!("{} has no method {}", foreign.type_.name, name));
+    };
+
+    let mut args = vec![object];
+    for value in &list[3..] {
+        args.push(eval_value(value, env)?);
+    }
+    method.call(&args)
+}
+
+pub fn eval_foreign_type(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // foreign-type value, which returns the name of a foreign object's type, or nil for anything else
+    if list.len() != 2 {
+        return Err(String::from("\"foreign-type\" requires 1 argument"));
+    }
+
+    match eval_value(&list[1], env)? {
+        Value::Foreign(foreign) => Ok(Value::String(foreign.type_.name.as_str().into())),
+        _ => Ok(Value::Nil),
+    }
+}
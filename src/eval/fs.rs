@@ -0,0 +1,222 @@
+use super::eval_value;
+use crate::{env::*, interpreter, parse::*, sandbox::normalize, symbol::Symbol};
+use std::{
+    cell::RefCell,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::UNIX_EPOCH,
+};
+
+fn check_args(name: &str, list: &[Value], count: usize) -> Result<(), String> {
+    if list.len() != count + 1 {
+        return Err(match count {
+            1 => format!("\"{}\" requires 1 argument", name),
+            _ => format!("\"{}\" requires {} arguments", name, count),
+        });
+    }
+    Ok(())
+}
+
+fn eval_string(name: &str, value: &Value, env: &mut Rc<RefCell<Env>>) -> Result<Rc<str>, String> {
+    match eval_value(value, env)? {
+        Value::String(s) => Ok(s),
+        _ => Err(format!("Arguments to \"{}\" must be strings", name)),
+    }
+}
+
+// evaluates a path argument and resolves it against the filesystem root, see Capabilities::resolve_path
+fn eval_path(name: &str, value: &Value, env: &mut Rc<RefCell<Env>>) -> Result<PathBuf, String> {
+    let path = eval_string(name, value, env)?;
+    let resolved = env.borrow().capabilities().resolve_path(&path)?;
+    Ok(resolved)
+}
+
+fn path_to_value(path: &Path) -> Result<Value, String> {
+    let path = path.to_string_lossy();
+    interpreter::check_string_length(path.chars().count())?;
+    Ok(Value::String(path.as_ref().into()))
+}
+
+pub fn eval_read_file(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // read-file path, which returns everything in the file as a string
+    check_args("read-file", list, 1)?;
+    let path = eval_path("read-file", &list[1], env)?;
+
+    let text = fs::read_to_string(&path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    interpreter::check_string_length(text.chars().count())?;
+    Ok(Value::String(text.into()))
+}
+
+pub fn eval_write_file(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // write-file path string replaces what's in the file, append-file path string adds to the end of it,
+    // and both create it if it doesn't exist
+    let append = list[0] == Value::Symbol(Symbol::APPEND_FILE);
+    let name = if append { "append-file" } else { "write-file" };
+    check_args(name, list, 2)?;
+    let path = eval_path(name, &list[1], env)?;
+    let text = eval_string(name, &list[2], env)?;
+
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .append(append)
+        .truncate(!append)
+        .open(&path)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .map_err(|e| format!("Could not write to {}: {}", path.display(), e))?;
+    Ok(Value::Nil)
+}
+
+pub fn eval_file_exists(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // file-exists? path, which is true for directories too
+    check_args("file-exists?", list, 1)?;
+    let path = eval_path("file-exists?", &list[1], env)?;
+
+    match path.try_exists() {
+        Ok(true) => Ok(Value::T),
+        Ok(false) => Ok(Value::Nil),
+        Err(e) => Err(format!("Could not check {}: {}", path.display(), e)),
+    }
+}
+
+pub fn eval_directory_list(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // directory-list path, which returns the names of the entries in the directory, sorted
+    check_args("directory-list", list, 1)?;
+    let path = eval_path("directory-list", &list[1], env)?;
+
+    let error = |e: std::io::Error| format!("Could not list {}: {}", path.display(), e);
+    let mut names = vec![];
+    for entry in fs::read_dir(&path).map_err(error)? {
+        names.push(
+            entry
+                .map_err(error)?
+                .file_name()
+                .to_string_lossy()
+                .into_owned(),
+        );
+    }
+    names.sort();
+
+    interpreter::allocate(names.len())?;
+    Ok(Value::List(
+        names
+            .into_iter()
+            .map(|name| Value::String(name.into()))
+            .collect(),
+    ))
+}
+
+pub fn eval_make_directory(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // make-directory path, which also makes the parent directories that don't exist yet
+    check_args("make-directory", list, 1)?;
+    let path = eval_path("make-directory", &list[1], env)?;
+
+    fs::create_dir_all(&path)
+        .map_err(|e| format!("Could not make directory {}: {}", path.display(), e))?;
+    Ok(Value::Nil)
+}
+
+pub fn eval_delete_file(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // delete-file path, which can also delete an empty directory
+    check_args("delete-file", list, 1)?;
+    let path = eval_path("delete-file", &list[1], env)?;
+
+    let result = if path.is_dir() {
+        fs::remove_dir(&path)
+    } else {
+        fs::remove_file(&path)
+    };
+    result.map_err(|e| format!("Could not delete {}: {}", path.display(), e))?;
+    Ok(Value::Nil)
+}
+
+pub fn eval_rename_file(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // rename-file from to, which replaces the file at to if there is one
+    check_args("rename-file", list, 2)?;
+    let from = eval_path("rename-file", &list[1], env)?;
+    let to = eval_path("rename-file", &list[2], env)?;
+
+    fs::rename(&from, &to).map_err(|e| {
+        format!(
+            "Could not rename {} to {}: {}",
+            from.display(),
+            to.display(),
+            e
+        )
+    })?;
+    Ok(Value::Nil)
+}
+
+pub fn eval_file_metadata(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // file-metadata path, which returns (:type :file/:directory/:other :size bytes :modified seconds
+    // :readonly t/nil), with the time it was last modified in seconds since the unix epoch
+    check_args("file-metadata", list, 1)?;
+    let path = eval_path("file-metadata", &list[1], env)?;
+
+    let metadata = fs::metadata(&path)
+        .map_err(|e| format!("Could not get metadata of {}: {}", path.display(), e))?;
+    let file_type = if metadata.is_file() {
+        "file"
+    } else if metadata.is_dir() {
+        "directory"
+    } else {
+        "other"
+    };
+    let modified = match metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+    {
+        Some(elapsed) => Value::Number(elapsed.as_secs_f64()),
+        None => Value::Nil,
+    };
+    let readonly = if metadata.permissions().readonly() {
+        Value::T
+    } else {
+        Value::Nil
+    };
+
+    let keyword = |name: &str| Value::Keyword(Symbol::new(name));
+    interpreter::allocate(8)?;
+    Ok(Value::List(
+        vec![
+            keyword("type"),
+            keyword(file_type),
+            keyword("size"),
+            Value::Number(metadata.len() as f64),
+            keyword("modified"),
+            modified,
+            keyword("readonly"),
+            readonly,
+        ]
+        .into(),
+    ))
+}
+
+pub fn eval_path_join(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // path-join parts..., where an absolute part replaces everything before it
+    if list.len() < 2 {
+        return Err(String::from("\"path-join\" requires at least 1 argument"));
+    }
+
+    let mut path = PathBuf::new();
+    for part in &list[1..] {
+        path.push(eval_string("path-join", part, env)?.as_ref());
+    }
+    path_to_value(&path)
+}
+
+pub fn eval_path_normalize(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // path-normalize path, which removes . and .. without looking at the filesystem
+    check_args("path-normalize", list, 1)?;
+    let path = eval_string("path-normalize", &list[1], env)?;
+
+    let normalized = normalize(Path::new(path.as_ref()));
+    if normalized.as_os_str().is_empty() {
+        return Ok(Value::String(".".into()));
+    }
+    path_to_value(&normalized)
+}
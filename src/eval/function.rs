@@ -1,15 +1,20 @@
-use super::eval_value;
-use crate::{env::*, parse::*};
+use super::{
+    eval_value,
+    resolve::{resolve_body, unresolve},
+    syntax::expand_syntax,
+};
+use crate::{env::*, interpreter, parse::*, symbol::Symbol};
 use std::{cell::RefCell, rc::Rc};
 
-pub fn eval_fun_definition(list: &[Value], _env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
-    let params = match &list[1] {
+/// Parses a lambda's parameter list, e.g. (a b &optional c &rest d &key e).
+pub fn parse_params(value: &Value) -> Result<LambdaParams, String> {
+    let params = match value {
         Value::List(list) => {
             let mut params = Vec::new();
-            for param in list {
+            for param in list.iter() {
                 match param {
                     Value::Symbol(s) => params.push(s.clone()),
-                    _ => return Err(format!("Invalid parameter name")),
+                    _ => return Err(String::from("Invalid parameter name")),
                 }
             }
             params
@@ -21,25 +26,21 @@ pub fn eval_fun_definition(list: &[Value], _env: &mut Rc<RefCell<Env>>) -> Resul
         }
     };
 
-    let body = Rc::from(list[2].clone());
-
     let mut markers: Vec<usize> = vec![];
-    for i in 0..params.len() {
-        let param = params[i].clone();
-        if param == "&optional" || param == "&key" || param == "&rest" {
+    for (i, param) in params.iter().enumerate() {
+        if matches!(*param, Symbol::OPTIONAL | Symbol::KEY | Symbol::REST) {
             markers.push(i);
         }
     }
     markers.push(params.len()); // add a placeholder marker at the end, this is used later on
     markers.sort();
 
-    let mut required: Vec<String> = vec![];
-    let mut optional: Vec<String> = vec![];
-    let mut keyword: Vec<String> = vec![];
-    let mut rest: Option<String> = None;
+    let mut required: Vec<Symbol> = vec![];
+    let mut optional: Vec<Symbol> = vec![];
+    let mut keyword: Vec<Symbol> = vec![];
+    let mut rest: Option<Symbol> = None;
 
-    println!("{:?}", markers);
-    if markers.len() > 0 {
+    if !markers.is_empty() {
         let mut last_marker_index: i32 = -1;
         for marker in &markers {
             for i in last_marker_index..(*marker as i32) {
@@ -54,10 +55,10 @@ pub fn eval_fun_definition(list: &[Value], _env: &mut Rc<RefCell<Env>>) -> Resul
                         continue;
                     }
 
-                    match params[last_marker_index as usize].as_str() {
-                        "&optional" => optional.push(params[i as usize].clone()),
-                        "&key" => keyword.push(params[i as usize].clone()),
-                        "&rest" => {
+                    match params[last_marker_index as usize] {
+                        Symbol::OPTIONAL => optional.push(params[i as usize].clone()),
+                        Symbol::KEY => keyword.push(params[i as usize].clone()),
+                        Symbol::REST => {
                             if rest.is_some() {
                                 return Err(String::from("There can be only 1 rest parameter"));
                             } else {
@@ -76,15 +77,30 @@ pub fn eval_fun_definition(list: &[Value], _env: &mut Rc<RefCell<Env>>) -> Resul
         required = params;
     }
 
+    Ok(LambdaParams {
+        required,
+        optional,
+        keyword,
+        rest,
+    })
+}
+
+pub fn eval_fun_definition(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    if list.len() < 3 {
+        return Err(String::from(
+            "\"lambda\" requires a parameter list and a body",
+        ));
+    }
+
+    let params = parse_params(&list[1])?;
+    // local variables in the body are resolved once, when the lambda is defined
+    let body = Rc::from(resolve_body(&params, &list[2], env));
+
     Ok(Value::Lambda {
-        params: LambdaParams {
-            required,
-            optional,
-            keyword,
-            rest,
-        },
+        params: Rc::new(params),
         body,
         is_macro: false,
+        env: CapturedEnv(env.clone()),
     })
 }
 
@@ -93,155 +109,102 @@ pub fn eval_fun_call(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value
 
     let fun = match first {
         Value::Symbol(s) => {
-            let lamdba = env.borrow_mut().get(&s);
+            let lamdba = env.borrow_mut().get(s.clone());
             if lamdba.is_none() {
                 return Err(format!("Unbound symbol: {}", s));
             }
 
             lamdba.unwrap()
         }
-        Value::Lambda { .. } => first.clone(),
+        Value::Lambda { .. } | Value::Syntax { .. } | Value::Native(_) => first.clone(),
+
+        Value::Closure(_) => {
+            return Err(String::from(
+                "Compiled lambdas can only be called by the VM",
+            ))
+        }
 
         _ => return Err(String::from("First parameter is not a function")),
     };
 
     match fun {
+        Value::Native(function) => {
+            let mut args: Vec<Value> = vec![];
+            for value in &list[1..] {
+                args.push(eval_value(value, env)?);
+            }
+            function.call(&args)
+        }
+        // syntax-rules macros are expanded and the resulting code is evaluated in the calling code's environment
+        Value::Syntax {
+            literals,
+            rules,
+            env: definition,
+        } => {
+            let scope = env.clone();
+            let is_local = |name| scope.borrow().is_local(name);
+            let list: Vec<Value> = list.iter().map(unresolve).collect();
+            let expanded = expand_syntax(&literals, &rules, &definition, &list, &is_local)?;
+            eval_value(&expanded, env)
+        }
         Value::Lambda {
             params,
             body,
             is_macro,
+            env: captured,
         } => {
-            let mut new_env = Rc::new(RefCell::new(Env::extend(env.clone())));
-            // first set all parameters to nil (or empty list in the case of the rest parameter)
-            for name in params.required.iter() {
-                new_env.borrow_mut().set(name, Value::Nil);
-            }
-            for name in params.optional.iter() {
-                new_env.borrow_mut().set(name, Value::Nil);
-            }
-            for name in params.keyword.iter() {
-                new_env.borrow_mut().set(name, Value::Nil);
-            }
-            if params.rest.is_some() {
-                new_env
-                    .borrow_mut()
-                    .set(&params.rest.clone().unwrap(), Value::List(vec![]));
-            }
-
-            // set required and optional parameter values
-            let mut last_index = 1;
-            let mut last_required = 0;
-            for (i, param_val) in list[1..].iter().enumerate() {
-                let val = eval_value(&param_val, env)?;
-
-                if i < params.required.len() {
-                    let name = &params.required[i];
-                    new_env.borrow_mut().set(name, val);
-                    last_required = i;
-                } else if i < params.required.len() + params.optional.len() {
-                    let name = &params.optional[i - last_required - 1];
-                    new_env.borrow_mut().set(name, val);
-                } else {
-                    break;
-                }
-
-                last_index = i;
-            }
-
-            // if there's more parameters after required and optional ones, set the rest parameter to a list of them
-            if list.len() > last_index + 2 {
-                if params.rest.is_some() {
-                    let mut values: Vec<Value> = vec![];
-                    for value in list[(last_index + 2)..].iter() {
-                        values.push(eval_value(value, env)?);
-                    }
-                    new_env
-                        .borrow_mut()
-                        .set(&params.rest.clone().unwrap(), Value::List(values));
-                }
-            }
-
-            for (i, param_val) in list[(last_index + 2)..].iter().enumerate() {
-                match eval_value(&param_val, env)? {
-                    Value::Symbol(s) => {
-                        // if it starts with a :, it's a keyword
-                        if s.starts_with(":") {
-                            let name = &String::from(&s[1..]);
-                            // if there's a parameter after the keyword and there's a keyword parameter with that name
-                            if last_index + 2 + i + 1 < list.len() && params.keyword.contains(name)
-                            {
-                                let value = list[last_index + 2 + i + 1].clone();
-                                new_env.borrow_mut().set(name, eval_value(&value, env)?);
-                            }
-                        }
-                    }
-
-                    _ => {}
-                }
-            }
-
-            let value = eval_value(body.as_ref(), &mut new_env)?;
-
             if is_macro {
-                // if it's a macro, evaluate the code it returns (with the calling code's environment)
-                return eval_value(&value, env);
+                // if it's a macro, its arguments are passed as is and the code it returns is evaluated (with the calling code's environment)
+                let args: Vec<Value> = list[1..].iter().map(unresolve).collect();
+                let value = apply_lambda(&params, &body, &captured, &args)?;
+                eval_value(&value, env)
             } else {
-                // if it's a function, just return its value
-                return Ok(value);
+                // if it's a function, evaluate its arguments and just return its value
+                let mut args: Vec<Value> = vec![];
+                for value in &list[1..] {
+                    args.push(eval_value(value, env)?);
+                }
+                apply_lambda(&params, &body, &captured, &args)
             }
         }
         _ => unreachable!(),
     }
 }
 
+/// Binds the (already evaluated) arguments to the lambda's parameters in a new environment, extending the one
+/// the lambda was defined in, and evaluates its body there.
+pub fn apply_lambda(
+    params: &LambdaParams,
+    body: &Value,
+    env: &CapturedEnv,
+    args: &[Value],
+) -> Result<Value, String> {
+    // the rest parameter gets a new list with the arguments that are left over
+    if params.rest.is_some() {
+        let positional = params.required.len() + params.optional.len();
+        interpreter::allocate(args.len().saturating_sub(positional))?;
+    }
+
+    let mut new_env = Env::extend(&env.0);
+    for (name, value) in params.names().into_iter().zip(params.bind(args)) {
+        new_env.borrow_mut().set(name, value);
+    }
+
+    interpreter::nested(|| eval_value(body, &mut new_env))
+}
+
 pub fn eval_macro_definition(list: &[Value], _env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
     let function = eval_fun_definition(list, _env);
     match function {
-        Ok(Value::Lambda { params, body, .. }) => Ok(Value::Lambda {
+        Ok(Value::Lambda {
+            params, body, env, ..
+        }) => Ok(Value::Lambda {
             params,
             body,
             is_macro: true,
+            env,
         }),
 
         _ => function,
     }
 }
-
-pub fn eval_macro_expand(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
-    if list.len() != 2 {
-        return Err(String::from("\"macroexpand\" requires 1 argument"));
-    }
-
-    let value = list[1].clone();
-
-    match eval_value(&value, env)? {
-        Value::List(list) => {
-            if list.len() > 1 {
-                match eval_value(&list[0], env)? {
-                    Value::Lambda { params, body, .. } => {
-                        // macroexpand is really the same as calling the macro as a function (instead of a macro)
-
-                        let mut new_value: Vec<Value> = vec![];
-                        new_value.push(Value::Lambda {
-                            params,
-                            body,
-                            is_macro: false,
-                        });
-
-                        for item in &list[1..] {
-                            new_value.push(item.clone());
-                        }
-
-                        eval_value(&Value::List(new_value), env)
-                    }
-
-                    _ => Ok(value),
-                }
-            } else {
-                Ok(value)
-            }
-        }
-
-        _ => Ok(value),
-    }
-}
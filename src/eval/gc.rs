@@ -0,0 +1,32 @@
+use crate::{env::*, gc, parse::*, symbol::Symbol};
+use std::{cell::RefCell, rc::Rc};
+
+pub fn eval_gc(list: &[Value], _env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    if list.len() != 1 {
+        return Err(String::from("\"gc\" doesn't take any arguments"));
+    }
+
+    // returns the number of environments that were freed
+    Ok(Value::Number(gc::collect() as f64))
+}
+
+pub fn eval_gc_stats(list: &[Value], _env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    if list.len() != 1 {
+        return Err(String::from("\"gc-stats\" doesn't take any arguments"));
+    }
+
+    // a property list, e.g. (:allocated 10 :live 2 :collections 1 :freed 3)
+    let stats = gc::stats();
+    let mut plist = vec![];
+    for (name, n) in [
+        ("allocated", stats.allocated),
+        ("live", stats.live),
+        ("collections", stats.collections),
+        ("freed", stats.freed),
+    ] {
+        plist.push(Value::Keyword(Symbol::new(name)));
+        plist.push(Value::Number(n as f64));
+    }
+
+    Ok(Value::List(plist.into()))
+}
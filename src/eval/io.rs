@@ -0,0 +1,299 @@
+use super::{eval_value, function::eval_fun_call, reader::check_strings};
+use crate::{
+    env::*,
+    foreign::Foreign,
+    interpreter,
+    parse::*,
+    port::{self, port_of, Current, Port},
+    pretty::{pretty_print, PrintOptions},
+    symbol::Symbol,
+    util::{display_to_string, value_to_string},
+};
+use std::{cell::RefCell, rc::Rc};
+
+// evaluates the optional port argument at `index`, or returns the current port if there isn't one
+fn port_arg(
+    name: &str,
+    list: &[Value],
+    index: usize,
+    which: Current,
+    env: &mut Rc<RefCell<Env>>,
+) -> Result<Rc<Foreign>, String> {
+    let Some(value) = list.get(index) else {
+        return Ok(port::current(which));
+    };
+
+    match eval_value(value, env)? {
+        Value::Foreign(foreign) if foreign.is::<Port>() => Ok(foreign),
+        _ => Err(format!("Port argument to \"{}\" must be a port", name)),
+    }
+}
+
+fn write_to(port: &Foreign, s: &str) -> Result<Value, String> {
+    // port_arg only returns ports
+    port.downcast_ref::<Port>().unwrap().write_str(s)?;
+    Ok(Value::Nil)
+}
+
+fn read_from<R>(
+    port: &Foreign,
+    read: impl FnOnce(&Port) -> Result<R, String>,
+) -> Result<R, String> {
+    read(port.downcast_ref::<Port>().unwrap())
+}
+
+pub fn eval_print(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // print and println write their arguments to the current output port separated by spaces, with strings
+    // written without quotes
+    let mut output = vec![];
+    for arg in &list[1..] {
+        output.push(match eval_value(arg, env)? {
+            Value::String(s) => s.to_string(),
+            value => value_to_string(&value),
+        });
+    }
+
+    let mut output = output.join(" ");
+    if list[0] == Value::Symbol(Symbol::PRINTLN) {
+        output.push('\n');
+    }
+
+    write_to(&port::current(Current::Output), &output)
+}
+
+pub fn eval_write(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // write value [port] prints it so it can be read back, display value [port] prints it for people (see
+    // util)
+    let name = if list[0] == Value::Symbol(Symbol::WRITE) {
+        "write"
+    } else {
+        "display"
+    };
+    if list.len() != 2 && list.len() != 3 {
+        return Err(format!("\"{}\" requires 1 or 2 arguments", name));
+    }
+
+    let value = eval_value(&list[1], env)?;
+    let port = port_arg(name, list, 2, Current::Output, env)?;
+    let output = if list[0] == Value::Symbol(Symbol::WRITE) {
+        value_to_string(&value)
+    } else {
+        display_to_string(&value)
+    };
+    write_to(&port, &output)
+}
+
+pub fn eval_newline(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // newline [port]
+    if list.len() > 2 {
+        return Err(String::from("\"newline\" requires 0 or 1 arguments"));
+    }
+
+    let port = port_arg("newline", list, 1, Current::Output, env)?;
+    write_to(&port, "\n")
+}
+
+pub fn eval_pprint(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // pprint value [port], which writes the value pretty printed with the options from the printer variables
+    if list.len() != 2 && list.len() != 3 {
+        return Err(String::from("\"pprint\" requires 1 or 2 arguments"));
+    }
+
+    let value = eval_value(&list[1], env)?;
+    let port = port_arg("pprint", list, 2, Current::Output, env)?;
+    let mut output = pretty_print(&value, &PrintOptions::from_env(&env.borrow()));
+    output.push('\n');
+    write_to(&port, &output)
+}
+
+pub fn eval_read_line(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // read-line [port], which returns the line without its line ending, or the end of file object at the end
+    // of the input
+    if list.len() > 2 {
+        return Err(String::from("\"read-line\" requires 0 or 1 arguments"));
+    }
+
+    let port = port_arg("read-line", list, 1, Current::Input, env)?;
+    match read_from(&port, Port::read_line)? {
+        Some(line) => {
+            interpreter::check_string_length(line.chars().count())?;
+            Ok(Value::String(line.into()))
+        }
+        None => Ok(port::eof()),
+    }
+}
+
+pub fn eval_read_char(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // read-char [port], which returns the character as a string, or the end of file object at the end of the
+    // input
+    if list.len() > 2 {
+        return Err(String::from("\"read-char\" requires 0 or 1 arguments"));
+    }
+
+    let port = port_arg("read-char", list, 1, Current::Input, env)?;
+    match read_from(&port, Port::read_char)? {
+        Some(c) => Ok(Value::String(c.to_string().into())),
+        None => Ok(port::eof()),
+    }
+}
+
+pub fn eval_read(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // read [port], which reads the next form (reading as many lines as it takes), or returns the end of file
+    // object at the end of the input
+    if list.len() > 2 {
+        return Err(String::from("\"read\" requires 0 or 1 arguments"));
+    }
+
+    let port = port_arg("read", list, 1, Current::Input, env)?;
+    match read_from(&port, Port::read)? {
+        Some(form) => {
+            check_strings(&form)?;
+            Ok(form)
+        }
+        None => Ok(port::eof()),
+    }
+}
+
+pub fn eval_eof_object_p(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // eof-object? value, which is t for what the read builtins return at the end of the input
+    if list.len() != 2 {
+        return Err(String::from("\"eof-object?\" requires 1 argument"));
+    }
+
+    if port::is_eof(&eval_value(&list[1], env)?) {
+        Ok(Value::T)
+    } else {
+        Ok(Value::Nil)
+    }
+}
+
+pub fn eval_open_file(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // open-input-file path, open-output-file path [:append t/nil]
+    let input = list[0] == Value::Symbol(Symbol::OPEN_INPUT_FILE);
+    let name = if input {
+        "open-input-file"
+    } else {
+        "open-output-file"
+    };
+    let has_append = !input && list.len() == 4 && list[2] == Value::Keyword(Symbol::new("append"));
+    if input && list.len() != 2 {
+        return Err(String::from("\"open-input-file\" requires 1 argument"));
+    }
+    if !input && list.len() != 2 && !has_append {
+        return Err(String::from(
+            "\"open-output-file\" requires a path and optionally :append",
+        ));
+    }
+
+    let path = match eval_value(&list[1], env)? {
+        Value::String(s) => s,
+        _ => return Err(format!("Argument to \"{}\" must be a string", name)),
+    };
+    let append = has_append && eval_value(&list[3], env)? != Value::Nil;
+    let path = env.borrow().capabilities().resolve_path(&path)?;
+
+    let port = if input {
+        Port::open_input_file(&path)?
+    } else {
+        Port::open_output_file(&path, append)?
+    };
+    Ok(Value::Foreign(port))
+}
+
+pub fn eval_close_port(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    if list.len() != 2 {
+        return Err(String::from("\"close-port\" requires 1 argument"));
+    }
+
+    match port_of(&eval_value(&list[1], env)?) {
+        Some(port) => port.close(),
+        None => return Err(String::from("Argument to \"close-port\" must be a port")),
+    }
+    Ok(Value::Nil)
+}
+
+pub fn eval_current_port(list: &[Value], _env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // current-input-port, current-output-port or current-error-port
+    let which = match &list[0] {
+        Value::Symbol(Symbol::CURRENT_INPUT_PORT) => Current::Input,
+        Value::Symbol(Symbol::CURRENT_OUTPUT_PORT) => Current::Output,
+        _ => Current::Error,
+    };
+    if list.len() != 1 {
+        return Err(format!(
+            "\"{}\" doesn't take any arguments",
+            value_to_string(&list[0])
+        ));
+    }
+
+    Ok(Value::Foreign(port::current(which)))
+}
+
+pub fn eval_with_port(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // with-output-to-port port thunk, with-input-from-port port thunk, which call the thunk with the current
+    // output or input port replaced by the port
+    let (name, which) = if list[0] == Value::Symbol(Symbol::WITH_OUTPUT_TO_PORT) {
+        ("with-output-to-port", Current::Output)
+    } else {
+        ("with-input-from-port", Current::Input)
+    };
+    if list.len() != 3 {
+        return Err(format!("\"{}\" requires 2 arguments", name));
+    }
+
+    let port = match eval_value(&list[1], env)? {
+        Value::Foreign(foreign) if foreign.is::<Port>() => foreign,
+        _ => return Err(format!("First argument to \"{}\" must be a port", name)),
+    };
+    let thunk = eval_value(&list[2], env)?;
+    port::with_current(which, port, || eval_fun_call(&[thunk], env))
+}
+
+pub fn eval_with_output_to_string(
+    list: &[Value],
+    env: &mut Rc<RefCell<Env>>,
+) -> Result<Value, String> {
+    // with-output-to-string thunk, which calls the thunk and returns everything it wrote to the current
+    // output port
+    if list.len() != 2 {
+        return Err(String::from(
+            "\"with-output-to-string\" requires 1 argument",
+        ));
+    }
+
+    let thunk = eval_value(&list[1], env)?;
+    let port = Port::output_string();
+    port::with_current(Current::Output, port.clone(), || {
+        eval_fun_call(&[thunk], env)
+    })?;
+
+    let text = port
+        .downcast_ref::<Port>()
+        .and_then(Port::text)
+        .unwrap_or_default();
+    interpreter::check_string_length(text.chars().count())?;
+    Ok(Value::String(text.into()))
+}
+
+pub fn eval_call_with_input_string(
+    list: &[Value],
+    env: &mut Rc<RefCell<Env>>,
+) -> Result<Value, String> {
+    // call-with-input-string string function, which calls the function with a port that reads the string
+    if list.len() != 3 {
+        return Err(String::from(
+            "\"call-with-input-string\" requires 2 arguments",
+        ));
+    }
+
+    let text = match eval_value(&list[1], env)? {
+        Value::String(s) => s,
+        _ => {
+            return Err(String::from(
+                "First argument to \"call-with-input-string\" must be a string",
+            ))
+        }
+    };
+    let function = eval_value(&list[2], env)?;
+    eval_fun_call(&[function, Value::Foreign(Port::input_string(&text))], env)
+}
@@ -0,0 +1,96 @@
+use super::eval_value;
+use crate::{
+    env::*,
+    json::{self, Keys},
+    parse::*,
+    symbol::Symbol,
+};
+use std::{cell::RefCell, rc::Rc};
+
+// evaluates the :keyword value options after the first argument, calling `option` with each of them
+fn eval_options(
+    name: &str,
+    list: &[Value],
+    env: &mut Rc<RefCell<Env>>,
+    mut option: impl FnMut(Symbol, Value) -> Result<(), String>,
+) -> Result<(), String> {
+    let options = &list[2..];
+    if !options.len().is_multiple_of(2) {
+        return Err(format!("Missing value for an option of \"{}\"", name));
+    }
+
+    for pair in options.chunks(2) {
+        let key = match &pair[0] {
+            Value::Keyword(k) => k.clone(),
+            _ => return Err(format!("Options of \"{}\" must be keywords", name)),
+        };
+        option(key, eval_value(&pair[1], env)?)?;
+    }
+    Ok(())
+}
+
+fn keys_option(name: &str, value: &Value) -> Result<Keys, String> {
+    match value {
+        Value::Keyword(k) if k.as_str() == "keyword" => Ok(Keys::Keyword),
+        Value::Keyword(k) if k.as_str() == "string" => Ok(Keys::String),
+        _ => Err(format!(
+            "Option :keys of \"{}\" must be :keyword or :string",
+            name
+        )),
+    }
+}
+
+pub fn eval_json_parse(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // json-parse string [:keys :keyword/:string]
+    if list.len() < 2 {
+        return Err(String::from("\"json-parse\" requires at least 1 argument"));
+    }
+
+    let text = match eval_value(&list[1], env)? {
+        Value::String(s) => s,
+        _ => {
+            return Err(String::from(
+                "First argument to \"json-parse\" must be a string",
+            ))
+        }
+    };
+
+    let mut keys = Keys::default();
+    eval_options("json-parse", list, env, |option, value| {
+        match option.as_str() {
+            "keys" => keys = keys_option("json-parse", &value)?,
+            _ => return Err(format!("Unknown option for \"json-parse\": :{}", option)),
+        }
+        Ok(())
+    })?;
+
+    json::parse(&text, keys)
+}
+
+pub fn eval_json_stringify(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // json-stringify value [:pretty t/nil] [:keys :keyword/:string]
+    if list.len() < 2 {
+        return Err(String::from(
+            "\"json-stringify\" requires at least 1 argument",
+        ));
+    }
+
+    let value = eval_value(&list[1], env)?;
+    let mut keys = Keys::default();
+    let mut pretty = false;
+    eval_options("json-stringify", list, env, |option, value| {
+        match option.as_str() {
+            "keys" => keys = keys_option("json-stringify", &value)?,
+            "pretty" => pretty = value != Value::Nil,
+            _ => {
+                return Err(format!(
+                    "Unknown option for \"json-stringify\": :{}",
+                    option
+                ))
+            }
+        }
+        Ok(())
+    })?;
+
+    Ok(Value::String(json::stringify(&value, keys, pretty)?.into()))
+}
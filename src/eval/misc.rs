@@ -1,14 +1,9 @@
-use super::eval_value;
-use crate::{env::*, parse::*};
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use super::{eval_toplevel, eval_value, function::eval_fun_definition, module::root};
+use crate::{env::*, parse::*, symbol::Symbol};
+use std::{cell::RefCell, rc::Rc};
 
-pub fn eval_symbol(symbol: &String, env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
-    // if it's a keyword, just return it (it doesn't get evaluated by looking up its value)
-    if symbol.starts_with(":") {
-        return Ok(Value::Symbol(symbol.clone()));
-    }
-
-    let val = env.borrow().get(symbol);
+pub fn eval_symbol(symbol: Symbol, env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    let val = env.borrow().get(symbol.clone());
 
     if val.is_none() {
         return Err(format!("Unbound symbol: {}", symbol));
@@ -20,23 +15,23 @@ pub fn eval_symbol(symbol: &String, env: &mut Rc<RefCell<Env>>) -> Result<Value,
 pub fn eval_if(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
     // if cond then else
     if list.len() != 3 && list.len() != 4 {
-        return Err(format!("\"if\" requires 2 or 3 arguments"));
+        return Err(String::from("\"if\" requires 2 or 3 arguments"));
     }
 
     let cond_obj = eval_value(&list[1], env)?;
     let cond = match cond_obj {
         Value::T => true,
         Value::Nil => false,
-        _ => return Err(format!("Condition must be a bool")),
+        _ => return Err(String::from("Condition must be a bool")),
     };
 
     if cond {
-        return eval_value(&list[2], env);
+        eval_value(&list[2], env)
     } else {
         if list.len() == 4 {
-            return eval_value(&list[3], env);
+            eval_value(&list[3], env)
         } else {
-            return Err(format!("No else branch found"));
+            Err(String::from("No else branch found"))
         }
     }
 }
@@ -44,65 +39,199 @@ pub fn eval_if(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, Stri
 pub fn eval_def(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
     // def symbol value
     if list.len() != 3 {
-        return Err(format!("\"def\" requires 2 arguments"));
+        return Err(String::from("\"def\" requires 2 arguments"));
     }
 
     let sym = match &list[1] {
         Value::Symbol(s) => s.clone(),
-        _ => return Err(format!("First parameter to \"def\" must be a symbol")),
+        _ => return Err(String::from("First parameter to \"def\" must be a symbol")),
     };
     let val = eval_value(&list[2], env)?;
-    env.borrow_mut().set(&sym, val.clone());
+    env.borrow_mut().set(sym, val.clone());
 
     // return the value that was defined
     Ok(val)
 }
 
-pub fn eval_let(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
-    let bindings = match &list[1] {
+/// Checks the shape of a let-style binding list, i.e. ((name value) ...), and returns the
+/// bindings in declaration order without evaluating them.
+fn parse_let_bindings<'a>(
+    name: &str,
+    list: &'a [Value],
+) -> Result<Vec<(Symbol, &'a Value)>, String> {
+    if list.len() < 2 {
+        return Err(format!("\"{}\" requires a list of bindings", name));
+    }
+
+    match &list[1] {
         Value::List(list) => {
-            let mut bindings = HashMap::new();
-            for binding in list {
+            let mut bindings = vec![];
+            for binding in list.iter() {
                 match binding {
-                    Value::List(l) => {
-                        if l.len() == 2 {
-                            match &l[0] {
-                                Value::Symbol(s) => {
-                                    bindings.insert(s, eval_value(&l[1], env)?);
-                                }
-                                _ => {
-                                    return Err(format!(
-                                        "The first parameter in each binding must be a symbol"
-                                    ))
-                                }
-                            }
-                        } else {
-                            return Err(format!("Bindings need to be of the form (name value)"));
+                    Value::List(l) if l.len() == 2 => match &l[0] {
+                        Value::Symbol(s) => bindings.push((s.clone(), &l[1])),
+                        _ => {
+                            return Err(String::from(
+                                "The first parameter in each binding must be a symbol",
+                            ))
                         }
-                    }
-                    _ => return Err(format!("Bindings need to be of the form (name value)")),
+                    },
+                    _ => return Err(String::from("Bindings need to be of the form (name value)")),
                 }
             }
 
-            bindings
+            Ok(bindings)
         }
+        Value::Nil => Ok(vec![]),
+        _ => Err(format!(
+            "First parameter to \"{}\" must be a list of bindings",
+            name
+        )),
+    }
+}
+
+/// Evaluates each body form in order and returns the value of the last one (or nil if there are none).
+pub fn eval_body(bodies: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    let mut last_value: Value = Value::Nil;
+    for value in bodies {
+        last_value = eval_value(value, env)?;
+    }
+    Ok(last_value)
+}
+
+pub fn eval_let(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // all values are evaluated in the outer environment, in the order they were declared
+    let mut values = vec![];
+    for (name, value) in parse_let_bindings("let", list)? {
+        values.push((name, eval_value(value, env)?));
+    }
+
+    let mut new_env = Env::extend(env);
+    for (name, value) in values {
+        new_env.borrow_mut().set(name, value);
+    }
+
+    eval_body(&list[2..], &mut new_env)
+}
+
+pub fn eval_let_star(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // each value is evaluated in the new environment, so it can see all the bindings before it
+    let mut new_env = Env::extend(env);
+    for (name, value) in parse_let_bindings("let*", list)? {
+        let value = eval_value(value, &mut new_env)?;
+        new_env.borrow_mut().set(name, value);
+    }
+
+    eval_body(&list[2..], &mut new_env)
+}
+
+pub fn eval_letrec(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    let bindings = parse_let_bindings("letrec", list)?;
+
+    // first bind every name to nil, so that all values (usually lambdas) can refer to each other
+    let mut new_env = Env::extend(env);
+    for (name, _) in bindings.iter() {
+        new_env.borrow_mut().set(name.clone(), Value::Nil);
+    }
+
+    for (name, value) in bindings {
+        let value = eval_value(value, &mut new_env)?;
+        new_env.borrow_mut().set(name, value);
+    }
+
+    eval_body(&list[2..], &mut new_env)
+}
+
+pub fn eval_labels(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // labels ((name (params) body) ...) body...
+    if list.len() < 2 {
+        return Err(String::from(
+            "\"labels\" requires a list of function definitions",
+        ));
+    }
+
+    let definitions = match &list[1] {
+        Value::List(l) => &l[..],
+        Value::Nil => &[],
         _ => {
             return Err(String::from(
-                "First parameter to \"lambda\" must be a list of parameter names",
+                "First parameter to \"labels\" must be a list of function definitions",
             ))
         }
     };
 
-    let bodies: Vec<Value> = list[2..].to_vec();
+    let mut new_env = Env::extend(env);
+    for definition in definitions {
+        match definition {
+            Value::List(l) if l.len() == 3 => {
+                let name = match &l[0] {
+                    Value::Symbol(s) => s.clone(),
+                    _ => return Err(String::from("Function names in \"labels\" must be symbols")),
+                };
 
-    let mut new_env = Rc::new(RefCell::new(Env::extend(env.clone())));
-    for (key, value) in bindings.iter() {
-        new_env.borrow_mut().set(key, value.clone());
+                let lambda = eval_fun_definition(
+                    &[Value::Symbol(Symbol::LAMBDA), l[1].clone(), l[2].clone()],
+                    &mut new_env,
+                )?;
+                new_env.borrow_mut().set(name, lambda);
+            }
+            _ => {
+                return Err(String::from(
+                    "Function definitions need to be of the form (name (params) body)",
+                ))
+            }
+        }
     }
 
-    let mut last_value: Value = Value::Nil;
-    for value in bodies {
-        last_value = eval_value(&value, &mut new_env)?;
+    eval_body(&list[2..], &mut new_env)
+}
+
+pub fn eval_eval(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // eval form [env], which evaluates a form as a top-level form in env, or the root environment by default
+    if list.len() != 2 && list.len() != 3 {
+        return Err(String::from("\"eval\" requires 1 or 2 arguments"));
     }
-    Ok(last_value)
+
+    let form = eval_value(&list[1], env)?;
+    let mut target = match list.get(2) {
+        Some(value) => match eval_value(value, env)? {
+            Value::Env(target) => target.0,
+            _ => {
+                return Err(String::from(
+                    "Second argument to \"eval\" must be an environment",
+                ))
+            }
+        },
+        None => root(env),
+    };
+    eval_toplevel(&form, &mut target)
+}
+
+pub fn eval_current_env(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    if list.len() != 1 {
+        return Err(String::from("\"current-env\" doesn't take any arguments"));
+    }
+
+    Ok(Value::Env(CapturedEnv(env.clone())))
+}
+
+pub fn eval_make_env(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // make-env [parent], which makes an environment whose definitions don't affect its parent, or a new root
+    // environment with just the prelude (and the same capabilities) without one
+    let new_env = match list.len() {
+        1 => env
+            .borrow()
+            .fresh_root()
+            .ok_or_else(|| String::from("The environment was already freed"))?,
+        2 => match eval_value(&list[1], env)? {
+            Value::Env(parent) => Env::extend(&parent.0),
+            _ => {
+                return Err(String::from(
+                    "Argument to \"make-env\" must be an environment",
+                ))
+            }
+        },
+        _ => return Err(String::from("\"make-env\" requires 0 or 1 arguments")),
+    };
+    Ok(Value::Env(CapturedEnv(new_env)))
 }
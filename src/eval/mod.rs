@@ -1,55 +1,248 @@
-use crate::{env::*, eval::function::*, eval::misc::*, eval::op::*, eval::quote::*, parse::*};
+use crate::{
+    env::*, eval::expand::*, eval::foreign::*, eval::fs::*, eval::function::*, eval::gc::*,
+    eval::io::*, eval::json::*, eval::misc::*, eval::module::*, eval::op::*, eval::pattern::*,
+    eval::process::*, eval::quote::*, eval::reader::*, eval::string::*, eval::symbol::*,
+    eval::syntax::*, eval::time::*, interpreter, parse::*, sandbox::Module, symbol::Symbol,
+};
 use std::{cell::RefCell, rc::Rc};
 
+mod expand;
+mod foreign;
+mod fs;
 mod function;
+mod gc;
+mod io;
+mod json;
 mod misc;
+mod module;
 mod op;
+mod pattern;
+mod process;
 mod quote;
+mod reader;
+mod resolve;
+mod string;
+mod symbol;
+mod syntax;
+mod time;
+
+/// Returns the module a builtin belongs to, or None if it's a special form (or not a builtin at all).
+fn builtin_module(s: Symbol) -> Option<Module> {
+    match s {
+        Symbol::ADD | Symbol::SUB | Symbol::MUL | Symbol::DIV => Some(Module::Math),
+        Symbol::EQUAL
+        | Symbol::NOT_EQUAL
+        | Symbol::LESS
+        | Symbol::GREATER
+        | Symbol::LESS_EQUAL
+        | Symbol::GREATER_EQUAL
+        | Symbol::AND
+        | Symbol::OR
+        | Symbol::NOT
+        | Symbol::CAR
+        | Symbol::CDR
+        | Symbol::LEN
+        | Symbol::MACROEXPAND
+        | Symbol::MACROEXPAND_1
+        | Symbol::MACROEXPAND_ALL
+        | Symbol::GENSYM
+        | Symbol::SYMBOL_TO_STRING
+        | Symbol::STRING_TO_SYMBOL
+        | Symbol::INTERN
+        | Symbol::GC
+        | Symbol::GC_STATS
+        | Symbol::EQUAL_P
+        | Symbol::SEND
+        | Symbol::FOREIGN_TYPE
+        | Symbol::EVAL
+        | Symbol::CURRENT_ENV
+        | Symbol::MAKE_ENV => Some(Module::Core),
+        Symbol::STRING_LENGTH
+        | Symbol::STRING_APPEND
+        | Symbol::SUBSTRING
+        | Symbol::NUMBER_TO_STRING
+        | Symbol::STRING_TO_NUMBER
+        | Symbol::JSON_PARSE
+        | Symbol::JSON_STRINGIFY
+        | Symbol::READ_FROM_STRING
+        | Symbol::READ_ALL => Some(Module::String),
+        Symbol::READ_LINE
+        | Symbol::READ
+        | Symbol::READ_CHAR
+        | Symbol::EOF_OBJECT_P
+        | Symbol::OPEN_INPUT_FILE
+        | Symbol::CURRENT_INPUT_PORT
+        | Symbol::WITH_INPUT_FROM_PORT
+        | Symbol::CALL_WITH_INPUT_STRING
+        | Symbol::LOAD
+        | Symbol::IMPORT => Some(Module::IoRead),
+        Symbol::PRINT
+        | Symbol::PRINTLN
+        | Symbol::PPRINT
+        | Symbol::WRITE
+        | Symbol::DISPLAY
+        | Symbol::NEWLINE
+        | Symbol::OPEN_OUTPUT_FILE
+        | Symbol::CLOSE_PORT
+        | Symbol::CURRENT_OUTPUT_PORT
+        | Symbol::CURRENT_ERROR_PORT
+        | Symbol::WITH_OUTPUT_TO_PORT
+        | Symbol::WITH_OUTPUT_TO_STRING => Some(Module::IoWrite),
+        Symbol::EXIT | Symbol::GETENV | Symbol::COMMAND_LINE => Some(Module::Process),
+        Symbol::CURRENT_TIME | Symbol::SLEEP => Some(Module::Time),
+        Symbol::READ_FILE
+        | Symbol::WRITE_FILE
+        | Symbol::APPEND_FILE
+        | Symbol::FILE_EXISTS
+        | Symbol::DIRECTORY_LIST
+        | Symbol::MAKE_DIRECTORY
+        | Symbol::DELETE_FILE
+        | Symbol::RENAME_FILE
+        | Symbol::FILE_METADATA
+        | Symbol::PATH_JOIN
+        | Symbol::PATH_NORMALIZE => Some(Module::Fs),
+        _ => None,
+    }
+}
 
 fn eval_list(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
     // handle empty list
-    if list.len() == 0 {
+    if list.is_empty() {
         return Ok(Value::Nil);
     }
 
     let head = &list[0];
+
+    // builtins from modules the environment doesn't have access to are looked up like any other name, so
+    // they're unbound unless they were defined
+    if let Value::Symbol(s) = head {
+        if let Some(module) = builtin_module(s.clone()) {
+            if !env.borrow().capabilities().allows(module) {
+                return eval_fun_call(list, env);
+            }
+        }
+    }
+
     match head {
-        Value::Symbol(s) => match s.as_str() {
-            "+" | "-" | "*" | "/" => eval_arithmetic_op(&list, env),
-            "=" | "!=" | "<" | ">" | "<=" | ">=" => eval_comparison_op(&list, env),
-            "and" | "or" | "not" => eval_logic_op(&list, env),
-            "car" | "cdr" | "len" => eval_list_op(&list, env),
-            "if" => eval_if(&list, env),
-            "def" => eval_def(&list, env),
-            "lambda" => eval_fun_definition(&list, env),
-            "macro" => eval_macro_definition(&list, env),
-            "macroexpand" => eval_macro_expand(&list, env),
-            "let" => eval_let(&list, env),
-            "quote" => eval_quote(&list, env),
-            "quasiquote" => eval_quasiquote(&list, env),
-            _ => eval_fun_call(&list, env),
+        Value::Symbol(s) => match *s {
+            Symbol::ADD | Symbol::SUB | Symbol::MUL | Symbol::DIV => eval_arithmetic_op(list, env),
+            Symbol::EQUAL
+            | Symbol::NOT_EQUAL
+            | Symbol::LESS
+            | Symbol::GREATER
+            | Symbol::LESS_EQUAL
+            | Symbol::GREATER_EQUAL => eval_comparison_op(list, env),
+            Symbol::AND | Symbol::OR | Symbol::NOT => eval_logic_op(list, env),
+            Symbol::CAR | Symbol::CDR | Symbol::LEN => eval_list_op(list, env),
+            Symbol::IF => eval_if(list, env),
+            Symbol::DEF => eval_def(list, env),
+            Symbol::LAMBDA => eval_fun_definition(list, env),
+            Symbol::MACRO => eval_macro_definition(list, env),
+            Symbol::DEFINE_SYNTAX => eval_define_syntax(list, env),
+            Symbol::SYNTAX_RULES => eval_syntax_rules(list, env),
+            Symbol::MACROEXPAND => eval_macro_expand(list, env),
+            Symbol::MACROEXPAND_1 => eval_macro_expand_1(list, env),
+            Symbol::MACROEXPAND_ALL => eval_macro_expand_all(list, env),
+            Symbol::GENSYM => eval_gensym(list, env),
+            Symbol::LET => eval_let(list, env),
+            Symbol::LET_STAR => eval_let_star(list, env),
+            Symbol::LETREC => eval_letrec(list, env),
+            Symbol::LABELS => eval_labels(list, env),
+            Symbol::MATCH => eval_match(list, env),
+            Symbol::QUOTE => eval_quote(list, env),
+            Symbol::QUASIQUOTE => eval_quasiquote(list, env),
+            Symbol::SYMBOL_TO_STRING => eval_symbol_to_string(list, env),
+            Symbol::STRING_TO_SYMBOL => eval_string_to_symbol(list, env),
+            Symbol::INTERN => eval_intern(list, env),
+            Symbol::GC => eval_gc(list, env),
+            Symbol::GC_STATS => eval_gc_stats(list, env),
+            Symbol::STRING_LENGTH => eval_string_length(list, env),
+            Symbol::STRING_APPEND => eval_string_append(list, env),
+            Symbol::SUBSTRING => eval_substring(list, env),
+            Symbol::NUMBER_TO_STRING => eval_number_to_string(list, env),
+            Symbol::STRING_TO_NUMBER => eval_string_to_number(list, env),
+            Symbol::PRINT | Symbol::PRINTLN => eval_print(list, env),
+            Symbol::PPRINT => eval_pprint(list, env),
+            Symbol::WRITE | Symbol::DISPLAY => eval_write(list, env),
+            Symbol::READ_LINE => eval_read_line(list, env),
+            Symbol::EXIT => eval_exit(list, env),
+            Symbol::GETENV => eval_getenv(list, env),
+            Symbol::COMMAND_LINE => eval_command_line(list, env),
+            Symbol::CURRENT_TIME => eval_current_time(list, env),
+            Symbol::SLEEP => eval_sleep(list, env),
+            Symbol::LOAD => eval_load(list, env),
+            Symbol::MODULE => eval_module(list, env),
+            Symbol::IMPORT => eval_import(list, env),
+            Symbol::EQUAL_P => eval_equal(list, env),
+            Symbol::SEND => eval_send(list, env),
+            Symbol::FOREIGN_TYPE => eval_foreign_type(list, env),
+            Symbol::JSON_PARSE => eval_json_parse(list, env),
+            Symbol::JSON_STRINGIFY => eval_json_stringify(list, env),
+            Symbol::READ => eval_read(list, env),
+            Symbol::READ_CHAR => eval_read_char(list, env),
+            Symbol::EOF_OBJECT_P => eval_eof_object_p(list, env),
+            Symbol::NEWLINE => eval_newline(list, env),
+            Symbol::OPEN_INPUT_FILE | Symbol::OPEN_OUTPUT_FILE => eval_open_file(list, env),
+            Symbol::CLOSE_PORT => eval_close_port(list, env),
+            Symbol::CURRENT_INPUT_PORT
+            | Symbol::CURRENT_OUTPUT_PORT
+            | Symbol::CURRENT_ERROR_PORT => eval_current_port(list, env),
+            Symbol::WITH_OUTPUT_TO_PORT | Symbol::WITH_INPUT_FROM_PORT => eval_with_port(list, env),
+            Symbol::WITH_OUTPUT_TO_STRING => eval_with_output_to_string(list, env),
+            Symbol::CALL_WITH_INPUT_STRING => eval_call_with_input_string(list, env),
+            Symbol::READ_FILE => eval_read_file(list, env),
+            Symbol::WRITE_FILE | Symbol::APPEND_FILE => eval_write_file(list, env),
+            Symbol::FILE_EXISTS => eval_file_exists(list, env),
+            Symbol::DIRECTORY_LIST => eval_directory_list(list, env),
+            Symbol::MAKE_DIRECTORY => eval_make_directory(list, env),
+            Symbol::DELETE_FILE => eval_delete_file(list, env),
+            Symbol::RENAME_FILE => eval_rename_file(list, env),
+            Symbol::FILE_METADATA => eval_file_metadata(list, env),
+            Symbol::PATH_JOIN => eval_path_join(list, env),
+            Symbol::PATH_NORMALIZE => eval_path_normalize(list, env),
+            Symbol::READ_FROM_STRING => eval_read_from_string(list, env),
+            Symbol::READ_ALL => eval_read_all(list, env),
+            Symbol::EVAL => eval_eval(list, env),
+            Symbol::CURRENT_ENV => eval_current_env(list, env),
+            Symbol::MAKE_ENV => eval_make_env(list, env),
+            _ => eval_fun_call(list, env),
         },
 
-        _ => eval_fun_call(&list, env),
+        _ => eval_fun_call(list, env),
     }
 }
 
+/// Expands all the macros in a top-level form (see [`eval_toplevel`]) without evaluating it.
+pub fn expand_value(value: &Value, env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    expand_toplevel(value, env)
+}
+
+/// Evaluates a top-level form, after expanding all of its macros once in a separate pass.
+pub fn eval_toplevel(value: &Value, env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    let expanded = expand_toplevel(value, env)?;
+    eval_value(&expanded, env)
+}
+
 pub fn eval_value(value: &Value, env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    interpreter::step()?;
+
     match value {
         Value::Nil => Ok(Value::Nil),
         Value::T => Ok(Value::T),
         Value::Number(n) => Ok(Value::Number(*n)),
         Value::String(s) => Ok(Value::String(s.clone())),
-        Value::Symbol(s) => eval_symbol(s, env),
-        Value::Lambda {
-            params,
-            body,
-            is_macro,
-        } => Ok(Value::Lambda {
-            params: params.clone(),
-            body: body.clone(),
-            is_macro: is_macro.clone(),
-        }),
-        Value::List(l) => eval_list(l, env),
+        Value::Symbol(s) => eval_symbol(s.clone(), env),
+        Value::Keyword(_) => Ok(value.clone()),
+        Value::Lambda { .. }
+        | Value::Syntax { .. }
+        | Value::Closure(_)
+        | Value::Native(_)
+        | Value::Foreign(_)
+        | Value::Env(_) => Ok(value.clone()),
+        Value::LocalRef { name, depth, slot } => env
+            .borrow()
+            .get_at(*depth, *slot)
+            .ok_or_else(|| format!("Local variable {} isn't in this environment", name)),
+        Value::List(l) => interpreter::nested(|| eval_list(l, env)),
     }
 }
@@ -0,0 +1,192 @@
+use super::{eval_toplevel, eval_value};
+use crate::{env::*, parse::*, symbol::Symbol, tokenize::tokenize};
+use std::{cell::RefCell, fs, path::Path, rc::Rc};
+
+pub(super) fn root(env: &Rc<RefCell<Env>>) -> Rc<RefCell<Env>> {
+    let mut env = env.clone();
+    loop {
+        let parent = env.borrow().parent();
+        match parent {
+            Some(parent) => env = parent,
+            None => return env,
+        }
+    }
+}
+
+/// Evaluates every top-level form in a file, returning the value of the last one.
+fn load_file(path: &Path, env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    let code = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    let mut tokens = tokenize(code).map_err(|e| format!("{}: {}", path.display(), e))?;
+    tokens.reverse();
+
+    let mut last_value = Value::Nil;
+    for form in parse_all(&mut tokens).map_err(|e| format!("{}: {}", path.display(), e))? {
+        last_value = eval_toplevel(&form, env).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    Ok(last_value)
+}
+
+pub fn eval_load(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // load path, evaluated in the root environment
+    if list.len() != 2 {
+        return Err(String::from("\"load\" requires 1 argument"));
+    }
+
+    let path = match eval_value(&list[1], env)? {
+        Value::String(s) => s,
+        _ => return Err(String::from("Argument to \"load\" must be a string")),
+    };
+    let path = env.borrow().capabilities().resolve_path(&path)?;
+    load_file(&path, &mut root(env))
+}
+
+pub fn eval_module(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // module name (export names...) body...
+    if list.len() < 3 {
+        return Err(String::from(
+            "\"module\" requires a name and a list of exports",
+        ));
+    }
+
+    let name = match &list[1] {
+        Value::Symbol(s) => s.clone(),
+        _ => return Err(String::from("Name of a module must be a symbol")),
+    };
+    let exports = match &list[2] {
+        Value::List(l) if l.first() == Some(&Value::Symbol(Symbol::EXPORT)) => {
+            let mut exports = vec![];
+            for export in &l[1..] {
+                match export {
+                    Value::Symbol(s) => exports.push(s.clone()),
+                    _ => return Err(String::from("Exported names must be symbols")),
+                }
+            }
+            exports
+        }
+        _ => {
+            return Err(String::from(
+                "Second parameter to \"module\" must be an (export names...) list",
+            ))
+        }
+    };
+
+    // the body gets its own root environment, so it only sees the names it defines and imports
+    let (mut module_env, modules) = {
+        let env = env.borrow();
+        match (env.fresh_root(), env.modules()) {
+            (Some(module_env), Some(modules)) => (module_env, modules),
+            _ => return Err(String::from("Modules can't be defined here")),
+        }
+    };
+    for form in &list[3..] {
+        eval_toplevel(form, &mut module_env)?;
+    }
+
+    let mut values = vec![];
+    for export in exports {
+        match module_env.borrow().get(export.clone()) {
+            Some(value) => values.push((export, value)),
+            None => {
+                return Err(format!(
+                    "Module {} doesn't define {}, which it exports",
+                    name, export
+                ))
+            }
+        }
+    }
+    modules.borrow_mut().define(name.clone(), values);
+
+    Ok(Value::Symbol(name))
+}
+
+/// Loads a module from the search path, unless it was already loaded or defined.
+fn require(name: Symbol, env: &Rc<RefCell<Env>>) -> Result<Vec<(Symbol, Value)>, String> {
+    let Some(modules) = env.borrow().modules() else {
+        return Err(String::from("Modules can't be imported here"));
+    };
+    if let Some(exports) = modules.borrow().get(name.clone()) {
+        return Ok(exports.to_vec());
+    }
+
+    let path = modules
+        .borrow()
+        .find(name.clone(), env.borrow().capabilities())?;
+    let path = path.ok_or_else(|| {
+        let search_path: Vec<String> = modules
+            .borrow()
+            .search_path()
+            .iter()
+            .map(|dir| dir.display().to_string())
+            .collect();
+        format!(
+            "Module {} not found (searched in: {})",
+            name,
+            search_path.join(", ")
+        )
+    })?;
+
+    // the file is evaluated in an environment of its own, which should define the module
+    modules.borrow_mut().start_loading(name.clone())?;
+    let result = env
+        .borrow()
+        .fresh_root()
+        .ok_or_else(|| String::from("Modules can't be imported here"))
+        .and_then(|mut file_env| load_file(&path, &mut file_env));
+    modules.borrow_mut().finish_loading(name.clone());
+    result?;
+
+    let exports = modules.borrow().get(name.clone()).map(<[_]>::to_vec);
+    exports.ok_or_else(|| format!("{} doesn't define module {}", path.display(), name))
+}
+
+// name, (only import-set names...) or (prefix import-set prefix)
+fn eval_import_set(set: &Value, env: &Rc<RefCell<Env>>) -> Result<Vec<(Symbol, Value)>, String> {
+    let l = match set {
+        Value::Symbol(name) => return require(name.clone(), env),
+        Value::List(l) if l.len() >= 2 => l,
+        _ => return Err(String::from("Invalid import set")),
+    };
+
+    let imports = eval_import_set(&l[1], env)?;
+    match &l[0] {
+        Value::Symbol(Symbol::ONLY) => {
+            let mut only = vec![];
+            for name in &l[2..] {
+                let name = match name {
+                    Value::Symbol(s) => s.clone(),
+                    _ => return Err(String::from("Names in \"only\" must be symbols")),
+                };
+                match imports.iter().find(|(n, _)| *n == name) {
+                    Some(import) => only.push(import.clone()),
+                    None => return Err(format!("{} isn't exported by the module", name)),
+                }
+            }
+            Ok(only)
+        }
+
+        Value::Symbol(Symbol::PREFIX) if l.len() == 3 => {
+            let prefix = match &l[2] {
+                Value::Symbol(s) => s.as_str(),
+                _ => return Err(String::from("Prefix in \"prefix\" must be a symbol")),
+            };
+            Ok(imports
+                .into_iter()
+                .map(|(name, value)| (Symbol::new(&format!("{}{}", prefix, name)), value))
+                .collect())
+        }
+
+        _ => Err(String::from("Invalid import set")),
+    }
+}
+
+pub fn eval_import(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // import import-sets..., which defines the imported names in the current environment
+    for set in &list[1..] {
+        for (name, value) in eval_import_set(set, env)? {
+            env.borrow_mut().set(name, value);
+        }
+    }
+
+    Ok(Value::Nil)
+}
@@ -1,14 +1,14 @@
 use super::eval_value;
-use crate::{env::*, parse::*};
-use std::{cell::RefCell, rc::Rc};
+use crate::{env::*, parse::*, symbol::Symbol};
+use std::{cell::RefCell, cmp::Ordering, rc::Rc};
 
 pub fn eval_arithmetic_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
     let head = &list[0];
     let tail = &list[1..];
 
     match head {
-        Value::Symbol(s) => match s.as_str() {
-            "+" => {
+        Value::Symbol(s) => match *s {
+            Symbol::ADD => {
                 let mut r: f64 = 0.0;
                 for v in tail {
                     match eval_value(v, env)? {
@@ -20,7 +20,11 @@ pub fn eval_arithmetic_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<
                 Ok(Value::Number(r))
             }
 
-            "-" => {
+            Symbol::SUB => {
+                if tail.is_empty() {
+                    return Err(String::from("At least 1 argument is required"));
+                }
+
                 // return the negative if there's only 1 argument
                 if tail.len() == 1 {
                     return match eval_value(&tail[0], env)? {
@@ -44,7 +48,7 @@ pub fn eval_arithmetic_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<
                 Ok(Value::Number(r))
             }
 
-            "*" => {
+            Symbol::MUL => {
                 let mut r: f64 = 1.0;
                 for v in tail {
                     match eval_value(v, env)? {
@@ -56,7 +60,11 @@ pub fn eval_arithmetic_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<
                 Ok(Value::Number(r))
             }
 
-            "/" => {
+            Symbol::DIV => {
+                if tail.is_empty() {
+                    return Err(String::from("At least 1 argument is required"));
+                }
+
                 let mut r: f64 = match eval_value(&tail[0], env)? {
                     Value::Number(n) => n,
                     _ => return Err(String::from("All arguments must be numbers")),
@@ -83,8 +91,8 @@ pub fn eval_comparison_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<
     let tail = &list[1..];
 
     match head {
-        Value::Symbol(s) => match s.as_str() {
-            ">" => {
+        Value::Symbol(s) => match *s {
+            Symbol::GREATER => {
                 let mut r: bool = true;
                 if tail.len() < 2 {
                     return Err(String::from("\">\" requires at least 2 arguments"));
@@ -93,7 +101,7 @@ pub fn eval_comparison_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<
                 for i in 1..tail.len() {
                     match (eval_value(&tail[i - 1], env)?, eval_value(&tail[i], env)?) {
                         (Value::Number(a), Value::Number(b)) => {
-                            if !(a > b) {
+                            if !matches!(a.partial_cmp(&b), Some(Ordering::Greater)) {
                                 r = false;
                                 break;
                             }
@@ -109,7 +117,7 @@ pub fn eval_comparison_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<
                 }
             }
 
-            "<" => {
+            Symbol::LESS => {
                 let mut r: bool = true;
                 if tail.len() < 2 {
                     return Err(String::from("\"<\" requires at least 2 arguments"));
@@ -118,7 +126,7 @@ pub fn eval_comparison_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<
                 for i in 1..tail.len() {
                     match (eval_value(&tail[i - 1], env)?, eval_value(&tail[i], env)?) {
                         (Value::Number(a), Value::Number(b)) => {
-                            if !(a < b) {
+                            if !matches!(a.partial_cmp(&b), Some(Ordering::Less)) {
                                 r = false;
                                 break;
                             }
@@ -134,7 +142,7 @@ pub fn eval_comparison_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<
                 }
             }
 
-            ">=" => {
+            Symbol::GREATER_EQUAL => {
                 let mut r: bool = true;
                 if tail.len() < 2 {
                     return Err(String::from("\">=\" requires at least 2 arguments"));
@@ -143,7 +151,10 @@ pub fn eval_comparison_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<
                 for i in 1..tail.len() {
                     match (eval_value(&tail[i - 1], env)?, eval_value(&tail[i], env)?) {
                         (Value::Number(a), Value::Number(b)) => {
-                            if !(a >= b) {
+                            if !matches!(
+                                a.partial_cmp(&b),
+                                Some(Ordering::Greater | Ordering::Equal)
+                            ) {
                                 r = false;
                                 break;
                             }
@@ -159,7 +170,7 @@ pub fn eval_comparison_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<
                 }
             }
 
-            "<=" => {
+            Symbol::LESS_EQUAL => {
                 let mut r: bool = true;
                 if tail.len() < 2 {
                     return Err(String::from("\"<=\" requires at least 2 arguments"));
@@ -168,7 +179,8 @@ pub fn eval_comparison_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<
                 for i in 1..tail.len() {
                     match (eval_value(&tail[i - 1], env)?, eval_value(&tail[i], env)?) {
                         (Value::Number(a), Value::Number(b)) => {
-                            if !(a <= b) {
+                            if !matches!(a.partial_cmp(&b), Some(Ordering::Less | Ordering::Equal))
+                            {
                                 r = false;
                                 break;
                             }
@@ -184,7 +196,7 @@ pub fn eval_comparison_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<
                 }
             }
 
-            "=" => {
+            Symbol::EQUAL => {
                 let mut r: bool = true;
                 if tail.len() < 2 {
                     return Err(String::from("\"=\" requires at least 2 arguments"));
@@ -209,7 +221,7 @@ pub fn eval_comparison_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<
                 }
             }
 
-            "!=" => {
+            Symbol::NOT_EQUAL => {
                 let mut r: bool = true;
                 if tail.len() < 2 {
                     return Err(String::from("\"!=\" requires at least 2 arguments"));
@@ -241,26 +253,48 @@ pub fn eval_comparison_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<
     }
 }
 
+pub fn eval_equal(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // equal? a b..., which compares any values structurally (foreign objects are compared by their type)
+    if list.len() < 3 {
+        return Err(String::from("\"equal?\" requires at least 2 arguments"));
+    }
+
+    let first = eval_value(&list[1], env)?;
+    let mut r = true;
+    for value in &list[2..] {
+        if eval_value(value, env)? != first {
+            r = false;
+        }
+    }
+
+    if r {
+        Ok(Value::T)
+    } else {
+        Ok(Value::Nil)
+    }
+}
+
 pub fn eval_logic_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
     let head = &list[0];
     let tail = &list[1..];
 
     match head {
-        Value::Symbol(s) => match s.as_str() {
-            "and" => {
+        Value::Symbol(s) => match *s {
+            Symbol::AND => {
+                let mut last = Value::T;
                 for v in tail {
-                    match eval_value(v, env)? {
-                        // early return if it's nil
-                        Value::Nil => return Ok(Value::Nil),
-                        _ => {}
+                    // early return if it's nil
+                    last = eval_value(v, env)?;
+                    if last == Value::Nil {
+                        return Ok(Value::Nil);
                     }
                 }
 
                 // return the value of the last element
-                Ok(tail[tail.len() - 1].clone())
+                Ok(last)
             }
 
-            "or" => {
+            Symbol::OR => {
                 for v in tail {
                     match eval_value(v, env)? {
                         // skip if it's nil
@@ -273,12 +307,12 @@ pub fn eval_logic_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value
                 Ok(Value::Nil)
             }
 
-            "not" => {
+            Symbol::NOT => {
                 if tail.len() != 1 {
                     return Err(String::from("\"not\" requires 1 argument"));
                 }
 
-                match tail[0] {
+                match eval_value(&tail[0], env)? {
                     Value::Nil => Ok(Value::T),
                     _ => Ok(Value::Nil),
                 }
@@ -296,15 +330,15 @@ pub fn eval_list_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value,
     let tail = &list[1..];
 
     match head {
-        Value::Symbol(s) => match s.as_str() {
-            "car" => {
+        Value::Symbol(s) => match *s {
+            Symbol::CAR => {
                 if tail.len() != 1 {
                     return Err(String::from("\"car\" requires 1 argument"));
                 }
 
                 match eval_value(&tail[0], env)? {
                     Value::List(l) => {
-                        if l.len() == 0 {
+                        if l.is_empty() {
                             Ok(Value::Nil)
                         } else {
                             Ok(l[0].clone())
@@ -314,7 +348,7 @@ pub fn eval_list_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value,
                 }
             }
 
-            "cdr" => {
+            Symbol::CDR => {
                 if tail.len() != 1 {
                     return Err(String::from("\"cdr\" requires 1 argument"));
                 }
@@ -324,14 +358,14 @@ pub fn eval_list_op(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value,
                         if l.len() < 2 {
                             Ok(Value::Nil)
                         } else {
-                            Ok(Value::List(l[1..].to_vec()))
+                            Ok(Value::List(l.skip(1)))
                         }
                     }
                     _ => Err(String::from("Argument needs to be a list")),
                 }
             }
 
-            "len" => {
+            Symbol::LEN => {
                 if tail.len() != 1 {
                     return Err(String::from("\"len\" requires 1 argument"));
                 }
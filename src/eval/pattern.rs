@@ -0,0 +1,306 @@
+use super::{eval_value, misc::eval_body};
+use crate::{env::*, parse::*, symbol::Symbol, util::value_to_string};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+// there are no vectors or maps, lists are the only sequences and property lists are used as maps (like JSON
+// objects are), so list and &key patterns are what matches them
+enum Pattern {
+    // _
+    Wildcard,
+    // any other symbol, binds the matched value to that name
+    Bind(Symbol),
+    // numbers, strings, nil, t, keywords and quoted values, which need to be equal to the matched value
+    Literal(Value),
+    // (a b c), or (a b . rest) if there's a rest pattern
+    List {
+        items: Vec<Pattern>,
+        rest: Option<Box<Pattern>>,
+    },
+    // (&key :a x :b y), which matches a property list that has all of the keys (in any order, and it can have
+    // others too), matching their values with the patterns
+    Map(Vec<(Value, Pattern)>),
+}
+
+// compiled patterns, kept beside the code rather than in it so the code stays data that can be printed and read
+// back. Each one is found by the address of its clause, with a weak reference to tell whether the clause at that
+// address is still the one it was compiled for
+struct Cache {
+    patterns: HashMap<*const Value, (Weak<[Value]>, Rc<Pattern>)>,
+    // how many patterns there can be before the ones of clauses that are gone are removed
+    limit: usize,
+}
+
+const MIN_CACHE_LIMIT: usize = 256;
+
+thread_local! {
+    static CACHE: RefCell<Cache> = RefCell::new(Cache {
+        patterns: HashMap::new(),
+        limit: MIN_CACHE_LIMIT,
+    });
+}
+
+// compiles the pattern of a clause, or returns it if it's been compiled already, so every match form compiles its
+// patterns once however many times it runs
+fn clause_pattern(clause: &List) -> Result<Rc<Pattern>, String> {
+    let key = clause.as_ptr();
+    let cached = CACHE.with(|cache| match cache.borrow().patterns.get(&key) {
+        Some((weak, pattern))
+            if weak
+                .upgrade()
+                .is_some_and(|c| Rc::ptr_eq(&c, clause.shared())) =>
+        {
+            Some(pattern.clone())
+        }
+        _ => None,
+    });
+    if let Some(pattern) = cached {
+        return Ok(pattern);
+    }
+
+    let pattern = Rc::new(compile_pattern(&clause[0])?);
+    CACHE.with(|cache| {
+        let cache = &mut *cache.borrow_mut();
+        if cache.patterns.len() >= cache.limit {
+            cache
+                .patterns
+                .retain(|_, (weak, _)| weak.strong_count() > 0);
+            cache.limit = MIN_CACHE_LIMIT.max(cache.patterns.len() * 2);
+        }
+        cache
+            .patterns
+            .insert(key, (Rc::downgrade(clause.shared()), pattern.clone()));
+    });
+    Ok(pattern)
+}
+
+struct Clause<'a> {
+    pattern: Rc<Pattern>,
+    guard: Option<&'a Value>,
+    body: &'a [Value],
+}
+
+fn compile_pattern(value: &Value) -> Result<Pattern, String> {
+    match value {
+        Value::Symbol(Symbol::WILDCARD) => Ok(Pattern::Wildcard),
+        Value::Symbol(s) => Ok(Pattern::Bind(s.clone())),
+
+        Value::List(l) if l.len() == 2 && l[0] == Value::Symbol(Symbol::QUOTE) => {
+            Ok(Pattern::Literal(l[1].clone()))
+        }
+
+        Value::List(l) if l.first() == Some(&Value::Symbol(Symbol::KEY)) => {
+            if l.len() % 2 == 0 {
+                return Err(String::from(
+                    "\"&key\" in a pattern must be followed by keys and patterns",
+                ));
+            }
+
+            let mut entries = vec![];
+            for entry in l[1..].chunks(2) {
+                if !matches!(entry[0], Value::Keyword(_) | Value::String(_)) {
+                    return Err(String::from(
+                        "Keys in a \"&key\" pattern must be keywords or strings",
+                    ));
+                }
+                entries.push((entry[0].clone(), compile_pattern(&entry[1])?));
+            }
+
+            Ok(Pattern::Map(entries))
+        }
+
+        Value::List(l) => {
+            let dot = Value::Symbol(Symbol::DOT);
+            let (items, rest) = match l.iter().position(|item| *item == dot) {
+                Some(i) if i + 2 == l.len() => {
+                    (&l[..i], Some(Box::new(compile_pattern(&l[i + 1])?)))
+                }
+                Some(_) => {
+                    return Err(String::from(
+                        "\".\" in a pattern must be followed by exactly one pattern",
+                    ))
+                }
+                None => (&l[..], None),
+            };
+
+            let mut compiled = vec![];
+            for item in items {
+                compiled.push(compile_pattern(item)?);
+            }
+
+            Ok(Pattern::List {
+                items: compiled,
+                rest,
+            })
+        }
+
+        _ => Ok(Pattern::Literal(value.clone())),
+    }
+}
+
+fn collect_names(pattern: &Pattern, names: &mut Vec<Symbol>) {
+    match pattern {
+        Pattern::Bind(name) => names.push(name.clone()),
+        Pattern::List { items, rest } => {
+            for item in items {
+                collect_names(item, names);
+            }
+            if let Some(rest) = rest {
+                collect_names(rest, names);
+            }
+        }
+        Pattern::Map(entries) => {
+            for (_, pattern) in entries {
+                collect_names(pattern, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Returns the names a pattern binds, in the order they get bound when it matches.
+pub fn pattern_names(pattern: &Value) -> Vec<Symbol> {
+    let mut names = vec![];
+    if let Ok(pattern) = compile_pattern(pattern) {
+        collect_names(&pattern, &mut names);
+    }
+    names
+}
+
+fn compile_clause(clause: &Value) -> Result<Clause<'_>, String> {
+    // (pattern body...) or (pattern when guard body...)
+    match clause {
+        Value::List(l) if !l.is_empty() => {
+            let pattern = clause_pattern(l)?;
+
+            if l.len() > 1 && l[1] == Value::Symbol(Symbol::WHEN) {
+                if l.len() < 3 {
+                    return Err(String::from("\"when\" in a match clause requires a guard"));
+                }
+
+                Ok(Clause {
+                    pattern,
+                    guard: Some(&l[2]),
+                    body: &l[3..],
+                })
+            } else {
+                Ok(Clause {
+                    pattern,
+                    guard: None,
+                    body: &l[1..],
+                })
+            }
+        }
+
+        _ => Err(String::from(
+            "Match clauses need to be of the form (pattern body...)",
+        )),
+    }
+}
+
+fn match_pattern(pattern: &Pattern, value: &Value, bindings: &mut Vec<(Symbol, Value)>) -> bool {
+    match pattern {
+        Pattern::Wildcard => true,
+        Pattern::Bind(name) => {
+            bindings.push((name.clone(), value.clone()));
+            true
+        }
+
+        // nil and the empty list are interchangeable, since cdr returns nil for the end of a list
+        Pattern::Literal(Value::Nil) => match value {
+            Value::Nil => true,
+            Value::List(l) => l.is_empty(),
+            _ => false,
+        },
+        Pattern::Literal(literal) => literal == value,
+
+        Pattern::List { items, rest } => {
+            let list: &[Value] = match value {
+                Value::List(l) => l,
+                Value::Nil => &[],
+                _ => return false,
+            };
+
+            match rest {
+                None if list.len() != items.len() => return false,
+                Some(_) if list.len() < items.len() => return false,
+                _ => {}
+            }
+
+            for (item, value) in items.iter().zip(list) {
+                if !match_pattern(item, value, bindings) {
+                    return false;
+                }
+            }
+
+            match rest {
+                Some(rest) => {
+                    let tail = match value {
+                        Value::List(l) => l.skip(items.len()),
+                        _ => List::from(vec![]),
+                    };
+                    match_pattern(rest, &Value::List(tail), bindings)
+                }
+                None => true,
+            }
+        }
+
+        Pattern::Map(entries) => {
+            let list: &[Value] = match value {
+                Value::List(l) if l.len() % 2 == 0 => l,
+                Value::Nil => &[],
+                _ => return false,
+            };
+
+            entries.iter().all(|(key, pattern)| {
+                match list.chunks(2).find(|entry| entry[0] == *key) {
+                    Some(entry) => match_pattern(pattern, &entry[1], bindings),
+                    None => false,
+                }
+            })
+        }
+    }
+}
+
+pub fn eval_match(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // match value (pattern body...)...
+    if list.len() < 2 {
+        return Err(String::from("\"match\" requires a value to match on"));
+    }
+
+    let value = eval_value(&list[1], env)?;
+
+    // compile all the clauses up front, so malformed patterns are reported even if an earlier clause matches
+    let mut clauses = vec![];
+    for clause in &list[2..] {
+        clauses.push(compile_clause(clause)?);
+    }
+
+    for clause in clauses {
+        let mut bindings = vec![];
+        if !match_pattern(&clause.pattern, &value, &mut bindings) {
+            continue;
+        }
+
+        let mut new_env = Env::extend(env);
+        for (name, value) in bindings {
+            new_env.borrow_mut().set(name, value);
+        }
+
+        if let Some(guard) = clause.guard {
+            if eval_value(guard, &mut new_env)? == Value::Nil {
+                continue;
+            }
+        }
+
+        return eval_body(clause.body, &mut new_env);
+    }
+
+    Err(format!(
+        "No matching clause for value: {}",
+        value_to_string(&value)
+    ))
+}
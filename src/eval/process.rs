@@ -0,0 +1,48 @@
+use super::eval_value;
+use crate::{env::*, interpreter, parse::*};
+use std::{cell::RefCell, rc::Rc};
+
+pub fn eval_exit(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // exit [code], the code is 0 by default
+    let code = match list.len() {
+        1 => 0,
+        2 => match eval_value(&list[1], env)? {
+            Value::Number(n) if n.fract() == 0.0 => n as i32,
+            _ => return Err(String::from("Exit code must be an integer")),
+        },
+        _ => return Err(String::from("\"exit\" requires 0 or 1 arguments")),
+    };
+
+    std::process::exit(code)
+}
+
+pub fn eval_getenv(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    if list.len() != 2 {
+        return Err(String::from("\"getenv\" requires 1 argument"));
+    }
+
+    // nil if the variable isn't set
+    match eval_value(&list[1], env)? {
+        Value::String(name) => match std::env::var(&*name) {
+            Ok(value) => {
+                interpreter::check_string_length(value.chars().count())?;
+                Ok(Value::String(value.into()))
+            }
+            Err(_) => Ok(Value::Nil),
+        },
+        _ => Err(String::from("Argument to \"getenv\" must be a string")),
+    }
+}
+
+pub fn eval_command_line(list: &[Value], _env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    if list.len() != 1 {
+        return Err(String::from("\"command-line\" doesn't take any arguments"));
+    }
+
+    // every argument the process was started with, including the program itself
+    let args: Vec<Value> = std::env::args()
+        .map(|arg| Value::String(arg.into()))
+        .collect();
+    interpreter::allocate(args.len())?;
+    Ok(Value::List(args.into()))
+}
@@ -1,5 +1,5 @@
 use super::eval_value;
-use crate::{env::*, parse::*};
+use crate::{env::*, interpreter, parse::*, symbol::Symbol, util::value_to_string};
 use std::{cell::RefCell, rc::Rc};
 
 pub fn eval_quote(list: &[Value], _env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
@@ -10,82 +10,115 @@ pub fn eval_quote(list: &[Value], _env: &mut Rc<RefCell<Env>>) -> Result<Value,
     Ok(list[1].clone())
 }
 
-pub fn eval_quasiquote_value(value: &Value, env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
-    match value {
-        Value::List(list) => {
-            if list.len() == 0 {
-                return Ok(Value::List(vec![]));
+fn is_splice(value: &Value) -> bool {
+    matches!(
+        value,
+        Value::Symbol(Symbol::SPLICE_UNQUOTE | Symbol::UNQUOTE_SPLICING)
+    )
+}
+
+/// Evaluates the quasiquoted value. `depth` is the number of quasiquotes the value is nested in, minus the
+/// number of unquotes around it, so only unquotes at depth 1 get evaluated and the ones inside nested
+/// quasiquotes are kept as they are.
+pub fn eval_quasiquote_value(
+    value: &Value,
+    depth: usize,
+    env: &mut Rc<RefCell<Env>>,
+) -> Result<Value, String> {
+    let list = match value {
+        Value::List(l) => l,
+        _ => return Ok(value.clone()),
+    };
+
+    match list.first() {
+        Some(Value::Symbol(Symbol::UNQUOTE)) => {
+            if list.len() != 2 {
+                return Err(String::from("\"unquote\" requires 1 argument"));
             }
 
-            if list.len() == 1 {
-                return Ok(Value::List(vec![eval_quasiquote_value(&list[0], env)?]));
+            if depth == 1 {
+                return eval_value(&list[1], env);
             }
 
-            if list[0] == Value::Symbol(String::from("unquote")) {
-                if list.len() == 2 {
-                    return Ok(list[1].clone());
-                } else {
-                    return Err(String::from("\"unquote\" requires 1 argument"));
-                }
+            return Ok(Value::List(
+                vec![
+                    list[0].clone(),
+                    eval_quasiquote_value(&list[1], depth - 1, env)?,
+                ]
+                .into(),
+            ));
+        }
+
+        Some(head) if is_splice(head) => {
+            if list.len() != 2 {
+                return Err(String::from("\"splice-unquote\" requires 1 argument"));
             }
 
-            if list[0] == Value::Symbol(String::from("splice-unquote")) {
+            if depth == 1 {
                 return Err(String::from(
-                    "Can't use \"splice-unquote\" directly under a \"quasiquote\"",
+                    "\"splice-unquote\" can only be used inside of a list",
                 ));
             }
 
-            let mut new_list: Vec<Value> = vec![];
-            for item in list {
-                match item {
-                    Value::List(list_item) => {
-                        let mut pushed = false;
-                        if list_item.len() >= 2 {
-                            if list_item[0] == Value::Symbol(String::from("unquote")) {
-                                if list_item.len() == 2 {
-                                    new_list.push(eval_value(&list_item[1], env)?);
-                                } else {
-                                    return Err(String::from("\"unquote\" requires 1 argument"));
-                                }
-                                pushed = true;
-                            }
-
-                            if list_item[0] == Value::Symbol(String::from("splice-unquote")) {
-                                if list_item.len() == 2 {
-                                    let value = eval_value(&list_item[1], env)?;
-                                    // if it's a regular value, just push it (same as unquote), but if it's a list, push its items one by one
-                                    match value {
-                                        Value::List(splice_items) => {
-                                            for item in splice_items {
-                                                new_list.push(item);
-                                            }
-                                        }
-
-                                        _ => new_list.push(value),
-                                    }
-                                } else {
-                                    return Err(String::from(
-                                        "\"splice-unquote\" requires 1 argument",
-                                    ));
-                                }
-                                pushed = true;
-                            }
-                        }
-
-                        if !pushed {
-                            new_list.push(eval_quasiquote_value(item, env)?);
-                        }
-                    }
+            return Ok(Value::List(
+                vec![
+                    list[0].clone(),
+                    eval_quasiquote_value(&list[1], depth - 1, env)?,
+                ]
+                .into(),
+            ));
+        }
 
-                    _ => new_list.push(item.clone()),
-                }
+        Some(Value::Symbol(Symbol::QUASIQUOTE)) => {
+            if list.len() != 2 {
+                return Err(String::from("\"quasiquote\" requires 1 argument"));
             }
 
-            Ok(Value::List(new_list))
+            return Ok(Value::List(
+                vec![
+                    list[0].clone(),
+                    eval_quasiquote_value(&list[1], depth + 1, env)?,
+                ]
+                .into(),
+            ));
         }
 
-        _ => Ok(value.clone()),
+        _ => {}
+    }
+
+    let mut new_list: Vec<Value> = vec![];
+    for (i, item) in list.iter().enumerate() {
+        match item {
+            // a dotted tail, e.g. (1 . ,x), which is joined onto the list if it's one, like in (1 ,@x)
+            Value::Symbol(Symbol::DOT) if depth == 1 && i + 2 == list.len() => {
+                match eval_quasiquote_value(&list[i + 1], depth, env)? {
+                    Value::List(items) => new_list.extend(items.iter().cloned()),
+                    Value::Nil => {}
+                    tail => new_list.extend([item.clone(), tail]),
+                }
+                break;
+            }
+
+            // a splice-unquote at depth 1 inserts all the items of the list it evaluates to
+            Value::List(l) if depth == 1 && l.len() == 2 && is_splice(&l[0]) => {
+                match eval_value(&l[1], env)? {
+                    Value::List(items) => new_list.extend(items.iter().cloned()),
+                    Value::Nil => {}
+                    value => {
+                        return Err(format!(
+                            "\"splice-unquote\" requires a list, got {}",
+                            value_to_string(&value)
+                        ))
+                    }
+                }
+            }
+
+            _ => new_list.push(eval_quasiquote_value(item, depth, env)?),
+        }
     }
+
+    interpreter::allocate(new_list.len())?;
+    Ok(Value::List(new_list.into()))
 }
 
 pub fn eval_quasiquote(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
@@ -93,6 +126,5 @@ pub fn eval_quasiquote(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Val
         return Err(String::from("\"quasiquote\" requires 1 argument"));
     }
 
-    let value = &list[1];
-    eval_quasiquote_value(value, env)
+    eval_quasiquote_value(&list[1], 1, env)
 }
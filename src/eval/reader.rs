@@ -0,0 +1,51 @@
+use super::eval_value;
+use crate::{env::*, interpreter, parse::*};
+use std::{cell::RefCell, rc::Rc};
+
+fn string_arg(name: &str, list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Rc<str>, String> {
+    if list.len() != 2 {
+        return Err(format!("\"{}\" requires 1 argument", name));
+    }
+
+    match eval_value(&list[1], env)? {
+        Value::String(s) => Ok(s),
+        _ => Err(format!("Argument to \"{}\" must be a string", name)),
+    }
+}
+
+// checks the strings in a form that was read against the string length limit, like strings that are made any
+// other way (the ones in the code being evaluated aren't checked, they're not made by it)
+pub(super) fn check_strings(form: &Value) -> Result<(), String> {
+    let mut stack = vec![form];
+    while let Some(value) = stack.pop() {
+        match value {
+            Value::String(s) => interpreter::check_string_length(s.chars().count())?,
+            Value::List(l) => stack.extend(l.iter()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn eval_read_from_string(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // read-from-string string, which reads the first form in the string
+    let code = string_arg("read-from-string", list, env)?;
+    match read_form(&code)? {
+        Some((form, _)) => {
+            check_strings(&form)?;
+            Ok(form)
+        }
+        None => Err(String::from("There's no form in the string to read")),
+    }
+}
+
+pub fn eval_read_all(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // read-all string, which reads every form in the string into a list
+    let code = string_arg("read-all", list, env)?;
+    let forms = read_all(&code)?;
+    for form in &forms {
+        check_strings(form)?;
+    }
+    interpreter::allocate(forms.len())?;
+    Ok(Value::List(forms.into()))
+}
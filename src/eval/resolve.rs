@@ -0,0 +1,311 @@
+use super::{function::parse_params, pattern::pattern_names};
+use crate::{env::*, parse::*, symbol::Symbol};
+use std::{cell::RefCell, rc::Rc};
+
+struct Resolver<'a> {
+    // the frames the code will run in which don't exist yet (innermost last), in the order their slots are added
+    frames: Vec<Vec<Symbol>>,
+    // the environment the lambda is defined in, which those frames will extend
+    env: &'a Rc<RefCell<Env>>,
+    // names that are defined with def somewhere in the body, which adds them to a frame at runtime, so
+    // they're always looked up by name
+    defined: Vec<Symbol>,
+}
+
+fn unique(names: Vec<Symbol>) -> Vec<Symbol> {
+    // setting a name that's already in a frame reuses its slot
+    let mut unique: Vec<Symbol> = vec![];
+    for name in names {
+        if !unique.contains(&name) {
+            unique.push(name);
+        }
+    }
+    unique
+}
+
+fn collect_defined(form: &Value, defined: &mut Vec<Symbol>) {
+    if let Value::List(l) = form {
+        match (l.first(), l.get(1)) {
+            (Some(Value::Symbol(Symbol::QUOTE)), _) => return,
+            (Some(Value::Symbol(Symbol::DEF)), Some(Value::Symbol(name))) => {
+                defined.push(name.clone())
+            }
+            _ => {}
+        }
+
+        for item in l.iter() {
+            collect_defined(item, defined);
+        }
+    }
+}
+
+impl Resolver<'_> {
+    fn lookup(&self, name: Symbol) -> Option<(usize, usize)> {
+        if self.defined.contains(&name) {
+            return None;
+        }
+
+        for (depth, frame) in self.frames.iter().rev().enumerate() {
+            if let Some(slot) = frame.iter().position(|n| *n == name) {
+                return Some((depth, slot));
+            }
+        }
+
+        // globals (in the root environment) stay as symbols
+        let mut depth = self.frames.len();
+        let mut env = self.env.clone();
+        loop {
+            let parent = env.borrow().parent()?;
+            if let Some(slot) = env.borrow().slot(&name) {
+                return Some((depth, slot));
+            }
+
+            depth += 1;
+            env = parent;
+        }
+    }
+
+    fn resolve_all(&mut self, forms: &[Value]) -> Vec<Value> {
+        forms.iter().map(|form| self.resolve(form)).collect()
+    }
+
+    fn resolve_in_frame(&mut self, names: Vec<Symbol>, forms: &[Value]) -> Vec<Value> {
+        self.frames.push(names);
+        let resolved = self.resolve_all(forms);
+        self.frames.pop();
+        resolved
+    }
+
+    fn resolve_quasiquoted(&mut self, value: &Value, depth: usize) -> Value {
+        match value {
+            Value::List(l) if l.len() == 2 => match &l[0] {
+                Value::Symbol(
+                    Symbol::UNQUOTE | Symbol::SPLICE_UNQUOTE | Symbol::UNQUOTE_SPLICING,
+                ) => {
+                    let inner = if depth == 1 {
+                        self.resolve(&l[1])
+                    } else {
+                        self.resolve_quasiquoted(&l[1], depth - 1)
+                    };
+                    Value::List(vec![l[0].clone(), inner].into())
+                }
+                Value::Symbol(Symbol::QUASIQUOTE) => Value::List(
+                    vec![l[0].clone(), self.resolve_quasiquoted(&l[1], depth + 1)].into(),
+                ),
+                _ => Value::List(
+                    l.iter()
+                        .map(|item| self.resolve_quasiquoted(item, depth))
+                        .collect(),
+                ),
+            },
+
+            Value::List(l) => Value::List(
+                l.iter()
+                    .map(|item| self.resolve_quasiquoted(item, depth))
+                    .collect(),
+            ),
+
+            _ => value.clone(),
+        }
+    }
+
+    fn resolve_lambda(&mut self, params: &Value, body: &[Value]) -> Option<Vec<Value>> {
+        let params = parse_params(params).ok()?;
+        Some(self.resolve_in_frame(unique(params.names()), body))
+    }
+
+    fn resolve(&mut self, form: &Value) -> Value {
+        let l = match form {
+            Value::Symbol(s) => {
+                return match self.lookup(s.clone()) {
+                    Some((depth, slot)) => Value::LocalRef {
+                        name: s.clone(),
+                        depth,
+                        slot,
+                    },
+                    None => form.clone(),
+                }
+            }
+            Value::List(l) if !l.is_empty() => l,
+            _ => return form.clone(),
+        };
+
+        // the head is never resolved, since the evaluator dispatches special forms and builtins on the symbol itself
+        let head = match &l[0] {
+            Value::Symbol(s) => s.clone(),
+            _ => return Value::List(self.resolve_all(l).into()),
+        };
+
+        // calls to macros get their arguments as code, so they're left alone
+        if self.lookup(head.clone()).is_none() {
+            if let Some(Value::Lambda { is_macro: true, .. } | Value::Syntax { .. }) =
+                self.env.borrow().get(head.clone())
+            {
+                return form.clone();
+            }
+        }
+
+        let mut list = vec![l[0].clone()];
+        match head {
+            Symbol::QUOTE | Symbol::SYNTAX_RULES | Symbol::DEFINE_SYNTAX | Symbol::MODULE => {
+                return form.clone()
+            }
+
+            Symbol::QUASIQUOTE if l.len() == 2 => list.push(self.resolve_quasiquoted(&l[1], 1)),
+
+            Symbol::LAMBDA | Symbol::MACRO if l.len() >= 3 => {
+                match self.resolve_lambda(&l[1], &l[2..]) {
+                    Some(body) => {
+                        list.push(l[1].clone());
+                        list.extend(body);
+                    }
+                    None => return form.clone(),
+                }
+            }
+
+            Symbol::LET | Symbol::LET_STAR | Symbol::LETREC if l.len() >= 2 => {
+                let bindings: Vec<(&Value, &Value)> = match &l[1] {
+                    Value::List(bindings) => bindings
+                        .iter()
+                        .filter_map(|binding| match binding {
+                            Value::List(b) if b.len() == 2 => Some((&b[0], &b[1])),
+                            _ => None,
+                        })
+                        .collect(),
+                    _ => vec![],
+                };
+                let names: Vec<Symbol> = bindings
+                    .iter()
+                    .filter_map(|(name, _)| match name {
+                        Value::Symbol(s) => Some(s.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                if names.len() != bindings.len() || !matches!(l[1], Value::List(_)) {
+                    return form.clone();
+                }
+
+                // let evaluates the values outside of the new frame, letrec inside of it, and let* inside of
+                // it while it's being filled
+                let mut resolved = vec![];
+                match head {
+                    Symbol::LET => {
+                        for (name, value) in bindings.iter() {
+                            resolved.push(Value::List(
+                                vec![(*name).clone(), self.resolve(value)].into(),
+                            ));
+                        }
+                        self.frames.push(unique(names));
+                    }
+                    Symbol::LETREC => {
+                        self.frames.push(unique(names));
+                        for (name, value) in bindings.iter() {
+                            resolved.push(Value::List(
+                                vec![(*name).clone(), self.resolve(value)].into(),
+                            ));
+                        }
+                    }
+                    _ => {
+                        self.frames.push(vec![]);
+                        for ((name, value), symbol) in bindings.iter().zip(names) {
+                            resolved.push(Value::List(
+                                vec![(*name).clone(), self.resolve(value)].into(),
+                            ));
+                            let frame = self.frames.last_mut().unwrap();
+                            if !frame.contains(&symbol) {
+                                frame.push(symbol);
+                            }
+                        }
+                    }
+                }
+
+                list.push(Value::List(resolved.into()));
+                list.extend(self.resolve_all(&l[2..]));
+                self.frames.pop();
+            }
+
+            Symbol::LABELS if l.len() >= 2 => {
+                let definitions = match &l[1] {
+                    Value::List(definitions) => definitions,
+                    _ => return form.clone(),
+                };
+                let mut names = vec![];
+                for definition in definitions.iter() {
+                    match definition {
+                        Value::List(d) if d.len() == 3 => match &d[0] {
+                            Value::Symbol(s) => names.push(s.clone()),
+                            _ => return form.clone(),
+                        },
+                        _ => return form.clone(),
+                    }
+                }
+
+                self.frames.push(unique(names));
+                let mut resolved = vec![];
+                for definition in definitions.iter() {
+                    if let Value::List(d) = definition {
+                        match self.resolve_lambda(&d[1], &d[2..]) {
+                            Some(body) => resolved.push(Value::List(
+                                vec![d[0].clone(), d[1].clone(), body[0].clone()].into(),
+                            )),
+                            None => resolved.push(definition.clone()),
+                        }
+                    }
+                }
+                list.push(Value::List(resolved.into()));
+                list.extend(self.resolve_all(&l[2..]));
+                self.frames.pop();
+            }
+
+            Symbol::MATCH if l.len() >= 2 => {
+                list.push(self.resolve(&l[1]));
+                for clause in &l[2..] {
+                    match clause {
+                        Value::List(c) if !c.is_empty() => {
+                            let names = pattern_names(&c[0]);
+                            let mut resolved = vec![c[0].clone()];
+                            resolved.extend(self.resolve_in_frame(unique(names), &c[1..]));
+                            list.push(Value::List(resolved.into()));
+                        }
+                        _ => list.push(clause.clone()),
+                    }
+                }
+            }
+
+            Symbol::DEF if l.len() == 3 => {
+                list.push(l[1].clone());
+                list.push(self.resolve(&l[2]));
+            }
+
+            _ => list.extend(self.resolve_all(&l[1..])),
+        }
+
+        Value::List(list.into())
+    }
+}
+
+/// Resolves the references to local variables in a lambda's body to (depth, slot) pairs (see
+/// [`Value::LocalRef`]), so they don't need to be looked up by name when it's called. `env` is the
+/// environment the lambda is being defined in.
+pub fn resolve_body(params: &LambdaParams, body: &Value, env: &Rc<RefCell<Env>>) -> Value {
+    let mut defined = vec![];
+    collect_defined(body, &mut defined);
+
+    let mut resolver = Resolver {
+        frames: vec![],
+        env,
+        defined,
+    };
+    let resolved = resolver.resolve_in_frame(unique(params.names()), std::slice::from_ref(body));
+    resolved.into_iter().next().unwrap()
+}
+
+/// Turns the references to local variables in some code back into the names they were resolved from. Macros
+/// get their arguments as code they can put anywhere (or return as data), so the slots wouldn't be right.
+pub fn unresolve(form: &Value) -> Value {
+    match form {
+        Value::LocalRef { name, .. } => Value::Symbol(name.clone()),
+        Value::List(l) => Value::List(l.iter().map(unresolve).collect()),
+        _ => form.clone(),
+    }
+}
@@ -0,0 +1,105 @@
+use super::eval_value;
+use crate::{env::*, interpreter, parse::*};
+use std::{cell::RefCell, rc::Rc};
+
+fn new_string(s: String) -> Result<Value, String> {
+    interpreter::check_string_length(s.chars().count())?;
+    Ok(Value::String(s.into()))
+}
+
+pub fn eval_string_length(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    if list.len() != 2 {
+        return Err(String::from("\"string-length\" requires 1 argument"));
+    }
+
+    match eval_value(&list[1], env)? {
+        Value::String(s) => Ok(Value::Number(s.chars().count() as f64)),
+        _ => Err(String::from(
+            "Argument to \"string-length\" must be a string",
+        )),
+    }
+}
+
+pub fn eval_string_append(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    let mut result = String::new();
+    for arg in &list[1..] {
+        match eval_value(arg, env)? {
+            Value::String(s) => result.push_str(&s),
+            _ => {
+                return Err(String::from(
+                    "Arguments to \"string-append\" must be strings",
+                ))
+            }
+        }
+    }
+
+    new_string(result)
+}
+
+pub fn eval_substring(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // substring string start [end], counted in characters
+    if list.len() != 3 && list.len() != 4 {
+        return Err(String::from("\"substring\" requires 2 or 3 arguments"));
+    }
+
+    let s = match eval_value(&list[1], env)? {
+        Value::String(s) => s,
+        _ => {
+            return Err(String::from(
+                "First argument to \"substring\" must be a string",
+            ))
+        }
+    };
+    let length = s.chars().count();
+
+    let mut index = |value: &Value| match eval_value(value, env)? {
+        Value::Number(n) if n >= 0.0 && n.fract() == 0.0 && n as usize <= length => Ok(n as usize),
+        _ => Err(format!(
+            "Indices passed to \"substring\" must be integers between 0 and {}",
+            length
+        )),
+    };
+    let start = index(&list[2])?;
+    let end = match list.get(3) {
+        Some(value) => index(value)?,
+        None => length,
+    };
+    if start > end {
+        return Err(String::from(
+            "The start of a substring can't be after its end",
+        ));
+    }
+
+    new_string(s.chars().skip(start).take(end - start).collect())
+}
+
+pub fn eval_number_to_string(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    if list.len() != 2 {
+        return Err(String::from("\"number->string\" requires 1 argument"));
+    }
+
+    match eval_value(&list[1], env)? {
+        Value::Number(n) => new_string(n.to_string()),
+        _ => Err(String::from(
+            "Argument to \"number->string\" must be a number",
+        )),
+    }
+}
+
+pub fn eval_string_to_number(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    if list.len() != 2 {
+        return Err(String::from("\"string->number\" requires 1 argument"));
+    }
+
+    // nil if the string isn't a number
+    match eval_value(&list[1], env)? {
+        Value::String(s) => Ok(s
+            .trim()
+            .parse::<f64>()
+            .map(Value::Number)
+            .unwrap_or(Value::Nil)),
+        _ => Err(String::from(
+            "Argument to \"string->number\" must be a string",
+        )),
+    }
+}
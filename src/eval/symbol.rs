@@ -0,0 +1,49 @@
+use super::eval_value;
+use crate::{env::*, interpreter, parse::*, symbol::Symbol};
+use std::{cell::RefCell, rc::Rc};
+
+fn eval_string_argument(
+    name: &str,
+    list: &[Value],
+    env: &mut Rc<RefCell<Env>>,
+) -> Result<String, String> {
+    if list.len() != 2 {
+        return Err(format!("\"{}\" requires 1 argument", name));
+    }
+
+    match eval_value(&list[1], env)? {
+        Value::String(s) => Ok(s.to_string()),
+        _ => Err(format!("Argument to \"{}\" must be a string", name)),
+    }
+}
+
+pub fn eval_symbol_to_string(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    if list.len() != 2 {
+        return Err(String::from("\"symbol->string\" requires 1 argument"));
+    }
+
+    // keywords keep their colon, so interning the name gives back the same keyword
+    let name = match eval_value(&list[1], env)? {
+        Value::Symbol(s) => s.as_str().to_string(),
+        Value::Keyword(k) => format!(":{}", k),
+        _ => {
+            return Err(String::from(
+                "Argument to \"symbol->string\" must be a symbol or keyword",
+            ))
+        }
+    };
+    interpreter::check_string_length(name.chars().count())?;
+    Ok(Value::String(name.into()))
+}
+
+pub fn eval_string_to_symbol(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // the symbol is named exactly like the string, even if it's something the reader would read differently
+    let name = eval_string_argument("string->symbol", list, env)?;
+    Ok(Value::Symbol(Symbol::new(&name)))
+}
+
+pub fn eval_intern(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // the name is read the same way as in code, so :name gives a keyword
+    let name = eval_string_argument("intern", list, env)?;
+    read_symbol(&name)
+}
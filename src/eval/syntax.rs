@@ -0,0 +1,456 @@
+use super::eval_value;
+use crate::{env::*, parse::*, symbol::Symbol, util::*};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+#[derive(Clone)]
+enum Binding {
+    One(Value),
+    // a pattern variable under an ellipsis, with one binding for each repetition
+    Many(Vec<Binding>),
+}
+
+fn is_ellipsis(value: Option<&Value>) -> bool {
+    matches!(value, Some(Value::Symbol(Symbol::ELLIPSIS)))
+}
+
+fn pattern_vars(pattern: &Value, literals: &[Symbol], vars: &mut Vec<Symbol>) {
+    match pattern {
+        Value::Symbol(Symbol::WILDCARD | Symbol::ELLIPSIS | Symbol::DOT) => {}
+        Value::Symbol(s) if !literals.contains(s) => vars.push(s.clone()),
+        Value::List(l) => {
+            for item in l.iter() {
+                pattern_vars(item, literals, vars);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn match_syntax(
+    pattern: &Value,
+    form: &Value,
+    literals: &[Symbol],
+    bindings: &mut HashMap<Symbol, Binding>,
+) -> bool {
+    match pattern {
+        Value::Symbol(Symbol::WILDCARD) => true,
+        Value::Symbol(s) if literals.contains(s) => pattern == form,
+        Value::Symbol(s) => {
+            bindings.insert(s.clone(), Binding::One(form.clone()));
+            true
+        }
+
+        Value::List(p) => {
+            let form: &[Value] = match form {
+                Value::List(l) => l,
+                Value::Nil => &[],
+                _ => return false,
+            };
+
+            // (a b . rest)
+            if p.len() >= 2 && p[p.len() - 2] == Value::Symbol(Symbol::DOT) {
+                let fixed = &p[..p.len() - 2];
+                if form.len() < fixed.len() {
+                    return false;
+                }
+
+                for (item, value) in fixed.iter().zip(form) {
+                    if !match_syntax(item, value, literals, bindings) {
+                        return false;
+                    }
+                }
+
+                return match_syntax(
+                    &p[p.len() - 1],
+                    &Value::List(List::from(&form[fixed.len()..])),
+                    literals,
+                    bindings,
+                );
+            }
+
+            // (a b ... c), where b matches any number of items
+            match (0..p.len()).find(|i| is_ellipsis(p.get(i + 1))) {
+                Some(e) => {
+                    let before = &p[..e];
+                    let after = &p[(e + 2)..];
+                    if form.len() < before.len() + after.len() {
+                        return false;
+                    }
+
+                    for (item, value) in before.iter().zip(form) {
+                        if !match_syntax(item, value, literals, bindings) {
+                            return false;
+                        }
+                    }
+
+                    let repeated = &form[before.len()..(form.len() - after.len())];
+                    let mut matches = vec![];
+                    for value in repeated {
+                        let mut inner = HashMap::new();
+                        if !match_syntax(&p[e], value, literals, &mut inner) {
+                            return false;
+                        }
+                        matches.push(inner);
+                    }
+
+                    let mut vars = vec![];
+                    pattern_vars(&p[e], literals, &mut vars);
+                    for var in vars {
+                        let items = matches
+                            .iter_mut()
+                            .map(|m| m.remove(&var).unwrap())
+                            .collect();
+                        bindings.insert(var, Binding::Many(items));
+                    }
+
+                    for (item, value) in after.iter().zip(&form[(form.len() - after.len())..]) {
+                        if !match_syntax(item, value, literals, bindings) {
+                            return false;
+                        }
+                    }
+
+                    true
+                }
+
+                None => {
+                    if form.len() != p.len() {
+                        return false;
+                    }
+
+                    for (item, value) in p.iter().zip(form) {
+                        if !match_syntax(item, value, literals, bindings) {
+                            return false;
+                        }
+                    }
+
+                    true
+                }
+            }
+        }
+
+        _ => pattern == form,
+    }
+}
+
+fn binding_names(list: &[Value], bindings: &HashMap<Symbol, Binding>, names: &mut Vec<Symbol>) {
+    for item in list {
+        if let Value::Symbol(s) = item {
+            if !s.as_str().starts_with('&') && *s != Symbol::ELLIPSIS && !bindings.contains_key(s) {
+                names.push(s.clone());
+            }
+        }
+    }
+}
+
+// the names a match pattern binds, leaving out quoted values and the keys of &key patterns
+fn pattern_binders(pattern: &Value, bindings: &HashMap<Symbol, Binding>, names: &mut Vec<Symbol>) {
+    match pattern {
+        Value::Symbol(Symbol::WILDCARD | Symbol::DOT) => {}
+        Value::Symbol(_) => binding_names(std::slice::from_ref(pattern), bindings, names),
+        Value::List(l) if l.first() == Some(&Value::Symbol(Symbol::QUOTE)) => {}
+        Value::List(l) => {
+            for item in l.iter() {
+                pattern_binders(item, bindings, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn clause_pattern(clause: &Value) -> Option<&Value> {
+    match clause {
+        Value::List(c) => c.first(),
+        _ => None,
+    }
+}
+
+/// Finds the symbols that the template itself introduces in a binding position (let bindings, lambda
+/// parameters, match patterns, defined names, ...). These get renamed on every expansion so they can't capture
+/// the user's variables.
+fn introduced_binders(
+    template: &Value,
+    bindings: &HashMap<Symbol, Binding>,
+    names: &mut Vec<Symbol>,
+) {
+    if let Value::List(l) = template {
+        if let Some(Value::Symbol(head)) = l.first() {
+            match (head.clone(), l.get(1)) {
+                (Symbol::LET | Symbol::LET_STAR | Symbol::LETREC, Some(Value::List(second))) => {
+                    for binding in second.iter() {
+                        if let Value::List(b) = binding {
+                            binding_names(&b[..1.min(b.len())], bindings, names);
+                        }
+                    }
+                }
+                (
+                    Symbol::LAMBDA | Symbol::MACRO | Symbol::WITH_GENSYMS,
+                    Some(Value::List(second)),
+                ) => binding_names(second, bindings, names),
+                (Symbol::LABELS, Some(Value::List(second))) => {
+                    for definition in second.iter() {
+                        if let Value::List(d) = definition {
+                            binding_names(&d[..1.min(d.len())], bindings, names);
+                            if let Some(Value::List(params)) = d.get(1) {
+                                binding_names(params, bindings, names);
+                            }
+                        }
+                    }
+                }
+                (Symbol::DEF, Some(name @ Value::Symbol(_))) => {
+                    binding_names(std::slice::from_ref(name), bindings, names)
+                }
+                (Symbol::MATCH, Some(_)) => {
+                    for clause in &l[2..] {
+                        if let Some(pattern) = clause_pattern(clause) {
+                            pattern_binders(pattern, bindings, names);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for item in l.iter() {
+            introduced_binders(item, bindings, names);
+        }
+    }
+}
+
+// what the names in a template that aren't pattern variables refer to
+struct Scope<'a> {
+    // the names the template binds itself, with the fresh names they get
+    renames: HashMap<Symbol, Symbol>,
+    // the environment the syntax-rules was made in
+    definition: &'a Rc<RefCell<Env>>,
+    // whether a name is a local variable where the macro is used
+    is_local: &'a dyn Fn(Symbol) -> bool,
+}
+
+impl Scope<'_> {
+    // a free identifier refers to what it's bound to where the macro was defined. If a local variable where
+    // the macro is used has the same name, the symbol would refer to that one instead, so it's replaced with
+    // the value it's bound to in the macro's environment. Macros are left as names, so they still get expanded
+    fn resolve(&self, name: Symbol) -> Value {
+        if let Some(renamed) = self.renames.get(&name) {
+            return Value::Symbol(renamed.clone());
+        }
+
+        if (self.is_local)(name.clone()) {
+            match self.definition.borrow().get(name.clone()) {
+                None | Some(Value::Lambda { is_macro: true, .. } | Value::Syntax { .. }) => {}
+                Some(value) => return value,
+            }
+        }
+        Value::Symbol(name)
+    }
+}
+
+// `quoted` is whether the template is data (quoted, or the name a def defines) rather than code
+fn expand_template(
+    template: &Value,
+    bindings: &HashMap<Symbol, Binding>,
+    scope: &Scope,
+    quoted: bool,
+) -> Result<Value, String> {
+    match template {
+        Value::Symbol(s) => match bindings.get(s) {
+            Some(Binding::One(value)) => Ok(value.clone()),
+            Some(Binding::Many(_)) => Err(format!(
+                "Pattern variable \"{}\" needs to be followed by an ellipsis in the template",
+                s
+            )),
+            None if quoted => Ok(Value::Symbol(scope.renames.get(s).unwrap_or(s).clone())),
+            None => Ok(scope.resolve(s.clone())),
+        },
+
+        Value::List(t) => {
+            // (... template) escapes the ellipsis, so it's copied as is
+            if t.len() == 2 && t[0] == Value::Symbol(Symbol::ELLIPSIS) {
+                return Ok(t[1].clone());
+            }
+
+            let head = t.first();
+            let quotes = quoted
+                || matches!(
+                    head,
+                    Some(Value::Symbol(Symbol::QUOTE | Symbol::QUASIQUOTE))
+                );
+            let defines = head == Some(&Value::Symbol(Symbol::DEF));
+
+            let mut list = vec![];
+            let mut i = 0;
+            while i < t.len() {
+                if !is_ellipsis(t.get(i + 1)) {
+                    let quoted = quotes || (defines && i == 1);
+                    list.push(expand_template(&t[i], bindings, scope, quoted)?);
+                    i += 1;
+                    continue;
+                }
+
+                // the item before an ellipsis is expanded once for every repetition of the pattern variables in it
+                let mut vars = vec![];
+                pattern_vars(&t[i], &[], &mut vars);
+                let mut count: Option<usize> = None;
+                for var in vars.iter() {
+                    if let Some(Binding::Many(items)) = bindings.get(var) {
+                        if count.is_some_and(|c| c != items.len()) {
+                            return Err(String::from(
+                                "Pattern variables under the same ellipsis matched a different number of items",
+                            ));
+                        }
+                        count = Some(items.len());
+                    }
+                }
+
+                let count = match count {
+                    Some(c) => c,
+                    None => {
+                        return Err(String::from(
+                            "An ellipsis in the template must follow a pattern variable that was matched with an ellipsis",
+                        ))
+                    }
+                };
+
+                for n in 0..count {
+                    let mut inner: HashMap<Symbol, Binding> = HashMap::new();
+                    for (name, binding) in bindings {
+                        let binding = match binding {
+                            Binding::Many(items) if vars.contains(name) => &items[n],
+                            _ => binding,
+                        };
+                        inner.insert(name.clone(), binding.clone());
+                    }
+                    list.push(expand_template(&t[i], &inner, scope, quotes)?);
+                }
+
+                i += 2;
+            }
+
+            Ok(Value::List(list.into()))
+        }
+
+        _ => Ok(template.clone()),
+    }
+}
+
+/// Expands a use of a syntax-rules macro. `definition` is the environment it was made in, and `is_local` tells
+/// whether a name is a local variable where it's used.
+pub fn expand_syntax(
+    literals: &[Symbol],
+    rules: &[(Value, Value)],
+    definition: &CapturedEnv,
+    form: &[Value],
+    is_local: &dyn Fn(Symbol) -> bool,
+) -> Result<Value, String> {
+    for (pattern, template) in rules {
+        // the first item of the pattern is the macro's name, so it's ignored
+        let pattern = match pattern {
+            Value::List(p) if !p.is_empty() => &p[1..],
+            _ => continue,
+        };
+
+        let mut bindings = HashMap::new();
+        if !match_syntax(
+            &Value::List(List::from(pattern)),
+            &Value::List(List::from(&form[1..])),
+            literals,
+            &mut bindings,
+        ) {
+            continue;
+        }
+
+        let mut names = vec![];
+        introduced_binders(template, &bindings, &mut names);
+        let renames = names
+            .into_iter()
+            .map(|name| {
+                let fresh = fresh_symbol(name.as_str());
+                (name, fresh)
+            })
+            .collect();
+
+        let scope = Scope {
+            renames,
+            definition: &definition.0,
+            is_local,
+        };
+        return expand_template(template, &bindings, &scope, false);
+    }
+
+    Err(format!(
+        "No syntax-rules pattern matches {}",
+        value_to_string(&Value::List(List::from(form)))
+    ))
+}
+
+pub fn eval_syntax_rules(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // syntax-rules (literals...) (pattern template)...
+    if list.len() < 2 {
+        return Err(String::from("\"syntax-rules\" requires a list of literals"));
+    }
+
+    let literals = match &list[1] {
+        Value::List(l) => {
+            let mut literals = vec![];
+            for literal in l.iter() {
+                match literal {
+                    Value::Symbol(s) => literals.push(s.clone()),
+                    _ => return Err(String::from("Literals in \"syntax-rules\" must be symbols")),
+                }
+            }
+            literals
+        }
+        Value::Nil => vec![],
+        _ => {
+            return Err(String::from(
+                "First parameter to \"syntax-rules\" must be a list of literals",
+            ))
+        }
+    };
+
+    let mut rules = vec![];
+    for rule in &list[2..] {
+        match rule {
+            Value::List(r) if r.len() == 2 && matches!(r[0], Value::List(_)) => {
+                rules.push((r[0].clone(), r[1].clone()))
+            }
+            _ => {
+                return Err(String::from(
+                    "Rules need to be of the form ((name pattern...) template)",
+                ))
+            }
+        }
+    }
+
+    Ok(Value::Syntax {
+        literals: literals.into(),
+        rules: rules.into(),
+        env: CapturedEnv(env.clone()),
+    })
+}
+
+pub fn eval_define_syntax(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    // define-syntax name (syntax-rules ...)
+    if list.len() != 3 {
+        return Err(String::from("\"define-syntax\" requires 2 arguments"));
+    }
+
+    let name = match &list[1] {
+        Value::Symbol(s) => s.clone(),
+        _ => {
+            return Err(String::from(
+                "First parameter to \"define-syntax\" must be a symbol",
+            ))
+        }
+    };
+
+    let value = eval_value(&list[2], env)?;
+    if !matches!(value, Value::Syntax { .. }) {
+        return Err(String::from(
+            "Second parameter to \"define-syntax\" must be a syntax-rules transformer",
+        ));
+    }
+
+    env.borrow_mut().set(name, value.clone());
+    Ok(value)
+}
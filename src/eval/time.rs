@@ -0,0 +1,36 @@
+use super::eval_value;
+use crate::{env::*, parse::*};
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+pub fn eval_current_time(list: &[Value], _env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    if list.len() != 1 {
+        return Err(String::from("\"current-time\" doesn't take any arguments"));
+    }
+
+    // seconds since the unix epoch, with a fractional part
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?;
+    Ok(Value::Number(elapsed.as_secs_f64()))
+}
+
+pub fn eval_sleep(list: &[Value], env: &mut Rc<RefCell<Env>>) -> Result<Value, String> {
+    if list.len() != 2 {
+        return Err(String::from("\"sleep\" requires 1 argument"));
+    }
+
+    match eval_value(&list[1], env)? {
+        Value::Number(seconds) if seconds >= 0.0 && seconds.is_finite() => {
+            thread::sleep(Duration::from_secs_f64(seconds));
+            Ok(Value::Nil)
+        }
+        _ => Err(String::from(
+            "Argument to \"sleep\" must be a non-negative number of seconds",
+        )),
+    }
+}
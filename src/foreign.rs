@@ -0,0 +1,214 @@
+use crate::{
+    native::{FromValue, IntoValue, NativeFunction, TypedFn},
+    parse::Value,
+    symbol::Symbol,
+    util::value_to_string,
+};
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt::{Debug, Display},
+    marker::PhantomData,
+    ops::Deref,
+    rc::Rc,
+};
+
+type Equal = Box<dyn Fn(&dyn Any, &dyn Any) -> bool>;
+type Show = Box<dyn Fn(&dyn Any) -> String>;
+
+/// Describes a kind of host object: its name, the methods Lisp code can call on it with `send`, and how it's
+/// compared with `equal?` and printed. It's shared by every object of that kind.
+pub struct ForeignType {
+    pub name: String,
+    methods: HashMap<Symbol, NativeFunction>,
+    equal: Option<Equal>,
+    show: Option<Show>,
+}
+
+impl ForeignType {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: String::from(name),
+            methods: HashMap::new(),
+            equal: None,
+            show: None,
+        }
+    }
+
+    /// Adds a method, which gets the object it's called on as its first argument.
+    pub fn method(
+        mut self,
+        name: &str,
+        function: impl Fn(&[Value]) -> Result<Value, String> + 'static,
+    ) -> Self {
+        let function = NativeFunction::new(name, function);
+        self.methods.insert(function.name.clone(), function);
+        self
+    }
+
+    /// Adds a method with typed arguments (see [`TypedFn`]). The object is usually taken as a [`Handle`].
+    pub fn typed_method<Args, F: TypedFn<Args>>(mut self, name: &str, function: F) -> Self {
+        let function = NativeFunction::typed(name, function);
+        self.methods.insert(function.name.clone(), function);
+        self
+    }
+
+    /// Makes `equal?` compare objects of this type with `equal`. Otherwise they're only equal to themselves.
+    pub fn equal<T: Any>(mut self, equal: impl Fn(&T, &T) -> bool + 'static) -> Self {
+        self.equal = Some(Box::new(move |a, b| {
+            match (a.downcast_ref::<T>(), b.downcast_ref::<T>()) {
+                (Some(a), Some(b)) => equal(a, b),
+                _ => false,
+            }
+        }));
+        self
+    }
+
+    /// Makes objects of this type print as what `show` returns, instead of just their type name.
+    pub fn show<T: Any>(mut self, show: impl Fn(&T) -> String + 'static) -> Self {
+        self.show = Some(Box::new(move |object| match object.downcast_ref::<T>() {
+            Some(object) => show(object),
+            None => String::new(),
+        }));
+        self
+    }
+
+    pub fn get_method(&self, name: Symbol) -> Option<&NativeFunction> {
+        self.methods.get(&name)
+    }
+
+    /// Wraps an object as a value of this type.
+    pub fn wrap<T: Any>(self: &Rc<Self>, object: T) -> Value {
+        Value::Foreign(Rc::new(Foreign::new(self, object)))
+    }
+}
+
+impl Debug for ForeignType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#<foreign-type {}>", self.name)
+    }
+}
+
+/// An object owned by the host, which Lisp code can only pass around, compare, print and call methods on.
+pub struct Foreign {
+    pub type_: Rc<ForeignType>,
+    object: Box<dyn Any>,
+}
+
+impl Foreign {
+    pub fn new<T: Any>(type_: &Rc<ForeignType>, object: T) -> Self {
+        Self {
+            type_: type_.clone(),
+            object: Box::new(object),
+        }
+    }
+
+    pub fn is<T: Any>(&self) -> bool {
+        self.object.is::<T>()
+    }
+
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.object.downcast_ref()
+    }
+}
+
+impl Display for Foreign {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.type_.show {
+            Some(show) => write!(f, "#<{} {}>", self.type_.name, show(self.object.as_ref())),
+            None => write!(f, "#<{}>", self.type_.name),
+        }
+    }
+}
+
+impl Debug for Foreign {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl PartialEq for Foreign {
+    fn eq(&self, other: &Self) -> bool {
+        if std::ptr::eq(self, other) {
+            return true;
+        }
+
+        match &self.type_.equal {
+            Some(equal) if Rc::ptr_eq(&self.type_, &other.type_) => {
+                equal(self.object.as_ref(), other.object.as_ref())
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A foreign object known to hold a `T`, for native functions to take as an argument. It dereferences to the
+/// object, so use a `RefCell` in `T` for anything that needs to be mutated.
+pub struct Handle<T> {
+    foreign: Rc<Foreign>,
+    marker: PhantomData<T>,
+}
+
+impl<T: Any> Handle<T> {
+    pub fn foreign(&self) -> &Rc<Foreign> {
+        &self.foreign
+    }
+}
+
+impl<T: Any> Deref for Handle<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // FromValue only makes handles to objects of the right type
+        self.foreign.downcast_ref().unwrap()
+    }
+}
+
+impl<T: Any> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            foreign: self.foreign.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Any> FromValue for Handle<T> {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Foreign(foreign) if foreign.is::<T>() => Ok(Self {
+                foreign: foreign.clone(),
+                marker: PhantomData,
+            }),
+            _ => Err(format!(
+                "expected a {}, got {}",
+                std::any::type_name::<T>(),
+                value_to_string(value)
+            )),
+        }
+    }
+}
+
+impl<T: Any> IntoValue for Handle<T> {
+    fn into_value(self) -> Value {
+        Value::Foreign(self.foreign)
+    }
+}
+
+impl FromValue for Rc<Foreign> {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Foreign(foreign) => Ok(foreign.clone()),
+            _ => Err(format!(
+                "expected a foreign object, got {}",
+                value_to_string(value)
+            )),
+        }
+    }
+}
+
+impl IntoValue for Rc<Foreign> {
+    fn into_value(self) -> Value {
+        Value::Foreign(self)
+    }
+}
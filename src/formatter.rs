@@ -0,0 +1,153 @@
+use crate::{
+    cst::{parse_cst, Item, Node, Trivia},
+    pretty::distinguished_args,
+    symbol::Symbol,
+};
+
+/// Formats code the canonical way: the line breaks are kept as they are (apart from having at most one
+/// blank line in a row), but everything is reindented, there's a single space between forms on the same
+/// line, and closing parentheses go right after the last form. Lines are indented like the pretty printer
+/// lays them out, so the body of a special form like let or lambda is indented by 2, arguments are aligned
+/// with the first one if it's on the same line as the function, and items of other lists are aligned with
+/// the first item.
+pub fn format_code(code: &str) -> Result<String, String> {
+    let cst = parse_cst(code)?;
+    let mut formatter = Formatter {
+        output: String::new(),
+    };
+
+    for item in &cst.items {
+        let newlines = formatter.trivia(&item.trivia, 0);
+        if !formatter.output.is_empty() {
+            formatter.newline(newlines >= 2, 0);
+        }
+        formatter.node(&item.node);
+    }
+    formatter.trivia(&cst.end, 0);
+
+    if !formatter.output.is_empty() {
+        formatter.newline(false, 0);
+    }
+    Ok(formatter.output)
+}
+
+// how the items of a list after the first one are indented
+enum Style {
+    // relative to the opening parenthesis
+    Indent(usize),
+    // aligned with the second item if it's on the same line as the first one, otherwise with the first one
+    Call,
+}
+
+fn is_symbol(text: &str) -> bool {
+    !text.starts_with(['"', '\'', '`', ',', ':'])
+        && text.parse::<f64>().is_err()
+        && text != "nil"
+        && text != "t"
+}
+
+struct Formatter {
+    output: String,
+}
+
+impl Formatter {
+    fn column(&self) -> usize {
+        let line = match self.output.rfind('\n') {
+            Some(i) => &self.output[i + 1..],
+            None => &self.output,
+        };
+        line.chars().count()
+    }
+
+    fn newline(&mut self, blank: bool, indent: usize) {
+        // lines never end with whitespace
+        let length = self.output.trim_end_matches(' ').len();
+        self.output.truncate(length);
+
+        self.output.push('\n');
+        if blank {
+            self.output.push('\n');
+        }
+        self.output.extend(std::iter::repeat_n(' ', indent));
+    }
+
+    // writes the comments in the trivia before a form (or a closing parenthesis), and returns how many
+    // line breaks there are between the last thing written and it, which is at least 1 if there was a comment
+    fn trivia(&mut self, trivia: &[Trivia], indent: usize) -> usize {
+        let mut newlines = 0;
+        let mut comment = false;
+        for trivia in trivia {
+            match trivia {
+                Trivia::Whitespace(s) => newlines += s.matches('\n').count(),
+                Trivia::Comment(s) => {
+                    if self.output.is_empty() {
+                        // the file starts with a comment
+                    } else if newlines == 0 {
+                        // a comment at the end of a line
+                        self.output.push(' ');
+                    } else {
+                        self.newline(newlines >= 2, indent);
+                    }
+                    self.output.push_str(s.trim_end());
+                    newlines = 0;
+                    comment = true;
+                }
+            }
+        }
+
+        if comment {
+            newlines.max(1)
+        } else {
+            newlines
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::Atom(s) => self.output.push_str(s),
+            Node::List { prefix, items, end } => self.list(prefix, items, end),
+        }
+    }
+
+    fn list(&mut self, prefix: &str, items: &[Item], end: &[Trivia]) {
+        self.output.push_str(prefix);
+        let open = self.column();
+        self.output.push('(');
+
+        let style = match items.first().map(|item| &item.node) {
+            Some(Node::Atom(head)) if is_symbol(head) => {
+                match distinguished_args(Symbol::new(head)) {
+                    Some(_) => Style::Indent(2),
+                    None => Style::Call,
+                }
+            }
+            _ => Style::Indent(1),
+        };
+
+        let mut indent = open + 1;
+        for (i, item) in items.iter().enumerate() {
+            let newlines = self.trivia(&item.trivia, indent);
+            if newlines > 0 {
+                self.newline(newlines >= 2, indent);
+            } else if i > 0 {
+                self.output.push(' ');
+            }
+
+            if i == 0 {
+                indent = match style {
+                    Style::Indent(n) => open + n,
+                    Style::Call => open + 1,
+                };
+            } else if i == 1 && newlines == 0 && matches!(style, Style::Call) {
+                indent = self.column();
+            }
+            self.node(&item.node);
+        }
+
+        // the closing parenthesis can only go right after the last form if there's no comment in between
+        if self.trivia(end, indent) > 0 && end.iter().any(|t| matches!(t, Trivia::Comment(_))) {
+            self.newline(false, indent);
+        }
+        self.output.push(')');
+    }
+}
@@ -0,0 +1,244 @@
+use crate::{env::Env, module::ModuleRegistry, parse::Value};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+// the fewest environments that get allocated between two automatic collections
+const MIN_THRESHOLD: usize = 10000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GcStats {
+    // environments created so far
+    pub allocated: usize,
+    // environments that haven't been freed yet
+    pub live: usize,
+    pub collections: usize,
+    // environments freed by collections, the ones that weren't part of a cycle are freed right away instead
+    pub freed: usize,
+}
+
+struct Heap {
+    envs: Vec<Weak<RefCell<Env>>>,
+    stats: GcStats,
+    since_collection: usize,
+    threshold: usize,
+}
+
+thread_local! {
+    static HEAP: RefCell<Heap> = const {
+        RefCell::new(Heap {
+            envs: vec![],
+            stats: GcStats {
+                allocated: 0,
+                live: 0,
+                collections: 0,
+                freed: 0,
+            },
+            since_collection: 0,
+            threshold: MIN_THRESHOLD,
+        })
+    };
+}
+
+/// Starts tracking a new environment, so it can be freed by [`collect`] if it ends up in a reference cycle.
+/// Collects automatically once enough environments were allocated since the last collection.
+pub fn track(env: &Rc<RefCell<Env>>) {
+    let should_collect = HEAP.with_borrow_mut(|heap| {
+        heap.envs.push(Rc::downgrade(env));
+        heap.stats.allocated += 1;
+        heap.since_collection += 1;
+        heap.since_collection >= heap.threshold
+    });
+
+    if should_collect {
+        collect();
+    }
+}
+
+pub fn stats() -> GcStats {
+    HEAP.with_borrow(|heap| GcStats {
+        live: heap
+            .envs
+            .iter()
+            .filter(|env| env.strong_count() > 0)
+            .count(),
+        ..heap.stats
+    })
+}
+
+// a reference counted object that's either mutable (so it can close a cycle) or can lead to one
+enum Node {
+    Env(Rc<RefCell<Env>>),
+    List(Rc<[Value]>),
+    Body(Rc<Value>),
+    Rules(Rc<[(Value, Value)]>),
+    Modules(Rc<RefCell<ModuleRegistry>>),
+}
+
+fn value_children(value: &Value, children: &mut Vec<Node>) {
+    match value {
+        // a list and its tails share their items, which is what the node is
+        Value::List(l) => children.push(Node::List(l.shared().clone())),
+        Value::Lambda { body, env, .. } => {
+            children.push(Node::Body(body.clone()));
+            children.push(Node::Env(env.0.clone()));
+        }
+        Value::Syntax { rules, env, .. } => {
+            children.push(Node::Rules(rules.clone()));
+            children.push(Node::Env(env.0.clone()));
+        }
+        Value::Env(env) => children.push(Node::Env(env.0.clone())),
+        // compiled closures aren't looked into, so everything they reference counts as referenced from outside
+        _ => {}
+    }
+}
+
+impl Node {
+    fn key(&self) -> *const () {
+        match self {
+            Node::Env(env) => Rc::as_ptr(env) as *const (),
+            Node::List(list) => Rc::as_ptr(list) as *const (),
+            Node::Body(body) => Rc::as_ptr(body) as *const (),
+            Node::Rules(rules) => Rc::as_ptr(rules) as *const (),
+            Node::Modules(modules) => Rc::as_ptr(modules) as *const (),
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Node::Env(env) => Rc::strong_count(env),
+            Node::List(list) => Rc::strong_count(list),
+            Node::Body(body) => Rc::strong_count(body),
+            Node::Rules(rules) => Rc::strong_count(rules),
+            Node::Modules(modules) => Rc::strong_count(modules),
+        }
+    }
+
+    /// Adds every node this one references directly. Returns false if it's an environment (or module
+    /// registry) that's borrowed right now, in which case its references can't be seen.
+    fn children(&self, children: &mut Vec<Node>) -> bool {
+        match self {
+            Node::Env(env) => {
+                let Ok(env) = env.try_borrow() else {
+                    return false;
+                };
+                match env.parent() {
+                    Some(parent) => children.push(Node::Env(parent)),
+                    None => children.extend(env.modules().map(Node::Modules)),
+                }
+                for value in env.values() {
+                    value_children(value, children);
+                }
+            }
+            Node::List(list) => {
+                for value in list.iter() {
+                    value_children(value, children);
+                }
+            }
+            Node::Body(body) => value_children(body, children),
+            Node::Rules(rules) => {
+                for (pattern, template) in rules.iter() {
+                    value_children(pattern, children);
+                    value_children(template, children);
+                }
+            }
+            Node::Modules(modules) => {
+                let Ok(modules) = modules.try_borrow() else {
+                    return false;
+                };
+                for value in modules.values() {
+                    value_children(value, children);
+                }
+            }
+        }
+
+        true
+    }
+}
+
+/// Finds the tracked environments that can only be reached through reference cycles, and breaks the cycles
+/// by clearing them, so they get freed. Returns the number of environments that were freed.
+///
+/// An object is reachable from outside of the heap if it has more strong references than there are
+/// references to it from the other objects, and everything it references is reachable as well.
+pub fn collect() -> usize {
+    let envs: Vec<Rc<RefCell<Env>>> = HEAP.with_borrow_mut(|heap| {
+        heap.envs.retain(|env| env.strong_count() > 0);
+        heap.envs.iter().filter_map(Weak::upgrade).collect()
+    });
+
+    // find every object reachable from the environments and count the references between them. Exactly one
+    // clone of each object is kept in `nodes`, so that's the only strong reference the collector adds
+    let mut nodes: Vec<Node> = envs.into_iter().map(Node::Env).collect();
+    let mut index: HashMap<*const (), usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.key(), i))
+        .collect();
+    let mut internal = vec![0; nodes.len()];
+    let mut edges: Vec<Vec<usize>> = vec![vec![]; nodes.len()];
+    let mut opaque = vec![false; nodes.len()];
+
+    let mut i = 0;
+    while i < nodes.len() {
+        let mut children = vec![];
+        opaque[i] = !nodes[i].children(&mut children);
+
+        for child in children {
+            let j = match index.get(&child.key()) {
+                Some(j) => *j,
+                None => {
+                    index.insert(child.key(), nodes.len());
+                    nodes.push(child);
+                    internal.push(0);
+                    edges.push(vec![]);
+                    opaque.push(false);
+                    nodes.len() - 1
+                }
+            };
+            internal[j] += 1;
+            edges[i].push(j);
+        }
+
+        i += 1;
+    }
+
+    // everything referenced from outside, and everything reachable from that, is still in use
+    let mut reachable = vec![false; nodes.len()];
+    let mut stack: Vec<usize> = (0..nodes.len())
+        .filter(|i| opaque[*i] || nodes[*i].strong_count() - 1 > internal[*i])
+        .collect();
+    while let Some(i) = stack.pop() {
+        if reachable[i] {
+            continue;
+        }
+        reachable[i] = true;
+        stack.extend(edges[i].iter().filter(|j| !reachable[**j]));
+    }
+
+    // clearing the unreachable environments drops their references, which frees the whole cycle once the
+    // collector drops its own clones
+    let mut cleared = vec![];
+    for (node, reachable) in nodes.iter().zip(reachable) {
+        if let (Node::Env(env), false) = (node, reachable) {
+            if let Ok(mut env) = env.try_borrow_mut() {
+                cleared.push(std::mem::take(&mut *env));
+            }
+        }
+    }
+    let freed = cleared.len();
+    drop(cleared);
+    drop(nodes);
+
+    HEAP.with_borrow_mut(|heap| {
+        heap.envs.retain(|env| env.strong_count() > 0);
+        heap.stats.collections += 1;
+        heap.stats.freed += freed;
+        heap.since_collection = 0;
+        heap.threshold = MIN_THRESHOLD.max(heap.envs.len());
+    });
+
+    freed
+}
@@ -0,0 +1,291 @@
+use crate::{
+    env::*,
+    eval::{eval_toplevel, expand_value},
+    parse::*,
+    tokenize::tokenize,
+};
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// Limits on a single evaluation, None means unlimited.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    // the number of forms that can be evaluated
+    pub max_steps: Option<u64>,
+    // how deeply forms (while reading, expanding and evaluating them) and lambda and macro calls can be nested
+    pub max_depth: Option<usize>,
+    // how many bytes of the Rust stack the evaluation can use. How much every level of nesting uses depends on
+    // the form and the build (debug builds use a lot more), so this is what keeps deeply nested code from
+    // overflowing the stack. The thread that's evaluating needs this much stack left, plus some to spare
+    pub max_stack: Option<usize>,
+    // the number of list items that can be created
+    pub max_cells: Option<usize>,
+    // the length of the longest string that can be created
+    pub max_string_length: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_steps: None,
+            max_depth: None,
+            // a quarter of the stack of the main thread, and half of what other threads get by default
+            max_stack: Some(1 << 20),
+            max_cells: None,
+            max_string_length: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Steps,
+    Depth,
+    Stack,
+    Cells,
+    StringLength,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalError {
+    Error(String),
+    LimitExceeded(Limit),
+    // the interrupt flag was set (see [`Interpreter::interrupt_handle`])
+    Interrupted,
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvalError::Error(message) => write!(f, "{}", message),
+            EvalError::LimitExceeded(Limit::Steps) => write!(f, "Evaluation step limit exceeded"),
+            EvalError::LimitExceeded(Limit::Depth) => write!(f, "Nesting depth limit exceeded"),
+            EvalError::LimitExceeded(Limit::Stack) => write!(f, "Stack limit exceeded"),
+            EvalError::LimitExceeded(Limit::Cells) => write!(f, "List cell limit exceeded"),
+            EvalError::LimitExceeded(Limit::StringLength) => {
+                write!(f, "String length limit exceeded")
+            }
+            EvalError::Interrupted => write!(f, "Evaluation was interrupted"),
+        }
+    }
+}
+
+// what the evaluation that's currently running has used up
+struct Budget {
+    limits: Limits,
+    interrupt: Arc<AtomicBool>,
+    steps: u64,
+    depth: usize,
+    // where the stack was when the evaluation started, see stack_position
+    stack_base: usize,
+    cells: usize,
+    // why the evaluation was stopped, the evaluator itself only sees the error message
+    stopped: Option<EvalError>,
+}
+
+thread_local! {
+    static BUDGET: RefCell<Option<Budget>> = const { RefCell::new(None) };
+}
+
+fn charge(f: impl FnOnce(&mut Budget) -> Option<EvalError>) -> Result<(), String> {
+    BUDGET.with_borrow_mut(|budget| {
+        let Some(budget) = budget else {
+            return Ok(());
+        };
+
+        // once stopped, everything fails, so the evaluation can't carry on even if the error gets ignored
+        if budget.stopped.is_none() {
+            budget.stopped = f(budget);
+        }
+        match &budget.stopped {
+            Some(error) => Err(error.to_string()),
+            None => Ok(()),
+        }
+    })
+}
+
+fn exceeds<T: PartialOrd>(used: T, limit: Option<T>) -> bool {
+    limit.is_some_and(|limit| used > limit)
+}
+
+/// Counts one evaluation step, and checks whether the evaluation was interrupted.
+pub(crate) fn step() -> Result<(), String> {
+    charge(|budget| {
+        budget.steps += 1;
+        if budget.interrupt.swap(false, Ordering::Relaxed) {
+            Some(EvalError::Interrupted)
+        } else if exceeds(budget.steps, budget.limits.max_steps) {
+            Some(EvalError::LimitExceeded(Limit::Steps))
+        } else {
+            None
+        }
+    })
+}
+
+// the address of a local variable, which is as deep in the stack as the function calling this
+#[inline(never)]
+fn stack_position() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+/// Runs `f` one level of nesting deeper (reading, expanding or evaluating a list, or calling a lambda), checking
+/// the depth and how much of the stack has been used first.
+pub(crate) fn nested<T>(f: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    let position = stack_position();
+    charge(|budget| {
+        budget.depth += 1;
+        if exceeds(budget.depth, budget.limits.max_depth) {
+            Some(EvalError::LimitExceeded(Limit::Depth))
+        } else if exceeds(
+            budget.stack_base.abs_diff(position),
+            budget.limits.max_stack,
+        ) {
+            Some(EvalError::LimitExceeded(Limit::Stack))
+        } else {
+            None
+        }
+    })?;
+
+    let result = f();
+    BUDGET.with_borrow_mut(|budget| {
+        if let Some(budget) = budget {
+            budget.depth -= 1;
+        }
+    });
+    result
+}
+
+/// Counts `cells` new list items.
+pub(crate) fn allocate(cells: usize) -> Result<(), String> {
+    charge(|budget| {
+        budget.cells += cells;
+        if exceeds(budget.cells, budget.limits.max_cells) {
+            Some(EvalError::LimitExceeded(Limit::Cells))
+        } else {
+            None
+        }
+    })
+}
+
+pub(crate) fn check_string_length(length: usize) -> Result<(), String> {
+    charge(|budget| {
+        if exceeds(length, budget.limits.max_string_length) {
+            Some(EvalError::LimitExceeded(Limit::StringLength))
+        } else {
+            None
+        }
+    })
+}
+
+/// Evaluates code in a root environment that's kept between evaluations, enforcing the limits on each one.
+pub struct Interpreter {
+    env: Rc<RefCell<Env>>,
+    limits: Limits,
+    interrupt: Arc<AtomicBool>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self::with_env(Env::new())
+    }
+
+    /// Creates an interpreter that evaluates code in `env`, e.g. a sandboxed one (see
+    /// [`crate::sandbox::EnvBuilder`]).
+    pub fn with_env(env: Rc<RefCell<Env>>) -> Self {
+        Self {
+            env,
+            limits: Limits::default(),
+            interrupt: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn with_limits(limits: Limits) -> Self {
+        Self {
+            limits,
+            ..Self::new()
+        }
+    }
+
+    pub fn env(&mut self) -> &mut Rc<RefCell<Env>> {
+        &mut self.env
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Returns a flag that can be set from another thread to stop the evaluation that's running (or the next
+    /// one) with [`EvalError::Interrupted`]. It's cleared once it stops an evaluation.
+    pub fn interrupt_handle(&self) -> Arc<AtomicBool> {
+        self.interrupt.clone()
+    }
+
+    // runs f with a fresh budget
+    fn run<T>(
+        &mut self,
+        f: impl FnOnce(&mut Rc<RefCell<Env>>) -> Result<T, String>,
+    ) -> Result<T, EvalError> {
+        let budget = Budget {
+            limits: self.limits,
+            interrupt: self.interrupt.clone(),
+            steps: 0,
+            depth: 0,
+            stack_base: stack_position(),
+            cells: 0,
+            stopped: None,
+        };
+
+        let outer = BUDGET.replace(Some(budget));
+        let result = f(&mut self.env);
+        let budget = BUDGET.replace(outer).unwrap();
+
+        result.map_err(|message| budget.stopped.unwrap_or(EvalError::Error(message)))
+    }
+
+    /// Expands and evaluates a top-level form.
+    pub fn eval(&mut self, form: &Value) -> Result<Value, EvalError> {
+        self.run(|env| eval_toplevel(form, env))
+    }
+
+    /// Expands all the macros in a top-level form without evaluating it (see [`expand_value`]).
+    pub fn expand(&mut self, form: &Value) -> Result<Value, EvalError> {
+        self.run(|env| expand_value(form, env))
+    }
+
+    /// Reads and evaluates a top-level form.
+    pub fn eval_str(&mut self, code: &str) -> Result<Value, EvalError> {
+        self.run(|env| {
+            let mut tokens = tokenize(String::from(code))?;
+            tokens.reverse();
+            eval_toplevel(&parse(&mut tokens)?, env)
+        })
+    }
+
+    /// Reads every top-level form in the code (see [`parse_all`]), with the depth and stack limits applying to
+    /// reading them.
+    pub fn parse_all(&mut self, code: &str) -> Result<Vec<Value>, EvalError> {
+        self.run(|_| {
+            let mut tokens = tokenize(String::from(code))?;
+            tokens.reverse();
+            parse_all(&mut tokens)
+        })
+    }
+}
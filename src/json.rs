@@ -0,0 +1,444 @@
+use crate::{interpreter, parse::Value, symbol::Symbol, util::value_to_string};
+use std::fmt::Write;
+
+// arrays and objects nested deeper than this are an error, so malformed input can't overflow the stack
+const MAX_DEPTH: usize = 512;
+
+// the name of the keyword false is converted to and from, since nil is already null
+const FALSE: &str = "false";
+// the name of the keyword at the head of the lists objects are converted to and from
+const OBJECT: &str = "object";
+
+/// How the keys of JSON objects are represented in the property lists they're converted to (and expected
+/// to be when converting back).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Keys {
+    // (:a 1 :b 2)
+    #[default]
+    Keyword,
+    // ("a" 1 "b" 2)
+    String,
+}
+
+/// Converts JSON text to a value. Objects become property lists that start with :object (so they can't be
+/// mistaken for arrays), arrays become lists, true becomes t, false becomes :false and null becomes nil, so
+/// everything is converted back as it was. For example, {"a": [1, false]} becomes (:object :a (1 :false)).
+pub fn parse(text: &str, keys: Keys) -> Result<Value, String> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        line: 1,
+        column: 1,
+        keys,
+    };
+
+    parser.skip_whitespace();
+    let value = parser.parse_value(0)?;
+    parser.skip_whitespace();
+    if parser.peek().is_some() {
+        return Err(parser.error("unexpected characters after the value"));
+    }
+    Ok(value)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    // of the next character
+    line: usize,
+    column: usize,
+    keys: Keys,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> String {
+        format!(
+            "JSON error at line {}, column {}: {}",
+            self.line, self.column, message
+        )
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.next();
+                Ok(())
+            }
+            _ => Err(self.unexpected(&format!("'{}'", expected))),
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> String {
+        match self.peek() {
+            Some(c) => self.error(&format!("expected {}, found '{}'", expected, c)),
+            None => self.error(&format!(
+                "expected {}, found the end of the input",
+                expected
+            )),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.next();
+        }
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<Value, String> {
+        match self.peek() {
+            Some('{') => self.parse_object(depth + 1),
+            Some('[') => self.parse_array(depth + 1),
+            Some('"') => Ok(Value::String(self.parse_string()?.into())),
+            Some('-' | '0'..='9') => self.parse_number(),
+            Some('t') => self.parse_literal("true", Value::T),
+            Some('f') => self.parse_literal("false", Value::Keyword(Symbol::new(FALSE))),
+            Some('n') => self.parse_literal("null", Value::Nil),
+            _ => Err(self.unexpected("a value")),
+        }
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: Value) -> Result<Value, String> {
+        for expected in literal.chars() {
+            if self.peek() != Some(expected) {
+                return Err(self.unexpected(&format!("\"{}\"", literal)));
+            }
+            self.next();
+        }
+        Ok(value)
+    }
+
+    fn parse_number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        let digits = |parser: &mut Parser| {
+            if !matches!(parser.peek(), Some('0'..='9')) {
+                return Err(parser.unexpected("a digit"));
+            }
+            while matches!(parser.peek(), Some('0'..='9')) {
+                parser.next();
+            }
+            Ok(())
+        };
+
+        if self.peek() == Some('-') {
+            self.next();
+        }
+        // there can't be any digits after a leading zero
+        if self.peek() == Some('0') {
+            self.next();
+        } else {
+            digits(self)?;
+        }
+        if self.peek() == Some('.') {
+            self.next();
+            digits(self)?;
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            self.next();
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.next();
+            }
+            digits(self)?;
+        }
+
+        let number: String = self.chars[start..self.pos].iter().collect();
+        Ok(Value::Number(number.parse().unwrap()))
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+
+        let mut s = String::new();
+        loop {
+            match self.peek() {
+                Some('"') => {
+                    self.next();
+                    break;
+                }
+                Some('\\') => {
+                    self.next();
+                    s.push(self.parse_escape()?);
+                }
+                Some(c) if (c as u32) < 0x20 => {
+                    return Err(self.error("control characters in strings must be escaped"))
+                }
+                Some(c) => {
+                    self.next();
+                    s.push(c);
+                }
+                None => return Err(self.unexpected("'\"'")),
+            }
+        }
+
+        interpreter::check_string_length(s.chars().count())?;
+        Ok(s)
+    }
+
+    fn parse_escape(&mut self) -> Result<char, String> {
+        let c = match self.peek() {
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('/') => '/',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('u') => {
+                self.next();
+                return self.parse_unicode_escape();
+            }
+            _ => return Err(self.unexpected("an escape sequence")),
+        };
+        self.next();
+        Ok(c)
+    }
+
+    // the part of a \uXXXX escape after the u, which can be the first half of a surrogate pair
+    fn parse_unicode_escape(&mut self) -> Result<char, String> {
+        let (line, column) = (self.line, self.column);
+        let high = self.parse_hex()?;
+        let code = if (0xd800..0xdc00).contains(&high) {
+            if self.peek() != Some('\\') {
+                return Err(self.unexpected("the second half of a surrogate pair"));
+            }
+            self.next();
+            self.expect('u')?;
+            let low = self.parse_hex()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(format!(
+                    "JSON error at line {}, column {}: invalid surrogate pair",
+                    line, column
+                ));
+            }
+            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            high
+        };
+
+        char::from_u32(code).ok_or_else(|| {
+            format!(
+                "JSON error at line {}, column {}: unpaired surrogate",
+                line, column
+            )
+        })
+    }
+
+    fn parse_hex(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..4 {
+            match self.peek().and_then(|c| c.to_digit(16)) {
+                Some(digit) => code = code * 16 + digit,
+                None => return Err(self.unexpected("a hexadecimal digit")),
+            }
+            self.next();
+        }
+        Ok(code)
+    }
+
+    fn parse_array(&mut self, depth: usize) -> Result<Value, String> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("arrays and objects are nested too deeply"));
+        }
+        self.expect('[')?;
+        self.skip_whitespace();
+
+        let mut items = vec![];
+        if self.peek() == Some(']') {
+            self.next();
+            return Ok(Value::List(vec![].into()));
+        }
+        loop {
+            items.push(self.parse_value(depth)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.next();
+                    self.skip_whitespace();
+                }
+                Some(']') => {
+                    self.next();
+                    break;
+                }
+                _ => return Err(self.unexpected("',' or ']'")),
+            }
+        }
+
+        interpreter::allocate(items.len())?;
+        Ok(Value::List(items.into()))
+    }
+
+    fn parse_object(&mut self, depth: usize) -> Result<Value, String> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("arrays and objects are nested too deeply"));
+        }
+        self.expect('{')?;
+        self.skip_whitespace();
+
+        let mut object = vec![Value::Keyword(Symbol::new(OBJECT))];
+        if self.peek() == Some('}') {
+            self.next();
+            return Ok(Value::List(object.into()));
+        }
+        loop {
+            if self.peek() != Some('"') {
+                return Err(self.unexpected("a string key"));
+            }
+            let key = self.parse_string()?;
+            object.push(match self.keys {
+                Keys::Keyword => Value::Keyword(Symbol::new(&key)),
+                Keys::String => Value::String(key.into()),
+            });
+
+            self.skip_whitespace();
+            self.expect(':')?;
+            self.skip_whitespace();
+            object.push(self.parse_value(depth)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.next();
+                    self.skip_whitespace();
+                }
+                Some('}') => {
+                    self.next();
+                    break;
+                }
+                _ => return Err(self.unexpected("',' or '}'")),
+            }
+        }
+
+        interpreter::allocate(object.len())?;
+        Ok(Value::List(object.into()))
+    }
+}
+
+/// Converts a value to JSON text. Lists that start with :object become objects (with the keys of the given kind
+/// and values after it), other lists become arrays, nil becomes null, t becomes true, :false becomes false, and
+/// other symbols and keywords become strings of their names.
+/// With `pretty`, every array item and object member goes on its own line, indented by 2 spaces.
+pub fn stringify(value: &Value, keys: Keys, pretty: bool) -> Result<String, String> {
+    let mut json = String::new();
+    write_value(&mut json, value, keys, pretty.then_some(0))?;
+    interpreter::check_string_length(json.chars().count())?;
+    Ok(json)
+}
+
+fn is_object(list: &[Value]) -> bool {
+    matches!(list.first(), Some(Value::Keyword(k)) if k.as_str() == OBJECT)
+}
+
+fn write_key(json: &mut String, key: &Value, keys: Keys) -> Result<(), String> {
+    match (key, keys) {
+        (Value::Keyword(k), Keys::Keyword) => write_string(json, k.as_str()),
+        (Value::String(s), Keys::String) => write_string(json, s),
+        (_, Keys::Keyword) => return Err(String::from("Keys of JSON objects must be keywords")),
+        (_, Keys::String) => return Err(String::from("Keys of JSON objects must be strings")),
+    }
+    Ok(())
+}
+
+fn write_string(json: &mut String, s: &str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            '\u{8}' => json.push_str("\\b"),
+            '\u{c}' => json.push_str("\\f"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+// starts a new line with the given indentation if it's pretty printing
+fn write_newline(json: &mut String, indent: Option<usize>) {
+    if let Some(indent) = indent {
+        json.push('\n');
+        json.extend(std::iter::repeat_n(' ', indent * 2));
+    }
+}
+
+fn write_value(
+    json: &mut String,
+    value: &Value,
+    keys: Keys,
+    indent: Option<usize>,
+) -> Result<(), String> {
+    let inner = indent.map(|indent| indent + 1);
+    match value {
+        Value::Nil => json.push_str("null"),
+        Value::T => json.push_str("true"),
+        Value::Keyword(k) if k.as_str() == FALSE => json.push_str("false"),
+        Value::Number(n) if n.is_finite() => write!(json, "{}", n).unwrap(),
+        Value::Number(n) => return Err(format!("{} can't be converted to JSON", n)),
+        Value::String(s) => write_string(json, s),
+        Value::Symbol(s) | Value::Keyword(s) => write_string(json, s.as_str()),
+
+        Value::List(l) if is_object(l) => {
+            let members = &l[1..];
+            if !members.len().is_multiple_of(2) {
+                return Err(String::from("Every key of a JSON object needs a value"));
+            }
+
+            json.push('{');
+            for (i, pair) in members.chunks(2).enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                write_newline(json, inner);
+                write_key(json, &pair[0], keys)?;
+                json.push_str(if indent.is_some() { ": " } else { ":" });
+                write_value(json, &pair[1], keys, inner)?;
+            }
+            if !members.is_empty() {
+                write_newline(json, indent);
+            }
+            json.push('}');
+        }
+
+        Value::List(l) => {
+            json.push('[');
+            for (i, item) in l.iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                write_newline(json, inner);
+                write_value(json, item, keys, inner)?;
+            }
+            if !l.is_empty() {
+                write_newline(json, indent);
+            }
+            json.push(']');
+        }
+
+        _ => {
+            return Err(format!(
+                "{} can't be converted to JSON",
+                value_to_string(value)
+            ))
+        }
+    }
+
+    Ok(())
+}
@@ -1,5 +1,24 @@
+pub mod cst;
 pub mod env;
 pub mod eval;
+pub mod foreign;
+pub mod formatter;
+pub mod gc;
+pub mod interpreter;
+pub mod json;
+pub mod module;
+pub mod native;
 pub mod parse;
+pub mod port;
+mod prelude;
+pub mod pretty;
+pub mod sandbox;
+pub mod symbol;
 pub mod tokenize;
 pub mod util;
+pub mod vm;
+
+#[cfg(feature = "serde")]
+pub mod serde;
+#[cfg(feature = "serde")]
+pub use crate::serde::{from_value, to_value};
@@ -1,13 +1,162 @@
-use euphie::{env::*, eval::*, parse::*, tokenize::*, util::*};
-use std::{cell::RefCell, fs, rc::Rc};
+use euphie::{formatter::*, interpreter::*, parse::*, pretty::*, sandbox::*, vm::*};
+use std::{env, fs, path::Path, process};
+
+// values that don't fit on the line after the label are printed starting on the next one
+fn print_result(value: &Value, interpreter: &mut Interpreter) {
+    let value = pretty_print(value, &PrintOptions::from_env(&interpreter.env().borrow()));
+    if value.contains('\n') {
+        println!("Result:\n{}", value);
+    } else {
+        println!("Result: {}", value);
+    }
+}
+
+// euphie fmt [--check] files... formats the files in place, or with --check, only lists the ones that aren't
+// formatted. The exit code is 1 if any of them isn't (with --check) or couldn't be formatted.
+fn fmt(args: &[String]) -> i32 {
+    let check = args.iter().any(|arg| arg == "--check");
+    let paths: Vec<&String> = args.iter().filter(|arg| !arg.starts_with("--")).collect();
+    if paths.is_empty() {
+        eprintln!("Usage: euphie fmt [--check] files...");
+        return 2;
+    }
+
+    let mut status = 0;
+    for path in paths {
+        let result = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|code| Ok((format_code(&code)?, code)));
+        match result {
+            Ok((formatted, code)) if formatted != code => {
+                if check {
+                    println!("{} isn't formatted", path);
+                    status = 1;
+                } else if let Err(e) = fs::write(path, formatted) {
+                    eprintln!("{}: {}", path, e);
+                    status = 1;
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                status = 1;
+            }
+        }
+    }
+    status
+}
 
 fn main() {
-    let code = fs::read_to_string("test.lisp").expect("Could not read file.");
-    let mut tokens = tokenize(code);
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "fmt") {
+        process::exit(fmt(&args[1..]));
+    }
+
+    let flag = |name: &str| args.iter().any(|arg| arg == name);
+    // --expand prints the program after macro expansion instead of running it
+    let expand = flag("--expand");
+    // --vm compiles the program to bytecode and runs it on the VM instead of the tree-walking evaluator
+    let use_vm = flag("--vm");
+    // --disassemble prints the compiled bytecode instead of running it
+    let disassemble_only = flag("--disassemble");
+    let path = args
+        .iter()
+        .find(|arg| !arg.starts_with("--"))
+        .map(String::as_str)
+        .unwrap_or("test.lisp");
+
+    let code = match fs::read_to_string(path) {
+        Ok(code) => code,
+        Err(error) => {
+            println!("Error: Could not read {}: {}", path, error);
+            return;
+        }
+    };
+
+    // modules are imported from the directory the program is in
+    let dir = Path::new(path).parent().unwrap_or(Path::new("."));
+    let mut interpreter = Interpreter::with_env(
+        EnvBuilder::new()
+            .all_modules()
+            .search_path(if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            })
+            .build(),
+    );
+    // the main thread's stack is big enough for 4 MiB (see Limits::max_stack)
+    interpreter.set_limits(Limits {
+        max_stack: Some(4 << 20),
+        ..Limits::default()
+    });
+    let forms = match interpreter.parse_all(&code) {
+        Ok(forms) => forms,
+        Err(error) => {
+            println!("Error: {}", error);
+            return;
+        }
+    };
+
+    if expand {
+        for form in &forms {
+            let expanded = match interpreter.expand(form) {
+                Ok(expanded) => expanded,
+                Err(error) => {
+                    println!("Error: {}", error);
+                    return;
+                }
+            };
+            let options = PrintOptions::from_env(&interpreter.env().borrow());
+            println!("{}", pretty_print(&expanded, &options));
+        }
+        return;
+    }
+
+    if use_vm || disassemble_only {
+        let mut vm = Vm::with_prelude();
+        let mut result = Value::Nil;
+        for form in &forms {
+            let function = match interpreter
+                .expand(form)
+                .map_err(|e| e.to_string())
+                .and_then(|expanded| compile(&expanded, interpreter.env()))
+            {
+                Ok(function) => function,
+                Err(error) => {
+                    println!("Error: {}", error);
+                    return;
+                }
+            };
+            if disassemble_only {
+                print!("{}", disassemble(&function));
+            } else {
+                match vm.run(function) {
+                    Ok(value) => result = value,
+                    Err(error) => {
+                        println!("Error: {}", error);
+                        return;
+                    }
+                }
+            }
+        }
 
-    tokens.reverse();
-    let tree = parse(&mut tokens).unwrap();
+        if !disassemble_only {
+            print_result(&result, &mut interpreter);
+        }
+        return;
+    }
 
-    let result = eval_value(&tree, &mut Rc::from(RefCell::from(Env::new())));
-    println!("Result: {}", value_to_string(&result.unwrap()));
+    // the result of the last form is printed
+    let mut result = Value::Nil;
+    for form in &forms {
+        match interpreter.eval(form) {
+            Ok(value) => result = value,
+            Err(error) => {
+                println!("Error: {}", error);
+                return;
+            }
+        }
+    }
+    print_result(&result, &mut interpreter);
 }
@@ -0,0 +1,130 @@
+use crate::{parse::Value, sandbox::Capabilities, symbol::Symbol};
+use std::{collections::HashMap, path::PathBuf, rc::Rc};
+
+/// The modules an interpreter has loaded, shared by its root environment and the environments the modules
+/// themselves are evaluated in, so every module is only loaded once.
+#[derive(Debug, PartialEq)]
+pub struct ModuleRegistry {
+    // the names each module exports, with their values
+    modules: HashMap<Symbol, Vec<(Symbol, Value)>>,
+    // the modules that are being loaded right now, in the order they were imported
+    loading: Vec<Symbol>,
+    // the directories imported modules are looked for in, in order
+    search_path: Vec<PathBuf>,
+    pub(crate) prelude: Prelude,
+}
+
+/// What the prelude defines, which is copied into every new root environment. It's only loaded once it's
+/// first needed.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Prelude {
+    Disabled,
+    Unloaded,
+    Loaded(Rc<[(Symbol, Value)]>),
+}
+
+// the path of a module's file relative to the search path. Every part of the name has to be a plain file
+// name, so a name can't lead out of the directories on the search path (like .etc.passwd or a/../b would)
+fn module_file(name: Symbol) -> Result<PathBuf, String> {
+    let mut file = PathBuf::new();
+    for part in name.as_str().split('.') {
+        if part.is_empty() || part.contains(['/', '\\', ':', '\0']) {
+            return Err(format!("Invalid module name: {}", name));
+        }
+        file.push(part);
+    }
+    file.set_extension("lisp");
+    Ok(file)
+}
+
+impl Default for ModuleRegistry {
+    fn default() -> Self {
+        Self::new(vec![PathBuf::from(".")])
+    }
+}
+
+impl ModuleRegistry {
+    pub fn new(search_path: Vec<PathBuf>) -> Self {
+        Self {
+            modules: HashMap::new(),
+            loading: vec![],
+            search_path,
+            prelude: Prelude::Unloaded,
+        }
+    }
+
+    /// Sets whether new root environments get the definitions from the prelude.
+    pub fn set_prelude(&mut self, enabled: bool) {
+        self.prelude = if enabled {
+            Prelude::Unloaded
+        } else {
+            Prelude::Disabled
+        };
+    }
+
+    pub fn search_path(&self) -> &[PathBuf] {
+        &self.search_path
+    }
+
+    pub fn set_search_path(&mut self, search_path: Vec<PathBuf>) {
+        self.search_path = search_path;
+    }
+
+    pub fn get(&self, name: Symbol) -> Option<&[(Symbol, Value)]> {
+        self.modules.get(&name).map(Vec::as_slice)
+    }
+
+    pub fn define(&mut self, name: Symbol, exports: Vec<(Symbol, Value)>) {
+        self.modules.insert(name, exports);
+    }
+
+    /// Returns the file a module is loaded from: a.b is looked for as a/b.lisp in every directory on the
+    /// search path, resolved against the filesystem root (see [`Capabilities::resolve_path`]).
+    pub fn find(
+        &self,
+        name: Symbol,
+        capabilities: &Capabilities,
+    ) -> Result<Option<PathBuf>, String> {
+        let file = module_file(name)?;
+        for dir in &self.search_path {
+            let path = capabilities.resolve_path(&dir.join(&file).to_string_lossy())?;
+            if path.is_file() {
+                return Ok(Some(path));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Marks a module as being loaded. Fails if it's already being loaded, which means it (indirectly)
+    /// imports itself.
+    pub fn start_loading(&mut self, name: Symbol) -> Result<(), String> {
+        if let Some(i) = self.loading.iter().position(|n| *n == name) {
+            let chain: Vec<&str> = self.loading[i..]
+                .iter()
+                .chain(std::iter::once(&name))
+                .map(|n| n.as_str())
+                .collect();
+            return Err(format!("Import cycle: {}", chain.join(" -> ")));
+        }
+
+        self.loading.push(name);
+        Ok(())
+    }
+
+    pub fn finish_loading(&mut self, name: Symbol) {
+        self.loading.retain(|n| *n != name);
+    }
+
+    /// Returns every exported value (and everything the prelude defines), for the garbage collector.
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        let prelude = match &self.prelude {
+            Prelude::Loaded(bindings) => &bindings[..],
+            _ => &[],
+        };
+        self.modules
+            .values()
+            .flat_map(|exports| exports.iter())
+            .chain(prelude.iter())
+            .map(|(_, value)| value)
+    }
+}
@@ -0,0 +1,327 @@
+use crate::{parse::Value, symbol::Symbol, util::value_to_string};
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+    hash::Hash,
+};
+
+type Function = Box<dyn Fn(&[Value]) -> Result<Value, String>>;
+
+/// A function implemented in Rust, which gets its arguments already evaluated.
+pub struct NativeFunction {
+    pub name: Symbol,
+    function: Function,
+}
+
+impl NativeFunction {
+    pub fn new(name: &str, function: impl Fn(&[Value]) -> Result<Value, String> + 'static) -> Self {
+        Self {
+            name: Symbol::new(name),
+            function: Box::new(function),
+        }
+    }
+
+    /// Wraps a closure with typed arguments (see [`TypedFn`]), so the arguments are checked and converted
+    /// before it's called.
+    pub fn typed<Args, F: TypedFn<Args>>(name: &str, function: F) -> Self {
+        let name = Symbol::new(name);
+        Self {
+            name: name.clone(),
+            function: Box::new(move |args| function.call(name.clone(), args)),
+        }
+    }
+
+    pub fn call(&self, args: &[Value]) -> Result<Value, String> {
+        (self.function)(args)
+    }
+}
+
+impl Debug for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#<native {}>", self.name)
+    }
+}
+
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// Converts a Value to a Rust type, failing if it's the wrong type.
+pub trait FromValue: Sized {
+    fn from_value(value: &Value) -> Result<Self, String>;
+}
+
+/// Converts a Rust type to a Value.
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+fn expected(what: &str, value: &Value) -> String {
+    format!("expected {}, got {}", what, value_to_string(value))
+}
+
+impl FromValue for Value {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        Ok(value.clone())
+    }
+}
+
+impl IntoValue for Value {
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Number(n) => Ok(*n),
+            _ => Err(expected("a number", value)),
+        }
+    }
+}
+
+impl IntoValue for f64 {
+    fn into_value(self) -> Value {
+        Value::Number(self)
+    }
+}
+
+macro_rules! integer_conversions {
+    ($($t:ty),*) => {
+        $(
+            impl FromValue for $t {
+                fn from_value(value: &Value) -> Result<Self, String> {
+                    match value {
+                        Value::Number(n) if n.fract() == 0.0 && *n >= <$t>::MIN as f64 && *n <= <$t>::MAX as f64 => {
+                            Ok(*n as $t)
+                        }
+                        _ => Err(expected(concat!("an integer that fits in ", stringify!($t)), value)),
+                    }
+                }
+            }
+
+            impl IntoValue for $t {
+                fn into_value(self) -> Value {
+                    Value::Number(self as f64)
+                }
+            }
+        )*
+    };
+}
+
+integer_conversions!(i8, i16, i32, i64, u8, u16, u32, u64, usize);
+
+impl FromValue for bool {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::T => Ok(true),
+            Value::Nil => Ok(false),
+            _ => Err(expected("t or nil", value)),
+        }
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self) -> Value {
+        if self {
+            Value::T
+        } else {
+            Value::Nil
+        }
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::String(s) => Ok(s.to_string()),
+            _ => Err(expected("a string", value)),
+        }
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self) -> Value {
+        Value::String(self.into())
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self) -> Value {
+        Value::String(self.into())
+    }
+}
+
+impl IntoValue for () {
+    fn into_value(self) -> Value {
+        Value::Nil
+    }
+}
+
+// nil is the empty list
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Nil => Ok(vec![]),
+            Value::List(l) => l.iter().map(T::from_value).collect(),
+            _ => Err(expected("a list", value)),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> Value {
+        Value::List(self.into_iter().map(IntoValue::into_value).collect())
+    }
+}
+
+// nil is None
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Nil => Ok(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> Value {
+        match self {
+            Some(value) => value.into_value(),
+            None => Value::Nil,
+        }
+    }
+}
+
+// maps are property lists, e.g. (:a 1 :b 2), and keywords are converted to (and from) string keys
+impl<K: FromValue + Eq + Hash, V: FromValue> FromValue for HashMap<K, V> {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        let l = match value {
+            Value::Nil => return Ok(HashMap::new()),
+            Value::List(l) if l.len().is_multiple_of(2) => l,
+            _ => return Err(expected("a property list", value)),
+        };
+
+        let mut map = HashMap::new();
+        for pair in l.chunks(2) {
+            let key = match &pair[0] {
+                Value::Keyword(k) => K::from_value(&Value::String(k.as_str().into()))?,
+                key => K::from_value(key)?,
+            };
+            map.insert(key, V::from_value(&pair[1])?);
+        }
+        Ok(map)
+    }
+}
+
+impl<K: IntoValue, V: IntoValue> IntoValue for HashMap<K, V> {
+    fn into_value(self) -> Value {
+        let mut plist = vec![];
+        for (key, value) in self {
+            plist.push(match key.into_value() {
+                Value::String(s) => Value::Keyword(Symbol::new(&s)),
+                key => key,
+            });
+            plist.push(value.into_value());
+        }
+        Value::List(plist.into())
+    }
+}
+
+macro_rules! tuple_conversions {
+    ($($len:literal => ($($t:ident $i:tt),*)),*) => {
+        $(
+            impl<$($t: FromValue),*> FromValue for ($($t,)*) {
+                fn from_value(value: &Value) -> Result<Self, String> {
+                    match value {
+                        Value::List(l) if l.len() == $len => Ok(($($t::from_value(&l[$i])?,)*)),
+                        _ => Err(expected(concat!("a list of ", $len, " items"), value)),
+                    }
+                }
+            }
+
+            impl<$($t: IntoValue),*> IntoValue for ($($t,)*) {
+                fn into_value(self) -> Value {
+                    Value::List(vec![$(self.$i.into_value()),*].into())
+                }
+            }
+        )*
+    };
+}
+
+tuple_conversions! {
+    2 => (A 0, B 1),
+    3 => (A 0, B 1, C 2),
+    4 => (A 0, B 1, C 2, D 3)
+}
+
+/// What a typed native function can return: a value, or a Result whose error becomes the error message.
+pub trait IntoResult {
+    fn into_result(self) -> Result<Value, String>;
+}
+
+impl<T: IntoValue> IntoResult for T {
+    fn into_result(self) -> Result<Value, String> {
+        Ok(self.into_value())
+    }
+}
+
+impl<T: IntoValue, E: Display> IntoResult for Result<T, E> {
+    fn into_result(self) -> Result<Value, String> {
+        self.map(IntoValue::into_value).map_err(|e| e.to_string())
+    }
+}
+
+/// A closure that takes arguments that can be converted from Values (up to 4 of them), like
+/// `|a: f64, b: f64| a + b`. `Args` is the tuple of its argument types.
+pub trait TypedFn<Args>: 'static {
+    fn call(&self, name: Symbol, args: &[Value]) -> Result<Value, String>;
+}
+
+fn check_arity(name: Symbol, args: &[Value], arity: usize) -> Result<(), String> {
+    if args.len() == arity {
+        return Ok(());
+    }
+
+    Err(format!(
+        "\"{}\" requires {} argument{}",
+        name,
+        arity,
+        if arity == 1 { "" } else { "s" }
+    ))
+}
+
+fn argument<T: FromValue>(name: Symbol, args: &[Value], i: usize) -> Result<T, String> {
+    T::from_value(&args[i]).map_err(|e| format!("Argument {} to \"{}\": {}", i + 1, name, e))
+}
+
+macro_rules! typed_fns {
+    ($($arity:literal => ($($t:ident $i:tt),*)),*) => {
+        $(
+            impl<F, R, $($t),*> TypedFn<($($t,)*)> for F
+            where
+                F: Fn($($t),*) -> R + 'static,
+                R: IntoResult,
+                $($t: FromValue,)*
+            {
+                #[allow(unused_variables)]
+                fn call(&self, name: Symbol, args: &[Value]) -> Result<Value, String> {
+                    check_arity(name.clone(), args, $arity)?;
+                    self($(argument::<$t>(name.clone(), args, $i)?),*).into_result()
+                }
+            }
+        )*
+    };
+}
+
+typed_fns! {
+    0 => (),
+    1 => (A 0),
+    2 => (A 0, B 1),
+    3 => (A 0, B 1, C 2),
+    4 => (A 0, B 1, C 2, D 3)
+}
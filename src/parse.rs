@@ -1,13 +1,24 @@
-use crate::{tokenize::*, util::value_to_string};
-use core::fmt::Debug;
-use std::rc::Rc;
+use crate::{
+    env::CapturedEnv, foreign::Foreign, interpreter, native::NativeFunction, symbol::Symbol,
+    tokenize::*, vm::Closure,
+};
+use core::fmt::{Debug, Display};
+use std::{ops::Deref, rc::Rc};
 
 #[derive(Clone, PartialEq)]
 pub struct LambdaParams {
-    pub required: Vec<String>,
-    pub optional: Vec<String>,
-    pub keyword: Vec<String>,
-    pub rest: Option<String>,
+    pub required: Vec<Symbol>,
+    pub optional: Vec<Symbol>,
+    pub keyword: Vec<Symbol>,
+    pub rest: Option<Symbol>,
+}
+
+fn join_symbols(symbols: &[Symbol]) -> String {
+    symbols
+        .iter()
+        .map(|s| s.as_str())
+        .collect::<Vec<&str>>()
+        .join(" ")
 }
 
 impl Debug for LambdaParams {
@@ -15,93 +26,244 @@ impl Debug for LambdaParams {
         write!(
             f,
             "required: [{}], optional: [{}], keyword: [{}], rest: [{}]",
-            self.required.join(" "),
-            self.optional.join(" "),
-            self.keyword.join(" "),
-            self.rest.clone().unwrap_or(String::from(""))
+            join_symbols(&self.required),
+            join_symbols(&self.optional),
+            join_symbols(&self.keyword),
+            self.rest.as_ref().map(Symbol::as_str).unwrap_or("")
         )
     }
 }
 
+// the parameter list the parameters were defined with, e.g. (a &optional b &rest c)
+impl Display for LambdaParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut params: Vec<&str> = self.required.iter().map(|s| s.as_str()).collect();
+        if !self.optional.is_empty() {
+            params.push("&optional");
+            params.extend(self.optional.iter().map(|s| s.as_str()));
+        }
+        if let Some(rest) = &self.rest {
+            params.push("&rest");
+            params.push(rest.as_str());
+        }
+        if !self.keyword.is_empty() {
+            params.push("&key");
+            params.extend(self.keyword.iter().map(|s| s.as_str()));
+        }
+        write!(f, "({})", params.join(" "))
+    }
+}
+
+impl LambdaParams {
+    /// Returns the names of all parameters, in the order their values are returned by [`LambdaParams::bind`].
+    pub fn names(&self) -> Vec<Symbol> {
+        self.required
+            .iter()
+            .chain(self.optional.iter())
+            .chain(self.rest.iter())
+            .chain(self.keyword.iter())
+            .cloned()
+            .collect()
+    }
+
+    /// Matches the (already evaluated) arguments to the parameters, returning the value of each one.
+    pub fn bind(&self, args: &[Value]) -> Vec<Value> {
+        let mut values = vec![];
+
+        // required and optional parameters are set positionally, and are nil if there's not enough arguments
+        let positional = self.required.len() + self.optional.len();
+        for i in 0..positional {
+            values.push(args.get(i).cloned().unwrap_or(Value::Nil));
+        }
+
+        // everything after the required and optional parameters is used for the rest and keyword parameters
+        let remaining = args.get(positional..).unwrap_or(&[]);
+        if self.rest.is_some() {
+            values.push(Value::List(List::from(remaining)));
+        }
+
+        for name in self.keyword.iter() {
+            let mut value = Value::Nil;
+            for (i, arg) in remaining.iter().enumerate() {
+                // the argument after the parameter's keyword is its value
+                if *arg == Value::Keyword(name.clone()) && i + 1 < remaining.len() {
+                    value = remaining[i + 1].clone();
+                }
+            }
+            values.push(value);
+        }
+
+        values
+    }
+}
+
+/// A value (or a piece of code). Everything that's stored on the heap is reference counted, so cloning a value
+/// never copies its contents.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Nil,
     T,
     Number(f64),
-    String(String),
-    Symbol(String),
+    String(Rc<str>),
+    Symbol(Symbol),
+    // :name, which evaluates to itself (the symbol is the name without the colon)
+    Keyword(Symbol),
     Lambda {
-        params: LambdaParams,
+        params: Rc<LambdaParams>,
         body: Rc<Value>,
         is_macro: bool,
+        env: CapturedEnv,
+    },
+    Syntax {
+        literals: Rc<[Symbol]>,
+        rules: Rc<[(Value, Value)]>,
+        // where it was made, which is what free identifiers in the templates refer to
+        env: CapturedEnv,
     },
-    List(Vec<Value>),
+    // a lambda compiled to bytecode, see the vm module
+    Closure(Rc<Closure>),
+    // a function implemented in Rust, see Env::register_fn
+    Native(Rc<NativeFunction>),
+    // an object owned by the host, see ForeignType
+    Foreign(Rc<Foreign>),
+    // an environment as a value, made by current-env or make-env
+    Env(CapturedEnv),
+    List(List),
+    // a reference to a local variable, resolved when the lambda it's in was defined (see Env::get_at)
+    LocalRef {
+        name: Symbol,
+        depth: usize,
+        slot: usize,
+    },
+}
+
+/// The items of a list. Lists share their items with the lists they were made from: the rest of a list (what cdr
+/// returns) is the same items starting one later, so it isn't a copy. It dereferences to a slice of the items.
+#[derive(Clone)]
+pub struct List {
+    items: Rc<[Value]>,
+    start: usize,
 }
 
-fn wrap_value_with_prefix(value: &Value, prefix: &[char]) -> Value {
-    if prefix.len() == 0 {
-        return value.clone();
+impl List {
+    /// Returns the list without its first `n` items, sharing them with this one.
+    pub fn skip(&self, n: usize) -> List {
+        List {
+            items: self.items.clone(),
+            start: (self.start + n).min(self.items.len()),
+        }
     }
 
-    let quote = '\'';
-    let backtick = '`';
-    let comma = ',';
-    let at = '@';
+    /// Returns whether the two lists are the same items, rather than just equal ones.
+    pub fn ptr_eq(a: &List, b: &List) -> bool {
+        Rc::ptr_eq(&a.items, &b.items) && a.start == b.start
+    }
 
-    if prefix[0] == quote {
-        if prefix.len() > 1 {
-            // if there's more things after the ', then treat the entire expression, along with rest of the prefix, as a single symbol
-            let mut string = String::with_capacity(prefix.len() - 1);
-            for c in &prefix[1..] {
-                string.insert(string.len(), *c);
-            }
-            string += &value_to_string(&value);
-            return Value::Symbol(string);
-        } else {
-            // otherwise, just quote the expression
-            return Value::List(vec![Value::Symbol(format!("quote")), value.clone()]);
-        }
+    // all the items the list shares with others, including the ones before its start
+    pub(crate) fn shared(&self) -> &Rc<[Value]> {
+        &self.items
     }
+}
 
-    if prefix[0] == backtick {
-        if prefix.len() > 1 {
-            // if there's more things after the `, wrap this same function recursively (without the 1st prefix character) in a quasiquote
-            return Value::List(vec![
-                Value::Symbol(format!("quasiquote")),
-                wrap_value_with_prefix(&value, &prefix[1..]),
-            ]);
-        } else {
-            // otherwise, just wrap this value in a quasiquote
-            return Value::List(vec![Value::Symbol(format!("quasiquote")), value.clone()]);
-        }
+impl Deref for List {
+    type Target = [Value];
+
+    fn deref(&self) -> &[Value] {
+        &self.items[self.start..]
     }
+}
 
-    if prefix[0] == comma {
-        if prefix.len() > 1 {
-            if prefix[1] == at {
-                if prefix.len() > 2 {
-                    return Value::List(vec![
-                        Value::Symbol(format!("splice-unquote")),
-                        wrap_value_with_prefix(&value, &prefix[2..]),
-                    ]);
-                } else {
-                    return Value::List(vec![
-                        Value::Symbol(format!("splice-unquote")),
-                        value.clone(),
-                    ]);
-                }
-            } else {
-                return Value::List(vec![
-                    Value::Symbol(format!("unquote")),
-                    wrap_value_with_prefix(&value, &prefix[1..]),
-                ]);
-            }
-        } else {
-            return Value::List(vec![Value::Symbol(format!("unquote")), value.clone()]);
-        }
+impl From<Rc<[Value]>> for List {
+    fn from(items: Rc<[Value]>) -> Self {
+        List { items, start: 0 }
     }
+}
 
-    Value::Nil
+impl From<Vec<Value>> for List {
+    fn from(items: Vec<Value>) -> Self {
+        List::from(Rc::from(items))
+    }
+}
+
+impl From<&[Value]> for List {
+    fn from(items: &[Value]) -> Self {
+        List::from(Rc::from(items))
+    }
+}
+
+impl FromIterator<Value> for List {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        List::from(iter.into_iter().collect::<Rc<[Value]>>())
+    }
+}
+
+impl PartialEq for List {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Debug for List {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// every prefix wraps everything after it, so ''a is (quote (quote a)) and `,x is (quasiquote (unquote x))
+fn wrap_value_with_prefix(value: &Value, prefix: &[char]) -> Result<Value, String> {
+    let mut symbols = vec![];
+    let mut prefix = prefix;
+    while !prefix.is_empty() {
+        let (symbol, rest) = match prefix {
+            ['\'', rest @ ..] => (Symbol::QUOTE, rest),
+            ['`', rest @ ..] => (Symbol::QUASIQUOTE, rest),
+            [',', '@', rest @ ..] => (Symbol::SPLICE_UNQUOTE, rest),
+            [',', rest @ ..] => (Symbol::UNQUOTE, rest),
+            _ => return Err(String::from("@ can only come right after a comma")),
+        };
+        symbols.push(symbol);
+        prefix = rest;
+    }
+
+    // wrapped from the inside out, so a long prefix doesn't need a deep stack
+    Ok(symbols
+        .into_iter()
+        .rev()
+        .fold(value.clone(), |value, symbol| {
+            Value::List(vec![Value::Symbol(symbol), value].into())
+        }))
+}
+
+/// Returns the symbol or keyword with the given name, the way the reader reads it.
+pub fn read_symbol(name: &str) -> Result<Value, String> {
+    // symbols made by gensym are printed with #:, and reading them back wouldn't give the same symbol
+    if name.starts_with("#:") {
+        return Err(format!("Uninterned symbols can't be read: {}", name));
+    }
+    // and neither can objects that are printed with #<...>
+    if name.starts_with("#<") {
+        return Err(format!("Objects printed as #<...> can't be read: {}", name));
+    }
+
+    match name.strip_prefix(':') {
+        Some(keyword) if !keyword.is_empty() => Ok(Value::Keyword(Symbol::new(keyword))),
+        _ => Ok(Value::Symbol(Symbol::new(name))),
+    }
+}
+
+// reads a token that isn't a parenthesis
+fn parse_atom(token: Token) -> Result<Value, String> {
+    let value = match token.t {
+        TokenType::T => Value::T,
+        TokenType::Nil => Value::Nil,
+        TokenType::Number(n) => Value::Number(n),
+        TokenType::String(s) => Value::String(s.into()),
+        TokenType::Symbol(s) => read_symbol(&s)?,
+        TokenType::EscapedSymbol(s) => Value::Symbol(Symbol::new(&s)),
+        TokenType::EscapedKeyword(s) => Value::Keyword(Symbol::new(&s)),
+        TokenType::StartParen | TokenType::EndParen => unreachable!(),
+    };
+    wrap_value_with_prefix(&value, &token.prefix)
 }
 
 pub fn parse(tokens: &mut Vec<Token>) -> Result<Value, String> {
@@ -109,51 +271,81 @@ pub fn parse(tokens: &mut Vec<Token>) -> Result<Value, String> {
         return Err(String::from("Expected '(' at beginning of file"));
     }
 
-    let token = tokens.pop().unwrap();
-    match token.t {
-        TokenType::T => Ok(wrap_value_with_prefix(&Value::T, &token.prefix)),
-        TokenType::Nil => Ok(wrap_value_with_prefix(&Value::Nil, &token.prefix)),
-        TokenType::Number(n) => Ok(wrap_value_with_prefix(&Value::Number(n), &token.prefix)),
-        TokenType::String(s) => Ok(wrap_value_with_prefix(&Value::String(s), &token.prefix)),
-        TokenType::Symbol(s) => Ok(wrap_value_with_prefix(&Value::Symbol(s), &token.prefix)),
+    parse_form(tokens)
+}
 
-        TokenType::StartParen => {
-            let mut list: Vec<Value> = vec![];
-
-            loop {
-                let token = tokens.pop();
-
-                match token {
-                    None => return Err(String::from("Expected ')' at end of file")),
-
-                    Some(tkn) => match tkn.t {
-                        TokenType::Nil => {
-                            list.push(wrap_value_with_prefix(&Value::Nil, &tkn.prefix))
-                        }
-                        TokenType::T => list.push(wrap_value_with_prefix(&Value::T, &tkn.prefix)),
-                        TokenType::Number(n) => {
-                            list.push(wrap_value_with_prefix(&Value::Number(n), &tkn.prefix))
-                        }
-                        TokenType::String(s) => {
-                            list.push(wrap_value_with_prefix(&Value::String(s), &tkn.prefix))
-                        }
-                        TokenType::Symbol(s) => {
-                            list.push(wrap_value_with_prefix(&Value::Symbol(s), &tkn.prefix))
-                        }
-
-                        TokenType::StartParen => {
-                            tokens.push(tkn.clone());
-                            list.push(parse(tokens)?);
-                        }
-
-                        TokenType::EndParen => break,
-                    },
+// reads the items of a list whose opening parenthesis was just read, up to and including the closing one
+fn parse_list(tokens: &mut Vec<Token>) -> Result<Value, String> {
+    let mut list: Vec<Value> = vec![];
+
+    loop {
+        let token = tokens.pop();
+
+        match token {
+            None => return Err(String::from("Expected ')' at end of file")),
+
+            Some(tkn) => match tkn.t {
+                TokenType::StartParen => {
+                    tokens.push(tkn.clone());
+                    list.push(parse_form(tokens)?);
                 }
-            }
 
-            Ok(wrap_value_with_prefix(&Value::List(list), &token.prefix))
+                TokenType::EndParen => break,
+
+                _ => list.push(parse_atom(tkn)?),
+            },
+        }
+    }
+
+    Ok(Value::List(list.into()))
+}
+
+// parses the next form in the (reversed) tokens, which can be an atom even if there's more after it
+fn parse_form(tokens: &mut Vec<Token>) -> Result<Value, String> {
+    let token = tokens.pop().unwrap();
+    match token.t {
+        TokenType::StartParen => {
+            let list = interpreter::nested(|| parse_list(tokens))?;
+            wrap_value_with_prefix(&list, &token.prefix)
         }
 
         TokenType::EndParen => Err(String::from("Unexpected ')'")),
+        _ => parse_atom(token),
+    }
+}
+
+/// Parses every form in the tokens (which are reversed, like for [`parse`]), e.g. all the top-level forms
+/// in a file.
+pub fn parse_all(tokens: &mut Vec<Token>) -> Result<Vec<Value>, String> {
+    let mut forms = vec![];
+    while !tokens.is_empty() {
+        forms.push(parse(tokens)?);
+    }
+    Ok(forms)
+}
+
+/// Reads the first form in the code, returning it along with the offset right after it, or None if there's
+/// only whitespace and comments. Unlike [`parse`], the form can be an atom.
+pub fn read_form(code: &str) -> Result<Option<(Value, usize)>, String> {
+    let mut tokens = tokenize(String::from(code))?;
+    let ends: Vec<usize> = tokens.iter().map(|token| token.end).collect();
+    tokens.reverse();
+    if tokens.is_empty() {
+        return Ok(None);
+    }
+
+    let form = parse_form(&mut tokens)?;
+    Ok(Some((form, ends[ends.len() - tokens.len() - 1])))
+}
+
+/// Reads every form in the code, which can be atoms too.
+pub fn read_all(code: &str) -> Result<Vec<Value>, String> {
+    let mut tokens = tokenize(String::from(code))?;
+    tokens.reverse();
+
+    let mut forms = vec![];
+    while !tokens.is_empty() {
+        forms.push(parse_form(&mut tokens)?);
     }
+    Ok(forms)
 }
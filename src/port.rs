@@ -0,0 +1,344 @@
+use crate::{
+    foreign::{Foreign, ForeignType},
+    parse::{read_form, Value},
+    tokenize::{tokenize, TokenType},
+};
+use std::{
+    cell::RefCell,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::Path,
+    rc::Rc,
+};
+
+enum Source {
+    Stdin,
+    File(BufReader<File>),
+    // string ports have all their text in the buffer from the start
+    Nothing,
+}
+
+struct Input {
+    source: Source,
+    // what was read from the source but not used yet starts at pos
+    buffer: String,
+    pos: usize,
+}
+
+enum Sink {
+    Stdout,
+    Stderr,
+    File(File),
+    String(String),
+}
+
+enum State {
+    Input(Input),
+    Output(Sink),
+    Closed,
+}
+
+/// Something text is read from or written to: stdin, stdout, stderr, a file or a string. Ports are foreign
+/// objects of the type "port", so Lisp code gets them as values (see [`port_of`]).
+pub struct Port {
+    // what the port is printed as, like stdout or the path of a file
+    name: String,
+    state: RefCell<State>,
+}
+
+/// One of the ports the builtins use when they aren't given one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Current {
+    Input,
+    Output,
+    Error,
+}
+
+thread_local! {
+    static PORT_TYPE: Rc<ForeignType> =
+        Rc::new(ForeignType::new("port").show(|port: &Port| port.name.clone()));
+
+    // input, output and error
+    static CURRENT: RefCell<[Rc<Foreign>; 3]> = RefCell::new([
+        Port::wrap("stdin", State::Input(Input::new(Source::Stdin, String::new()))),
+        Port::wrap("stdout", State::Output(Sink::Stdout)),
+        Port::wrap("stderr", State::Output(Sink::Stderr)),
+    ]);
+
+    static EOF: Value = Rc::new(ForeignType::new("eof")).wrap(Eof);
+}
+
+// the type of the end of file object, which there's only one of
+struct Eof;
+
+/// Returns what reading returns at the end of the input. It's a foreign object that's only equal to itself, so
+/// it's different from anything that can be read.
+pub fn eof() -> Value {
+    EOF.with(Value::clone)
+}
+
+/// Returns whether the value is the end of file object (see [`eof`]).
+pub fn is_eof(value: &Value) -> bool {
+    matches!(value, Value::Foreign(foreign) if foreign.is::<Eof>())
+}
+
+// whether the code has a list, string or |symbol| that isn't closed yet, so reading more of it could finish
+// the form
+fn is_unfinished(code: &str) -> bool {
+    let tokens = match tokenize(String::from(code)) {
+        Ok(tokens) => tokens,
+        Err(e) => return e.starts_with("Unterminated"),
+    };
+
+    let mut depth = 0;
+    for token in tokens {
+        match token.t {
+            TokenType::StartParen => depth += 1,
+            TokenType::EndParen if depth == 0 => return false,
+            TokenType::EndParen => depth -= 1,
+            _ => {}
+        }
+    }
+    depth > 0
+}
+
+impl Input {
+    fn new(source: Source, buffer: String) -> Self {
+        Self {
+            source,
+            buffer,
+            pos: 0,
+        }
+    }
+
+    fn pending(&self) -> &str {
+        &self.buffer[self.pos..]
+    }
+
+    // reads another line from the source into the buffer, returning false at the end of the input
+    fn fill(&mut self, name: &str) -> Result<bool, String> {
+        self.buffer.drain(..self.pos);
+        self.pos = 0;
+
+        let read = match &mut self.source {
+            Source::Stdin => io::stdin().lock().read_line(&mut self.buffer),
+            Source::File(reader) => reader.read_line(&mut self.buffer),
+            Source::Nothing => return Ok(false),
+        };
+        let read = read.map_err(|e| format!("Could not read from {}: {}", name, e))?;
+        Ok(read > 0)
+    }
+}
+
+impl Port {
+    fn wrap(name: &str, state: State) -> Rc<Foreign> {
+        let port = Port {
+            name: String::from(name),
+            state: RefCell::new(state),
+        };
+        PORT_TYPE.with(|port_type| Rc::new(Foreign::new(port_type, port)))
+    }
+
+    /// Makes a port that reads the string.
+    pub fn input_string(text: &str) -> Rc<Foreign> {
+        Self::wrap(
+            "string",
+            State::Input(Input::new(Source::Nothing, String::from(text))),
+        )
+    }
+
+    /// Makes a port that collects everything written to it, see [`Port::text`].
+    pub fn output_string() -> Rc<Foreign> {
+        Self::wrap("string", State::Output(Sink::String(String::new())))
+    }
+
+    pub fn open_input_file(path: &Path) -> Result<Rc<Foreign>, String> {
+        let file =
+            File::open(path).map_err(|e| format!("Could not open {}: {}", path.display(), e))?;
+        Ok(Self::wrap(
+            &path.display().to_string(),
+            State::Input(Input::new(
+                Source::File(BufReader::new(file)),
+                String::new(),
+            )),
+        ))
+    }
+
+    /// Opens a file for writing, replacing what's in it unless `append` is set.
+    pub fn open_output_file(path: &Path, append: bool) -> Result<Rc<Foreign>, String> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)
+            .map_err(|e| format!("Could not open {}: {}", path.display(), e))?;
+        Ok(Self::wrap(
+            &path.display().to_string(),
+            State::Output(Sink::File(file)),
+        ))
+    }
+
+    pub fn is_input(&self) -> bool {
+        matches!(*self.state.borrow(), State::Input(_))
+    }
+
+    pub fn is_output(&self) -> bool {
+        matches!(*self.state.borrow(), State::Output(_))
+    }
+
+    fn input<R>(&self, f: impl FnOnce(&mut Input) -> Result<R, String>) -> Result<R, String> {
+        match &mut *self.state.borrow_mut() {
+            State::Input(input) => f(input),
+            State::Output(_) => Err(format!("Port {} isn't an input port", self.name)),
+            State::Closed => Err(format!("Port {} is closed", self.name)),
+        }
+    }
+
+    pub fn write_str(&self, s: &str) -> Result<(), String> {
+        let result = match &mut *self.state.borrow_mut() {
+            State::Output(Sink::String(output)) => {
+                output.push_str(s);
+                Ok(())
+            }
+            State::Output(Sink::Stdout) => {
+                let mut stdout = io::stdout().lock();
+                stdout.write_all(s.as_bytes()).and_then(|_| stdout.flush())
+            }
+            State::Output(Sink::Stderr) => io::stderr().lock().write_all(s.as_bytes()),
+            // files aren't buffered, so nothing is lost if the process exits without closing them
+            State::Output(Sink::File(file)) => file.write_all(s.as_bytes()),
+            State::Input(_) => return Err(format!("Port {} isn't an output port", self.name)),
+            State::Closed => return Err(format!("Port {} is closed", self.name)),
+        };
+        result.map_err(|e| format!("Could not write to {}: {}", self.name, e))
+    }
+
+    /// Reads the next line without its line ending, or returns None at the end of the input.
+    pub fn read_line(&self) -> Result<Option<String>, String> {
+        self.input(|input| {
+            while !input.pending().contains('\n') {
+                if !input.fill(&self.name)? {
+                    break;
+                }
+            }
+            if input.pending().is_empty() {
+                return Ok(None);
+            }
+
+            let pending = input.pending();
+            let end = pending.find('\n').map_or(pending.len(), |i| i + 1);
+            let line = String::from(pending[..end].trim_end_matches(['\n', '\r']));
+            input.pos += end;
+            Ok(Some(line))
+        })
+    }
+
+    /// Reads the next character, or returns None at the end of the input.
+    pub fn read_char(&self) -> Result<Option<char>, String> {
+        self.input(|input| {
+            if input.pending().is_empty() && !input.fill(&self.name)? {
+                return Ok(None);
+            }
+
+            let c = input.pending().chars().next();
+            input.pos += c.map_or(0, char::len_utf8);
+            Ok(c)
+        })
+    }
+
+    /// Reads the next form, reading as many lines as it takes, or returns None at the end of the input.
+    pub fn read(&self) -> Result<Option<Value>, String> {
+        self.input(|input| {
+            let mut at_end = false;
+            loop {
+                let pending = input.pending();
+                match read_form(pending) {
+                    // a form that goes until the end of what was read so far could continue on the next line,
+                    // like a string with a line break in it
+                    Ok(Some((form, end))) if end < pending.len() || at_end => {
+                        input.pos += end;
+                        return Ok(Some(form));
+                    }
+                    Ok(None) if at_end => {
+                        input.pos = input.buffer.len();
+                        return Ok(None);
+                    }
+                    Err(e) if at_end || !is_unfinished(pending) => {
+                        input.pos = input.buffer.len();
+                        return Err(e);
+                    }
+                    _ => {}
+                }
+                at_end = !input.fill(&self.name)?;
+            }
+        })
+    }
+
+    /// Returns everything written to a string port so far, or None for any other kind of port.
+    pub fn text(&self) -> Option<String> {
+        match &*self.state.borrow() {
+            State::Output(Sink::String(output)) => Some(output.clone()),
+            _ => None,
+        }
+    }
+
+    /// Closes the port, after which it can't be read from or written to. Closing stdin, stdout or stderr
+    /// does nothing.
+    pub fn close(&self) {
+        let mut state = self.state.borrow_mut();
+        let stdio = matches!(
+            *state,
+            State::Input(Input {
+                source: Source::Stdin,
+                ..
+            }) | State::Output(Sink::Stdout | Sink::Stderr)
+        );
+        if !stdio {
+            *state = State::Closed;
+        }
+    }
+}
+
+/// Returns the port in a value, if it's one.
+pub fn port_of(value: &Value) -> Option<&Port> {
+    match value {
+        Value::Foreign(foreign) => foreign.downcast_ref(),
+        _ => None,
+    }
+}
+
+fn index(which: Current) -> usize {
+    match which {
+        Current::Input => 0,
+        Current::Output => 1,
+        Current::Error => 2,
+    }
+}
+
+/// Returns the current input, output or error port, which starts out as stdin, stdout or stderr.
+pub fn current(which: Current) -> Rc<Foreign> {
+    CURRENT.with_borrow(|ports| ports[index(which)].clone())
+}
+
+/// Replaces the current input, output or error port, returning the previous one.
+pub fn set_current(which: Current, port: Rc<Foreign>) -> Rc<Foreign> {
+    CURRENT.with_borrow_mut(|ports| std::mem::replace(&mut ports[index(which)], port))
+}
+
+/// Calls `f` with the current input, output or error port replaced, and puts the previous one back after.
+pub fn with_current<R>(which: Current, port: Rc<Foreign>, f: impl FnOnce() -> R) -> R {
+    let previous = set_current(which, port);
+    let result = f();
+    set_current(which, previous);
+    result
+}
+
+/// Calls `f` with the current output port redirected to a string, and returns what it wrote along with its
+/// result, e.g. to check what code prints in tests.
+pub fn capture_output<R>(f: impl FnOnce() -> R) -> (R, String) {
+    let port = Port::output_string();
+    let result = with_current(Current::Output, port.clone(), f);
+    let text = port.downcast_ref::<Port>().and_then(Port::text);
+    (result, text.unwrap_or_default())
+}
@@ -0,0 +1,41 @@
+; the prelude is evaluated in a root environment of its own, and everything it defines is copied into every
+; new root environment (unless it's turned off with EnvBuilder::prelude). It only uses what the compiler
+; supports, so the VM can run it too (see Vm::with_prelude)
+
+(def list (lambda (&rest items) items))
+
+(def cadr (lambda (l) (car (cdr l))))
+(def cddr (lambda (l) (cdr (cdr l))))
+(def caddr (lambda (l) (car (cddr l))))
+
+; (when condition body...) evaluates the body if the condition is true, otherwise it's nil
+(def when (macro (condition &rest body) `(if ,condition (let () ,@body) nil)))
+
+; (unless condition body...) evaluates the body if the condition is false, otherwise it's nil
+(def unless (macro (condition &rest body) `(if ,condition nil (let () ,@body))))
+
+(def map
+  (lambda (f l)
+    (if (or (not l) (= (len l) 0))
+      nil
+      `(,(f (car l)) ,@(map f (cdr l))))))
+
+(def filter
+  (lambda (f l)
+    (if (or (not l) (= (len l) 0))
+      nil
+      (if (f (car l))
+        `(,(car l) ,@(filter f (cdr l)))
+        (filter f (cdr l))))))
+
+; (with-gensyms (name...) body...) evaluates the body with each name bound to a new uninterned symbol, so
+; the code a macro makes can have variables that can't capture the ones it's given
+(def with-gensyms
+  (macro (names &rest body)
+    `(let ,(map (lambda (name) `(,name (gensym ',name))) names) ,@body)))
+
+; the printer variables, which pprint (and printing results) uses. Lists print at most *print-length* items
+; and are only printed *print-depth* levels deep, with no limit if they're nil
+(def *print-width* 80)
+(def *print-length* nil)
+(def *print-depth* nil)
@@ -0,0 +1,39 @@
+use crate::{
+    env::Env, eval::eval_toplevel, module::*, parse::*, sandbox::Capabilities, symbol::Symbol,
+    tokenize::tokenize,
+};
+use std::{cell::RefCell, rc::Rc};
+
+const PRELUDE: &str = include_str!("prelude.lisp");
+
+/// Returns the forms in the prelude.
+pub(crate) fn forms() -> Vec<Value> {
+    let mut tokens = tokenize(String::from(PRELUDE)).expect("Could not read the prelude");
+    tokens.reverse();
+    parse_all(&mut tokens).expect("Could not parse the prelude")
+}
+
+/// Returns what the prelude defines, evaluating it the first time it's needed.
+pub(crate) fn bindings(
+    capabilities: &Rc<Capabilities>,
+    modules: &Rc<RefCell<ModuleRegistry>>,
+) -> Option<Rc<[(Symbol, Value)]>> {
+    match &modules.borrow().prelude {
+        Prelude::Disabled => return None,
+        Prelude::Loaded(bindings) => return Some(bindings.clone()),
+        Prelude::Unloaded => {}
+    }
+
+    // the prelude gets a root environment of its own, so redefining something it uses doesn't break it
+    let mut prelude_modules = ModuleRegistry::new(vec![]);
+    prelude_modules.set_prelude(false);
+    let mut env = Env::new_root(capabilities.clone(), Rc::new(RefCell::new(prelude_modules)));
+
+    for form in forms() {
+        eval_toplevel(&form, &mut env).expect("Could not evaluate the prelude");
+    }
+
+    let bindings: Rc<[(Symbol, Value)]> = env.borrow().globals().collect();
+    modules.borrow_mut().prelude = Prelude::Loaded(bindings.clone());
+    Some(bindings)
+}
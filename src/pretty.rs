@@ -0,0 +1,240 @@
+use crate::{env::Env, parse::Value, symbol::Symbol, util::value_to_string};
+
+/// How values are pretty printed. The limits are read from `*print-width*`, `*print-length*` and
+/// `*print-depth*` by [`PrintOptions::from_env`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrintOptions {
+    // lines are broken so they fit in this many columns, if possible
+    pub width: usize,
+    // lists print at most this many items, followed by ...
+    pub length: Option<usize>,
+    // lists nested deeper than this print as #
+    pub depth: Option<usize>,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        Self {
+            width: 80,
+            length: None,
+            depth: None,
+        }
+    }
+}
+
+impl PrintOptions {
+    /// Reads the options from the printer variables visible in an environment. A variable that's unbound or
+    /// nil (or isn't a non-negative integer) keeps its default.
+    pub fn from_env(env: &Env) -> Self {
+        let get = |name: Symbol| match env.get(name) {
+            Some(Value::Number(n)) if n >= 0.0 && n.fract() == 0.0 => Some(n as usize),
+            _ => None,
+        };
+
+        let default = Self::default();
+        Self {
+            width: get(Symbol::PRINT_WIDTH).unwrap_or(default.width),
+            length: get(Symbol::PRINT_LENGTH),
+            depth: get(Symbol::PRINT_DEPTH),
+        }
+    }
+}
+
+// a document in Wadler's "prettier printer" algebra, extended with alignment
+enum Doc {
+    Text(String),
+    // a space if its group fits on the line, otherwise a newline
+    Line,
+    Concat(Vec<Doc>),
+    // indents lines inside it by this much more than the enclosing indentation
+    Nest(usize, Box<Doc>),
+    // indents lines inside it to the column it starts at
+    Align(Box<Doc>),
+    // laid out on one line if it fits, otherwise every Line directly inside it becomes a newline
+    Group(Box<Doc>),
+}
+
+fn text(s: &str) -> Doc {
+    Doc::Text(String::from(s))
+}
+
+fn join(docs: Vec<Doc>) -> Vec<Doc> {
+    let mut joined = vec![];
+    for (i, doc) in docs.into_iter().enumerate() {
+        if i > 0 {
+            joined.push(Doc::Line);
+        }
+        joined.push(doc);
+    }
+    joined
+}
+
+// how many arguments of a special form go on the same line as its name, with the rest of them indented as
+// a body
+pub(crate) fn distinguished_args(s: Symbol) -> Option<usize> {
+    match s {
+        Symbol::LAMBDA
+        | Symbol::MACRO
+        | Symbol::DEF
+        | Symbol::LET
+        | Symbol::LET_STAR
+        | Symbol::LETREC
+        | Symbol::LABELS
+        | Symbol::MATCH
+        | Symbol::WHEN
+        | Symbol::UNLESS
+        | Symbol::WITH_GENSYMS
+        | Symbol::DEFINE_SYNTAX
+        | Symbol::SYNTAX_RULES => Some(1),
+        Symbol::MODULE => Some(2),
+        _ => None,
+    }
+}
+
+fn to_doc(value: &Value, options: &PrintOptions, depth: usize) -> Doc {
+    let l = match value {
+        Value::List(l) => l,
+        _ => return Doc::Text(value_to_string(value)),
+    };
+    if options.depth.is_some_and(|max| depth >= max) {
+        return text("#");
+    }
+
+    let shown = options.length.map_or(l.len(), |max| max.min(l.len()));
+    let mut items: Vec<Doc> = l[..shown]
+        .iter()
+        .map(|item| to_doc(item, options, depth + 1))
+        .collect();
+    if shown < l.len() {
+        items.push(text("..."));
+    }
+
+    let head = match l.first() {
+        Some(Value::Symbol(s)) if shown > 0 => Some(s.clone()),
+        _ => None,
+    };
+    let inner = match head {
+        // (let (bindings)
+        //   body...)
+        Some(s) if distinguished_args(s.clone()).is_some_and(|n| items.len() > n + 1) => {
+            let body = items.split_off(distinguished_args(s).unwrap() + 1);
+            let mut docs = vec![];
+            for (i, item) in items.into_iter().enumerate() {
+                if i > 0 {
+                    docs.push(text(" "));
+                }
+                docs.push(item);
+            }
+            let mut body = join(body);
+            body.insert(0, Doc::Line);
+            docs.push(Doc::Nest(2, Box::new(Doc::Concat(body))));
+            Doc::Concat(docs)
+        }
+
+        // (f a
+        //    b)
+        Some(_) if items.len() > 1 => {
+            let args = items.split_off(1);
+            Doc::Concat(vec![
+                items.pop().unwrap(),
+                text(" "),
+                Doc::Align(Box::new(Doc::Concat(join(args)))),
+            ])
+        }
+
+        // (a
+        //  b)
+        _ => Doc::Align(Box::new(Doc::Concat(join(items)))),
+    };
+
+    // lines are indented relative to the opening parenthesis
+    Doc::Align(Box::new(Doc::Concat(vec![
+        text("("),
+        Doc::Group(Box::new(inner)),
+        text(")"),
+    ])))
+}
+
+fn width(s: &str) -> usize {
+    s.chars().count()
+}
+
+// whether the rest of the line fits in `remaining` columns, if `doc` is laid out flat and everything after it
+// (in `rest`, whose last item comes next) as it would be
+fn fits(mut remaining: isize, doc: &Doc, rest: &[(usize, bool, &Doc)]) -> bool {
+    let mut stack: Vec<(bool, &Doc)> = vec![(true, doc)];
+    let mut rest = rest.iter().rev();
+    loop {
+        let (flat, doc) = match stack.pop() {
+            Some(item) => item,
+            None => match rest.next() {
+                Some((_, flat, doc)) => (*flat, *doc),
+                None => return true,
+            },
+        };
+
+        match doc {
+            Doc::Text(s) => {
+                // a string with a newline in it ends the line
+                if let Some(i) = s.find('\n') {
+                    return remaining >= width(&s[..i]) as isize;
+                }
+                remaining -= width(s) as isize;
+                if remaining < 0 {
+                    return false;
+                }
+            }
+            Doc::Line if flat => {
+                remaining -= 1;
+                if remaining < 0 {
+                    return false;
+                }
+            }
+            Doc::Line => return true,
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (flat, doc))),
+            Doc::Nest(_, doc) | Doc::Align(doc) | Doc::Group(doc) => stack.push((flat, doc)),
+        }
+    }
+}
+
+fn render(doc: &Doc, width_limit: usize, output: &mut String) {
+    let mut column = 0;
+    // (indentation, whether it's laid out flat, document), with the next one last
+    let mut stack: Vec<(usize, bool, &Doc)> = vec![(0, false, doc)];
+    while let Some((indent, flat, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(s) => {
+                output.push_str(s);
+                column = match s.rfind('\n') {
+                    Some(i) => width(&s[i + 1..]),
+                    None => column + width(s),
+                };
+            }
+            Doc::Line if flat => {
+                output.push(' ');
+                column += 1;
+            }
+            Doc::Line => {
+                output.push('\n');
+                output.extend(std::iter::repeat_n(' ', indent));
+                column = indent;
+            }
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, flat, doc))),
+            Doc::Nest(n, doc) => stack.push((indent + n, flat, doc)),
+            Doc::Align(doc) => stack.push((column, flat, doc)),
+            Doc::Group(doc) => {
+                let flat = flat || fits(width_limit as isize - column as isize, doc, &stack);
+                stack.push((indent, flat, doc));
+            }
+        }
+    }
+}
+
+/// Prints a value like [`value_to_string`], but breaking lines so it fits in the width if possible. Lists
+/// that don't fit have every item on its own line, aligned with the first argument (or the first item, if
+/// the list isn't a call), and special forms like let and lambda have their body indented by 2.
+pub fn pretty_print(value: &Value, options: &PrintOptions) -> String {
+    let mut output = String::new();
+    render(&to_doc(value, options, 0), options.width, &mut output);
+    output
+}
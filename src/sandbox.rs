@@ -0,0 +1,248 @@
+use crate::{env::Env, module::ModuleRegistry};
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    ffi::OsString,
+    fs,
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+};
+
+/// A group of builtins that an environment can be given access to. Special forms (if, def, lambda, let,
+/// quote, ...) are part of the language itself, so they're always available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Module {
+    // list operations, comparisons, logic, symbols, gensym, macroexpand, the garbage collector, foreign
+    // objects, eval and environments
+    Core,
+    // arithmetic
+    Math,
+    String,
+    // reading from stdin and other input ports, opening files for reading, and loading files and modules
+    IoRead,
+    // writing to stdout and other output ports, and opening files for writing
+    IoWrite,
+    // exiting, environment variables and command line arguments
+    Process,
+    // the clock and sleeping
+    Time,
+    // reading, writing and managing files and directories, and working with paths
+    Fs,
+}
+
+impl Module {
+    pub const ALL: [Module; 8] = [
+        Module::Core,
+        Module::Math,
+        Module::String,
+        Module::IoRead,
+        Module::IoWrite,
+        Module::Process,
+        Module::Time,
+        Module::Fs,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Module::Core => "core",
+            Module::Math => "math",
+            Module::String => "string",
+            Module::IoRead => "io.read",
+            Module::IoWrite => "io.write",
+            Module::Process => "process",
+            Module::Time => "time",
+            Module::Fs => "fs",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Module> {
+        Module::ALL.into_iter().find(|module| module.name() == name)
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// What code running in an environment is allowed to do. Every environment shares the capabilities of the
+/// root environment it extends.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Capabilities {
+    modules: u8,
+    // filesystem access is restricted to this directory if it's set
+    fs_root: Option<PathBuf>,
+}
+
+/// Removes . and .. from a path without touching the filesystem. A .. at the start of a relative path is
+/// kept, since there's nothing before it to remove.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                // the parent of the root is the root
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => normalized.push(component),
+            },
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+// the most symlinks followed while resolving a path, so links that point at each other don't loop forever
+const MAX_LINKS: usize = 40;
+
+// appends the path to a directory that has no symlinks in it one component at a time, replacing every symlink
+// with what it points to, even when that doesn't exist (yet)
+fn follow_links(dir: &Path, path: &Path) -> Result<PathBuf, String> {
+    let mut resolved = dir.to_path_buf();
+    let mut rest: VecDeque<OsString> = path
+        .components()
+        .map(|component| component.as_os_str().to_os_string())
+        .collect();
+    let mut links = 0;
+
+    while let Some(component) = rest.pop_front() {
+        let next = resolved.join(&component);
+        match Path::new(&component).components().next() {
+            Some(Component::RootDir) => resolved = next,
+            Some(Component::ParentDir) => {
+                resolved.pop();
+            }
+            Some(Component::Normal(_)) => {
+                let is_link = next
+                    .symlink_metadata()
+                    .is_ok_and(|metadata| metadata.file_type().is_symlink());
+                if !is_link {
+                    resolved = next;
+                    continue;
+                }
+
+                links += 1;
+                if links > MAX_LINKS {
+                    return Err(format!("Too many symlinks in {}", next.display()));
+                }
+                // what the link points to is looked at in its place, relative to the directory it's in
+                let target = fs::read_link(&next).map_err(|e| e.to_string())?;
+                for component in target.components().rev() {
+                    rest.push_front(component.as_os_str().to_os_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(resolved)
+}
+
+impl Capabilities {
+    pub fn all() -> Self {
+        let mut capabilities = Self::default();
+        for module in Module::ALL {
+            capabilities.modules |= module.bit();
+        }
+        capabilities
+    }
+
+    pub fn allows(&self, module: Module) -> bool {
+        self.modules & module.bit() != 0
+    }
+
+    pub fn fs_root(&self) -> Option<&Path> {
+        self.fs_root.as_deref()
+    }
+
+    /// Returns the path a builtin should access for `path`. With a filesystem root, relative paths are
+    /// relative to the root, and paths that end up outside of it (including through symlinks) are an error.
+    pub fn resolve_path(&self, path: &str) -> Result<PathBuf, String> {
+        let Some(root) = &self.fs_root else {
+            return Ok(PathBuf::from(path));
+        };
+
+        let outside = || format!("Path \"{}\" is outside of the filesystem root", path);
+        let root = normalize(
+            &std::env::current_dir()
+                .map_err(|e| e.to_string())?
+                .join(root),
+        );
+        let resolved = normalize(&root.join(path));
+        if !resolved.starts_with(&root) {
+            return Err(outside());
+        }
+
+        // the part of the path that already exists could contain symlinks that lead out of the root
+        let real_root = root.canonicalize().unwrap_or_else(|_| root.clone());
+        let relative = resolved.strip_prefix(&root).unwrap_or(Path::new(""));
+        let real = follow_links(&real_root, relative)?;
+        if !real.starts_with(&real_root) {
+            return Err(outside());
+        }
+
+        Ok(real)
+    }
+}
+
+/// Builds a root environment that only has access to the modules it's given, so untrusted code can run in
+/// a pure environment. [`Env::new`] gives access to everything.
+///
+/// Only the tree-walking evaluator is sandboxed, the compiler doesn't support any of the builtins that have
+/// side effects.
+#[derive(Debug, Clone, Default)]
+pub struct EnvBuilder {
+    capabilities: Capabilities,
+    search_path: Vec<PathBuf>,
+    no_prelude: bool,
+}
+
+impl EnvBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn module(mut self, module: Module) -> Self {
+        self.capabilities.modules |= module.bit();
+        self
+    }
+
+    pub fn modules(mut self, modules: &[Module]) -> Self {
+        for module in modules {
+            self = self.module(*module);
+        }
+        self
+    }
+
+    pub fn all_modules(self) -> Self {
+        self.modules(&Module::ALL)
+    }
+
+    /// Restricts filesystem access to `root` and everything in it.
+    pub fn fs_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.capabilities.fs_root = Some(root.into());
+        self
+    }
+
+    /// Adds a directory that imported modules are looked for in. Modules can only be imported from the
+    /// search path, which is empty by default.
+    pub fn search_path(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.search_path.push(dir.into());
+        self
+    }
+
+    /// Sets whether the environment (and the ones modules are evaluated in) get the definitions from the
+    /// prelude, which they do by default.
+    pub fn prelude(mut self, enabled: bool) -> Self {
+        self.no_prelude = !enabled;
+        self
+    }
+
+    pub fn build(self) -> Rc<RefCell<Env>> {
+        let mut modules = ModuleRegistry::new(self.search_path);
+        modules.set_prelude(!self.no_prelude);
+        Env::new_root(Rc::new(self.capabilities), Rc::new(RefCell::new(modules)))
+    }
+}
@@ -0,0 +1,734 @@
+//! Converting between [`Value`] and Rust types with serde.
+//!
+//! Structs and maps become property lists with keyword keys, e.g. `(:name "a" :size 2)`, enum variants become
+//! lists tagged with a keyword, e.g. `(:Circle 1.5)` (or just `:Circle` if they don't have any data),
+//! sequences and tuples become lists, false, None and () become nil, and every number becomes a float.
+
+use crate::{parse::Value, symbol::Symbol};
+use ::serde::{
+    de::{self, DeserializeOwned, IntoDeserializer, Visitor},
+    forward_to_deserialize_any, ser, Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::fmt::Display;
+
+// symbols and keywords are serialized as newtype structs with these names, which other serializers ignore,
+// so they keep their type when they're serialized to a Value
+const SYMBOL: &str = "$euphie::Symbol";
+const KEYWORD: &str = "$euphie::Keyword";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error(String);
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+/// Converts any serializable Rust value to a Value.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, Error> {
+    value.serialize(ValueSerializer)
+}
+
+/// Converts a Value to any deserializable Rust type.
+pub fn from_value<T: DeserializeOwned>(value: &Value) -> Result<T, Error> {
+    T::deserialize(value)
+}
+
+// a list like (:a 1 :b 2)
+fn is_plist(list: &[Value]) -> bool {
+    !list.is_empty()
+        && list.len().is_multiple_of(2)
+        && list
+            .iter()
+            .step_by(2)
+            .all(|key| matches!(key, Value::Keyword(_)))
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Nil => serializer.serialize_unit(),
+            Value::T => serializer.serialize_bool(true),
+            Value::Number(n) => serializer.serialize_f64(*n),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Symbol(s) | Value::LocalRef { name: s, .. } => {
+                serializer.serialize_newtype_struct(SYMBOL, s.as_str())
+            }
+            Value::Keyword(k) => serializer.serialize_newtype_struct(KEYWORD, k.as_str()),
+            Value::List(l) if is_plist(l) => {
+                let mut map = ser::Serializer::serialize_map(serializer, Some(l.len() / 2))?;
+                for pair in l.chunks(2) {
+                    if let Value::Keyword(key) = &pair[0] {
+                        ser::SerializeMap::serialize_entry(&mut map, key.as_str(), &pair[1])?;
+                    }
+                }
+                ser::SerializeMap::end(map)
+            }
+            Value::List(l) => serializer.collect_seq(l.iter()),
+            Value::Lambda { .. } | Value::Syntax { .. } | Value::Closure(_) | Value::Native(_) => {
+                Err(ser::Error::custom(
+                    "Functions and macros can't be serialized",
+                ))
+            }
+            Value::Foreign(_) => Err(ser::Error::custom("Foreign objects can't be serialized")),
+            Value::Env(_) => Err(ser::Error::custom("Environments can't be serialized")),
+        }
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "a value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(if v { Value::T } else { Value::Nil })
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Number(v as f64))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::Number(v as f64))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::Number(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.into()))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Nil)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Nil)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut list = vec![];
+        while let Some(item) = seq.next_element()? {
+            list.push(item);
+        }
+        Ok(Value::List(list.into()))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        // string keys become keywords
+        let mut plist = vec![];
+        while let Some((key, value)) = map.next_entry::<Value, Value>()? {
+            plist.push(match key {
+                Value::String(s) => Value::Keyword(Symbol::new(&s)),
+                key => key,
+            });
+            plist.push(value);
+        }
+        Ok(Value::List(plist.into()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueSerializer;
+
+struct SeqSerializer {
+    // the keyword tagging an enum variant
+    tag: Option<Value>,
+    items: Vec<Value>,
+}
+
+impl SeqSerializer {
+    fn new(tag: Option<&str>) -> Self {
+        Self {
+            tag: tag.map(|tag| Value::Keyword(Symbol::new(tag))),
+            items: vec![],
+        }
+    }
+
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.items.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn push_key(&mut self, key: &str) {
+        self.items.push(Value::Keyword(Symbol::new(key)));
+    }
+
+    fn finish(self) -> Result<Value, Error> {
+        Ok(Value::List(
+            self.tag.into_iter().chain(self.items).collect(),
+        ))
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeMap for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        // string keys become keywords
+        match to_value(key)? {
+            Value::String(s) => self.push_key(&s),
+            key => self.items.push(key),
+        }
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.push_key(key);
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.push_key(key);
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+impl Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = SeqSerializer;
+    type SerializeStruct = SeqSerializer;
+    type SerializeStructVariant = SeqSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, Error> {
+        Ok(if v { Value::T } else { Value::Nil })
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, Error> {
+        Ok(Value::Number(v as f64))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, Error> {
+        Ok(Value::Number(v as f64))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, Error> {
+        Ok(Value::Number(v as f64))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, Error> {
+        Ok(Value::Number(v as f64))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, Error> {
+        Ok(Value::Number(v as f64))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, Error> {
+        Ok(Value::Number(v as f64))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, Error> {
+        Ok(Value::Number(v as f64))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, Error> {
+        Ok(Value::Number(v as f64))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, Error> {
+        Ok(Value::Number(v as f64))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, Error> {
+        Ok(Value::Number(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, Error> {
+        Ok(Value::String(v.to_string().into()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, Error> {
+        Ok(Value::String(v.into()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, Error> {
+        Ok(Value::List(
+            v.iter().map(|b| Value::Number(*b as f64)).collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<Value, Error> {
+        Ok(Value::Nil)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, Error> {
+        to_value(value)
+    }
+
+    fn serialize_unit(self) -> Result<Value, Error> {
+        Ok(Value::Nil)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Error> {
+        Ok(Value::Nil)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Value, Error> {
+        Ok(Value::Keyword(Symbol::new(variant)))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        match (name, to_value(value)?) {
+            (SYMBOL, Value::String(s)) => Ok(Value::Symbol(Symbol::new(&s))),
+            (KEYWORD, Value::String(s)) => Ok(Value::Keyword(Symbol::new(&s))),
+            (_, value) => Ok(value),
+        }
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        let mut list = SeqSerializer::new(Some(variant));
+        list.push(value)?;
+        list.finish()
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer::new(None))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer::new(None))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer::new(None))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer::new(Some(variant)))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer::new(None))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer::new(None))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer::new(Some(variant)))
+    }
+}
+
+fn unexpected(value: &Value) -> de::Unexpected<'_> {
+    match value {
+        Value::Nil => de::Unexpected::Unit,
+        Value::T => de::Unexpected::Bool(true),
+        Value::Number(n) => de::Unexpected::Float(*n),
+        Value::String(s) => de::Unexpected::Str(s),
+        Value::List(_) => de::Unexpected::Seq,
+        _ => de::Unexpected::Other("a symbol or function"),
+    }
+}
+
+// the items of a list, or of an enum variant's list after its tag
+struct SeqAccess<'de>(std::slice::Iter<'de, Value>);
+
+impl<'de> de::SeqAccess<'de> for SeqAccess<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        self.0.next().map(|item| seed.deserialize(item)).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+// the keys and values of a property list
+struct MapAccess<'de> {
+    pairs: std::slice::ChunksExact<'de, Value>,
+    value: Option<&'de Value>,
+}
+
+impl<'de> MapAccess<'de> {
+    fn new(list: &'de [Value]) -> Result<Self, Error> {
+        if !list.len().is_multiple_of(2) {
+            return Err(Error(String::from(
+                "A property list needs a value for every key",
+            )));
+        }
+
+        Ok(Self {
+            pairs: list.chunks_exact(2),
+            value: None,
+        })
+    }
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.pairs.next() {
+            Some(pair) => {
+                self.value = Some(&pair[1]);
+                seed.deserialize(&pair[0]).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        seed.deserialize(self.value.take().unwrap())
+    }
+}
+
+struct EnumAccess<'de> {
+    variant: &'de str,
+    fields: &'de [Value],
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess<'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for EnumAccess<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        if self.fields.is_empty() {
+            Ok(())
+        } else {
+            Err(Error(format!(
+                "Variant {} doesn't take any values",
+                self.variant
+            )))
+        }
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        match self.fields {
+            [value] => seed.deserialize(value),
+            _ => Err(Error(format!("Variant {} takes 1 value", self.variant))),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_seq(SeqAccess(self.fields.iter()))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_map(MapAccess::new(self.fields)?)
+    }
+}
+
+impl<'de> Deserializer<'de> for &'de Value {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Nil => visitor.visit_unit(),
+            Value::T => visitor.visit_bool(true),
+            // whole numbers are passed as integers, so they can be deserialized to integer types
+            Value::Number(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => {
+                visitor.visit_i64(*n as i64)
+            }
+            Value::Number(n) => visitor.visit_f64(*n),
+            Value::String(s) => visitor.visit_borrowed_str(s),
+            Value::Symbol(s) | Value::Keyword(s) | Value::LocalRef { name: s, .. } => {
+                visitor.visit_borrowed_str(s.as_str())
+            }
+            Value::List(l) if is_plist(l) => visitor.visit_map(MapAccess::new(l)?),
+            Value::List(l) => visitor.visit_seq(SeqAccess(l.iter())),
+            Value::Lambda { .. } | Value::Syntax { .. } | Value::Closure(_) | Value::Native(_) => {
+                Err(Error(String::from(
+                    "Functions and macros can't be deserialized",
+                )))
+            }
+            Value::Foreign(_) => Err(Error(String::from("Foreign objects can't be deserialized"))),
+            Value::Env(_) => Err(Error(String::from("Environments can't be deserialized"))),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::T => visitor.visit_bool(true),
+            Value::Nil => visitor.visit_bool(false),
+            value => Err(de::Error::invalid_type(unexpected(value), &visitor)),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Nil => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Nil => visitor.visit_seq(SeqAccess([].iter())),
+            Value::List(l) => visitor.visit_seq(SeqAccess(l.iter())),
+            value => Err(de::Error::invalid_type(unexpected(value), &visitor)),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Nil => visitor.visit_map(MapAccess::new(&[])?),
+            Value::List(l) => visitor.visit_map(MapAccess::new(l)?),
+            value => Err(de::Error::invalid_type(unexpected(value), &visitor)),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        // :Variant, or (:Variant values...)
+        let (variant, fields): (&str, &[Value]) = match self {
+            Value::Keyword(s) | Value::Symbol(s) => (s.as_str(), &[]),
+            Value::String(s) => (s, &[]),
+            Value::List(l) => match l.first() {
+                Some(Value::Keyword(s) | Value::Symbol(s)) => (s.as_str(), &l[1..]),
+                _ => {
+                    return Err(Error(String::from(
+                        "An enum variant must start with its name",
+                    )))
+                }
+            },
+            value => return Err(de::Error::invalid_type(unexpected(value), &visitor)),
+        };
+
+        visitor.visit_enum(EnumAccess { variant, fields })
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Nil => visitor.visit_unit(),
+            value => Err(de::Error::invalid_type(unexpected(value), &visitor)),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf identifier
+    }
+}
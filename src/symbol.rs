@@ -0,0 +1,234 @@
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    fmt::{Debug, Display},
+    rc::Rc,
+    sync::{LazyLock, Mutex},
+};
+
+/// A name. Interned symbols with the same name are the same id, so comparing and hashing them is as cheap as
+/// comparing integers, and the name is only looked up when it's printed. Uninterned symbols keep their own name,
+/// which is freed with the last copy of the symbol.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(Repr);
+
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Repr {
+    Interned(u32),
+    Uninterned(Rc<Uninterned>),
+}
+
+// the id tells apart uninterned symbols with the same name. Ids are never reused, but they aren't indices into
+// anything, so nothing grows with them
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Uninterned {
+    id: u64,
+    name: Box<str>,
+}
+
+thread_local! {
+    static NEXT_UNINTERNED: Cell<u64> = const { Cell::new(0) };
+}
+
+struct Interner {
+    // interned names are never freed, so they can be handed out as &'static str
+    names: Vec<&'static str>,
+    ids: HashMap<&'static str, u32>,
+}
+
+impl Interner {
+    fn intern(&mut self, name: &str) -> u32 {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+
+        let name: &'static str = Box::leak(name.into());
+        let id = self.names.len() as u32;
+        self.names.push(name);
+        self.ids.insert(name, id);
+        id
+    }
+}
+
+static INTERNER: LazyLock<Mutex<Interner>> = LazyLock::new(|| {
+    let mut interner = Interner {
+        names: vec![],
+        ids: HashMap::new(),
+    };
+    for name in KNOWN {
+        interner.intern(name);
+    }
+
+    Mutex::new(interner)
+});
+
+impl Symbol {
+    /// Returns the symbol with the given name, adding it to the symbol table if it's not there yet.
+    pub fn new(name: &str) -> Symbol {
+        Symbol(Repr::Interned(INTERNER.lock().unwrap().intern(name)))
+    }
+
+    /// Returns a new symbol that isn't in the symbol table, so it's different from every other symbol, even
+    /// ones with the same name.
+    pub fn uninterned(name: &str) -> Symbol {
+        let id = NEXT_UNINTERNED.with(|next| next.replace(next.get() + 1));
+        Symbol(Repr::Uninterned(Rc::new(Uninterned {
+            id,
+            name: name.into(),
+        })))
+    }
+
+    /// Returns whether the symbol is in the symbol table, i.e. it wasn't made by [`Symbol::uninterned`].
+    pub fn is_interned(&self) -> bool {
+        matches!(self.0, Repr::Interned(_))
+    }
+
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            Repr::Interned(id) => INTERNER.lock().unwrap().names[*id as usize],
+            Repr::Uninterned(uninterned) => &uninterned.name,
+        }
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Debug for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// the symbols the evaluator and the compiler dispatch on are interned up front with fixed ids, so they can be
+// used as constants (and in match patterns)
+macro_rules! known_symbols {
+    ($($name:ident = $string:literal,)*) => {
+        #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+        enum Known {
+            $($name,)*
+        }
+
+        impl Symbol {
+            $(pub const $name: Symbol = Symbol(Repr::Interned(Known::$name as u32));)*
+        }
+
+        const KNOWN: &[&str] = &[$($string,)*];
+    };
+}
+
+known_symbols! {
+    ADD = "+",
+    SUB = "-",
+    MUL = "*",
+    DIV = "/",
+    EQUAL = "=",
+    NOT_EQUAL = "!=",
+    LESS = "<",
+    GREATER = ">",
+    LESS_EQUAL = "<=",
+    GREATER_EQUAL = ">=",
+    AND = "and",
+    OR = "or",
+    NOT = "not",
+    CAR = "car",
+    CDR = "cdr",
+    LEN = "len",
+    IF = "if",
+    DEF = "def",
+    LAMBDA = "lambda",
+    MACRO = "macro",
+    DEFINE_SYNTAX = "define-syntax",
+    SYNTAX_RULES = "syntax-rules",
+    MACROEXPAND = "macroexpand",
+    MACROEXPAND_1 = "macroexpand-1",
+    MACROEXPAND_ALL = "macroexpand-all",
+    GENSYM = "gensym",
+    WITH_GENSYMS = "with-gensyms",
+    LET = "let",
+    LET_STAR = "let*",
+    LETREC = "letrec",
+    LABELS = "labels",
+    MATCH = "match",
+    WHEN = "when",
+    UNLESS = "unless",
+    QUOTE = "quote",
+    QUASIQUOTE = "quasiquote",
+    UNQUOTE = "unquote",
+    SPLICE_UNQUOTE = "splice-unquote",
+    UNQUOTE_SPLICING = "unquote-splicing",
+    SYMBOL_TO_STRING = "symbol->string",
+    STRING_TO_SYMBOL = "string->symbol",
+    INTERN = "intern",
+    GC = "gc",
+    GC_STATS = "gc-stats",
+    STRING_LENGTH = "string-length",
+    STRING_APPEND = "string-append",
+    SUBSTRING = "substring",
+    NUMBER_TO_STRING = "number->string",
+    STRING_TO_NUMBER = "string->number",
+    PRINT = "print",
+    PRINTLN = "println",
+    READ_LINE = "read-line",
+    EXIT = "exit",
+    GETENV = "getenv",
+    COMMAND_LINE = "command-line",
+    CURRENT_TIME = "current-time",
+    SLEEP = "sleep",
+    LOAD = "load",
+    MODULE = "module",
+    EXPORT = "export",
+    IMPORT = "import",
+    ONLY = "only",
+    PREFIX = "prefix",
+    EQUAL_P = "equal?",
+    SEND = "send",
+    FOREIGN_TYPE = "foreign-type",
+    JSON_PARSE = "json-parse",
+    JSON_STRINGIFY = "json-stringify",
+    PPRINT = "pprint",
+    WRITE = "write",
+    DISPLAY = "display",
+    READ = "read",
+    READ_CHAR = "read-char",
+    EOF_OBJECT_P = "eof-object?",
+    NEWLINE = "newline",
+    OPEN_INPUT_FILE = "open-input-file",
+    OPEN_OUTPUT_FILE = "open-output-file",
+    CLOSE_PORT = "close-port",
+    CURRENT_INPUT_PORT = "current-input-port",
+    CURRENT_OUTPUT_PORT = "current-output-port",
+    CURRENT_ERROR_PORT = "current-error-port",
+    WITH_OUTPUT_TO_PORT = "with-output-to-port",
+    WITH_INPUT_FROM_PORT = "with-input-from-port",
+    WITH_OUTPUT_TO_STRING = "with-output-to-string",
+    CALL_WITH_INPUT_STRING = "call-with-input-string",
+    READ_FROM_STRING = "read-from-string",
+    READ_ALL = "read-all",
+    EVAL = "eval",
+    CURRENT_ENV = "current-env",
+    MAKE_ENV = "make-env",
+    READ_FILE = "read-file",
+    WRITE_FILE = "write-file",
+    APPEND_FILE = "append-file",
+    FILE_EXISTS = "file-exists?",
+    DIRECTORY_LIST = "directory-list",
+    MAKE_DIRECTORY = "make-directory",
+    DELETE_FILE = "delete-file",
+    RENAME_FILE = "rename-file",
+    FILE_METADATA = "file-metadata",
+    PATH_JOIN = "path-join",
+    PATH_NORMALIZE = "path-normalize",
+    PRINT_WIDTH = "*print-width*",
+    PRINT_LENGTH = "*print-length*",
+    PRINT_DEPTH = "*print-depth*",
+    OPTIONAL = "&optional",
+    KEY = "&key",
+    REST = "&rest",
+    WILDCARD = "_",
+    ELLIPSIS = "...",
+    DOT = ".",
+}
@@ -1,5 +1,3 @@
-use std::str;
-
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenType {
     Nil,
@@ -7,6 +5,9 @@ pub enum TokenType {
     Number(f64),
     String(String),
     Symbol(String),
+    // a symbol written between |s (or a keyword written as :|name|), which is read as is
+    EscapedSymbol(String),
+    EscapedKeyword(String),
     StartParen,
     EndParen,
 }
@@ -15,10 +16,51 @@ pub enum TokenType {
 pub struct Token {
     pub t: TokenType,
     pub prefix: Vec<char>,
+    // the byte offsets of the token in the code (including its prefix), see the cst module
+    pub start: usize,
+    pub end: usize,
     // TODO: line and column number
 }
 
-pub fn tokenize(code: String) -> Vec<Token> {
+// reads a string or |symbol| starting at the delimiter at `start`, returning what's in it (with \ escapes
+// replaced) and where the closing delimiter is, or None if it isn't closed
+fn read_delimited(code: &str, start: usize) -> Option<(String, usize)> {
+    let delimiter = code.as_bytes()[start] as char;
+    let mut s = String::new();
+    let mut chars = code[start + 1..].char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            c if c == delimiter => return Some((s, start + 1 + i)),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => s.push('\n'),
+                Some((_, 't')) => s.push('\t'),
+                Some((_, 'r')) => s.push('\r'),
+                Some((_, c)) if c == '\\' || c == delimiter => s.push(c),
+                // unknown escapes are kept as they are
+                Some((_, c)) => {
+                    s.push('\\');
+                    s.push(c);
+                }
+                None => return None,
+            },
+            c => s.push(c),
+        }
+    }
+
+    None
+}
+
+// the error for a prefix with nothing after it to apply to, like in (a ') or ' a
+fn dangling_prefix(prefix: &[char]) -> String {
+    format!(
+        "Expected a form after the prefix {}",
+        prefix.iter().collect::<String>()
+    )
+}
+
+/// Splits the code into tokens. Strings and |symbols| have to be closed, and prefixes (like ' and ,@) have to
+/// be followed right away by the form they apply to.
+pub fn tokenize(code: String) -> Result<Vec<Token>, String> {
     let mut tokens: Vec<Token> = vec![];
 
     // these are all used as single char prefixes, with the exception of splice-unquote, which is ,@
@@ -27,11 +69,16 @@ pub fn tokenize(code: String) -> Vec<Token> {
     let mut i = 0;
     let mut prefix: Vec<char> = vec![];
     while i < code.len() {
+        // the token starts at its prefix
+        let start = i - prefix.len();
         let c = code.as_bytes()[i] as char;
 
-        if c.is_whitespace() {
+        // only ASCII whitespace separates tokens, so a token never ends in the middle of a character
+        if c.is_ascii_whitespace() {
+            if !prefix.is_empty() {
+                return Err(dangling_prefix(&prefix));
+            }
             i += 1;
-            prefix.clear();
             continue;
         }
 
@@ -41,32 +88,60 @@ pub fn tokenize(code: String) -> Vec<Token> {
             continue;
         }
 
+        // comments go until the end of the line
+        if c == ';' {
+            if !prefix.is_empty() {
+                return Err(dangling_prefix(&prefix));
+            }
+            while i < code.len() && code.as_bytes()[i] != b'\n' {
+                i += 1;
+            }
+            continue;
+        }
+
         if c == '(' {
             tokens.push(Token {
                 t: TokenType::StartParen,
                 prefix: prefix.clone(),
+                start,
+                end: i + 1,
             });
             prefix.clear();
         } else if c == ')' {
+            if !prefix.is_empty() {
+                return Err(dangling_prefix(&prefix));
+            }
             tokens.push(Token {
                 t: TokenType::EndParen,
                 prefix: prefix.clone(),
+                start,
+                end: i + 1,
             });
             prefix.clear();
         } else if c == '"' {
-            let mut j = i + 1;
-            while j < code.len() {
-                if code.as_bytes()[j] == b'"' {
-                    break;
-                }
-
-                j += 1;
-            }
-
-            let substr = String::from(str::from_utf8(&code.as_bytes()[(i + 1)..j]).unwrap());
+            let (substr, j) =
+                read_delimited(&code, i).ok_or_else(|| String::from("Unterminated string"))?;
             tokens.push(Token {
                 t: TokenType::String(substr),
                 prefix: prefix.clone(),
+                start,
+                end: j + 1,
+            });
+            prefix.clear();
+            i = j;
+        } else if c == '|' || code[i..].starts_with(":|") {
+            let keyword = c == ':';
+            let (substr, j) = read_delimited(&code, if keyword { i + 1 } else { i })
+                .ok_or_else(|| String::from("Unterminated |symbol|"))?;
+            tokens.push(Token {
+                t: if keyword {
+                    TokenType::EscapedKeyword(substr)
+                } else {
+                    TokenType::EscapedSymbol(substr)
+                },
+                prefix: prefix.clone(),
+                start,
+                end: j + 1,
             });
             prefix.clear();
             i = j;
@@ -74,25 +149,29 @@ pub fn tokenize(code: String) -> Vec<Token> {
             let mut j = i + 1;
             while j < code.len() {
                 let inner_c = code.as_bytes()[j] as char;
-                if inner_c.is_whitespace() || inner_c == ')' {
+                if inner_c.is_ascii_whitespace() || inner_c == ')' {
                     break;
                 }
 
                 j += 1;
             }
 
-            let substr = String::from(str::from_utf8(&code.as_bytes()[i..j]).unwrap());
+            let substr = String::from(&code[i..j]);
 
             if substr == "nil" {
                 tokens.push(Token {
                     t: TokenType::Nil,
                     prefix: prefix.clone(),
+                    start,
+                    end: j,
                 });
                 prefix.clear();
             } else if substr == "t" {
                 tokens.push(Token {
                     t: TokenType::T,
                     prefix: prefix.clone(),
+                    start,
+                    end: j,
                 });
                 prefix.clear();
             } else {
@@ -101,6 +180,8 @@ pub fn tokenize(code: String) -> Vec<Token> {
                         tokens.push(Token {
                             t: TokenType::Number(n),
                             prefix: prefix.clone(),
+                            start,
+                            end: j,
                         });
 
                         prefix.clear();
@@ -109,6 +190,8 @@ pub fn tokenize(code: String) -> Vec<Token> {
                         tokens.push(Token {
                             t: TokenType::Symbol(substr),
                             prefix: prefix.clone(),
+                            start,
+                            end: j,
                         });
                         prefix.clear();
                     }
@@ -121,5 +204,8 @@ pub fn tokenize(code: String) -> Vec<Token> {
         i += 1;
     }
 
-    tokens
+    if !prefix.is_empty() {
+        return Err(dangling_prefix(&prefix));
+    }
+    Ok(tokens)
 }
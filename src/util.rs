@@ -1,25 +1,112 @@
-use crate::parse::Value;
+use crate::{parse::Value, symbol::Symbol};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-pub fn value_to_string(value: &Value) -> String {
+static SYMBOL_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns a new uninterned symbol named after `base` (see [`Symbol::uninterned`]). The names are numbered
+/// and start with #:, so they're also different from every other symbol when printed.
+pub fn fresh_symbol(base: &str) -> Symbol {
+    let n = SYMBOL_COUNTER.fetch_add(1, Ordering::Relaxed);
+    Symbol::uninterned(&format!("#:{}{}", base, n))
+}
+
+// whether a keyword's name reads back as the same keyword without being written as :|name|
+fn is_plain_keyword(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('|')
+        && !name.contains(|c: char| c.is_ascii_whitespace() || "()\";\\".contains(c))
+}
+
+// whether a symbol's name reads back as the same symbol without being written between |s
+fn is_plain_symbol(name: &str) -> bool {
+    is_plain_keyword(name)
+        && !name.starts_with(['\'', '`', ',', '@', ':', '#'])
+        && name != "nil"
+        && name != "t"
+        && name.parse::<f64>().is_err()
+}
+
+fn write_escaped(output: &mut String, s: &str, delimiter: char) {
+    output.push(delimiter);
+    for c in s.chars() {
+        match c {
+            '\n' => output.push_str("\\n"),
+            '\t' => output.push_str("\\t"),
+            '\r' => output.push_str("\\r"),
+            c if c == '\\' || c == delimiter => {
+                output.push('\\');
+                output.push(c);
+            }
+            c => output.push(c),
+        }
+    }
+    output.push(delimiter);
+}
+
+fn print_value(output: &mut String, value: &Value, readable: bool) {
     match value {
-        Value::Nil => format!("nil"),
-        Value::T => format!("t"),
-        Value::Number(n) => format!("{}", n),
-        Value::String(s) => format!("\"{}\"", s),
-        Value::Symbol(s) => format!("{}", s),
+        Value::Nil => output.push_str("nil"),
+        Value::T => output.push('t'),
+        Value::Number(n) => output.push_str(&n.to_string()),
+        Value::String(s) if readable => write_escaped(output, s, '"'),
+        Value::String(s) => output.push_str(s),
+        // symbols made by gensym keep their #: name, since they can't be read back anyway
+        Value::Symbol(s) if readable && s.is_interned() && !is_plain_symbol(s.as_str()) => {
+            write_escaped(output, s.as_str(), '|')
+        }
+        Value::Symbol(s) | Value::LocalRef { name: s, .. } => output.push_str(s.as_str()),
+        Value::Keyword(k) => {
+            output.push(':');
+            if readable && !is_plain_keyword(k.as_str()) {
+                write_escaped(output, k.as_str(), '|');
+            } else {
+                output.push_str(k.as_str());
+            }
+        }
         Value::Lambda {
             params, is_macro, ..
-        } => format!(
-            "<{} ({:?})>",
-            if *is_macro { "macro" } else { "lambda" },
-            params
-        ),
-        Value::List(l) => format!(
-            "({})",
-            l.iter()
-                .map(value_to_string)
-                .collect::<Vec<String>>()
+        } => {
+            let kind = if *is_macro { "macro" } else { "lambda" };
+            output.push_str(&format!("#<{} {}>", kind, params));
+        }
+        Value::Syntax { literals, .. } => output.push_str(&format!(
+            "#<syntax-rules ({})>",
+            literals
+                .iter()
+                .map(|l| l.as_str())
+                .collect::<Vec<&str>>()
                 .join(" ")
-        ),
+        )),
+        Value::Closure(c) => output.push_str(&format!("#<lambda {}>", c.function.params)),
+        Value::Native(f) => output.push_str(&format!("{:?}", f)),
+        Value::Foreign(f) => output.push_str(&f.to_string()),
+        Value::Env(_) => output.push_str("#<env>"),
+        Value::List(l) => {
+            output.push('(');
+            for (i, item) in l.iter().enumerate() {
+                if i > 0 {
+                    output.push(' ');
+                }
+                print_value(output, item, readable);
+            }
+            output.push(')');
+        }
     }
 }
+
+/// Prints a value the way `write` does: data is printed so reading it gives back an equal value, with
+/// strings escaped and symbols the reader would read differently written between |s. Functions and other
+/// objects that can't be read back are printed as #<...>, which the reader rejects.
+pub fn value_to_string(value: &Value) -> String {
+    let mut output = String::new();
+    print_value(&mut output, value, true);
+    output
+}
+
+/// Prints a value the way `display` does, for people rather than the reader: strings (even inside lists)
+/// are printed without quotes or escapes, and symbols as just their name.
+pub fn display_to_string(value: &Value) -> String {
+    let mut output = String::new();
+    print_value(&mut output, value, false);
+    output
+}
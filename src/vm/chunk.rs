@@ -0,0 +1,119 @@
+use crate::{parse::*, util::value_to_string};
+use std::{fmt::Write, rc::Rc};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+    LessEqual,
+    GreaterEqual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Nil,
+    T,
+    // push constants[i]
+    Const(usize),
+    // locals live in the frame of the function that declared them, `depth` functions up from the current one
+    GetLocal { depth: usize, slot: usize },
+    // pops the value and stores it in the local
+    SetLocal { depth: usize, slot: usize },
+    // the name of the global is constants[i]
+    GetGlobal(usize),
+    // defines the global constants[i] as the value on top of the stack, without popping it
+    DefGlobal(usize),
+    Pop,
+    Jump(usize),
+    // pops the condition and jumps if it's nil, errors if it's not a bool
+    JumpIfNil(usize),
+    // jumps if the value on top of the stack is nil (keeping it), otherwise pops it
+    JumpIfNilOrPop(usize),
+    // jumps if the value on top of the stack isn't nil (keeping it), otherwise pops it
+    JumpIfNotNilOrPop(usize),
+    // creates a closure of functions[i] that captures the current frame
+    Closure(usize),
+    // calls the function below the n arguments on top of the stack
+    Call(usize),
+    Return,
+    // fails with the message in constants[i]
+    Error(usize),
+
+    Add(usize),
+    Sub(usize),
+    Mul(usize),
+    Div(usize),
+    Compare(Comparison, usize),
+    Not,
+    Car,
+    Cdr,
+    Len,
+    // makes a list of the top n values
+    MakeList(usize),
+    // concatenates the top n lists into one
+    Concat(usize),
+    // replaces the top value with what it adds to a list as a dotted tail: its items if it's a list, or . and
+    // the value itself if it's not
+    DottedTail,
+}
+
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<Op>,
+    pub constants: Vec<Value>,
+    pub functions: Vec<Rc<Function>>,
+}
+
+impl Chunk {
+    pub fn add_constant(&mut self, value: Value) -> usize {
+        match self.constants.iter().position(|c| *c == value) {
+            Some(i) => i,
+            None => {
+                self.constants.push(value);
+                self.constants.len() - 1
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Function {
+    pub name: String,
+    pub params: LambdaParams,
+    // the number of local slots (parameters first, then let bindings) in the function's frame
+    pub slot_count: usize,
+    pub chunk: Chunk,
+}
+
+pub fn disassemble(function: &Function) -> String {
+    let mut out = String::new();
+    disassemble_into(function, &mut out);
+    out
+}
+
+fn disassemble_into(function: &Function, out: &mut String) {
+    let chunk = &function.chunk;
+    let _ = writeln!(
+        out,
+        "== {} ({:?}), {} slots ==",
+        function.name, function.params, function.slot_count
+    );
+
+    for (i, op) in chunk.code.iter().enumerate() {
+        let comment = match op {
+            Op::Const(c) | Op::GetGlobal(c) | Op::DefGlobal(c) | Op::Error(c) => {
+                format!("; {}", value_to_string(&chunk.constants[*c]))
+            }
+            Op::Closure(f) => format!("; {}", chunk.functions[*f].name),
+            _ => String::new(),
+        };
+        let _ = writeln!(out, "{:04} {:<40} {}", i, format!("{:?}", op), comment);
+    }
+
+    for function in chunk.functions.iter() {
+        let _ = writeln!(out);
+        disassemble_into(function, out);
+    }
+}
@@ -0,0 +1,673 @@
+use super::chunk::*;
+use crate::{env::*, eval::eval_value, parse::*, symbol::Symbol};
+use std::{cell::RefCell, rc::Rc};
+
+struct FunctionCompiler {
+    name: String,
+    params: LambdaParams,
+    chunk: Chunk,
+    // the locals that are currently in scope, later ones shadow earlier ones with the same name
+    locals: Vec<(Symbol, usize)>,
+    slot_count: usize,
+}
+
+impl FunctionCompiler {
+    fn new(name: String, params: LambdaParams) -> Self {
+        let mut compiler = Self {
+            name,
+            params,
+            chunk: Chunk::default(),
+            locals: vec![],
+            slot_count: 0,
+        };
+
+        for name in compiler.params.names() {
+            compiler.declare_local(name);
+        }
+
+        compiler
+    }
+
+    fn declare_local(&mut self, name: Symbol) -> usize {
+        // slots are never reused, so a closure can't see a later binding in a slot it captured
+        let slot = self.slot_count;
+        self.slot_count += 1;
+        self.locals.push((name, slot));
+        slot
+    }
+
+    fn resolve(&self, name: &Symbol) -> Option<usize> {
+        self.locals
+            .iter()
+            .rev()
+            .find(|(local, _)| local == name)
+            .map(|(_, slot)| *slot)
+    }
+}
+
+/// Compiles (already macro-expanded) forms to bytecode, resolving every local variable to a
+/// (depth, slot) pair and leaving everything else to be looked up as a global.
+struct Compiler<'a> {
+    // the function being compiled is the last one, the ones before it enclose it
+    functions: Vec<FunctionCompiler>,
+    // where the code was expanded, which is where the macros it defines are made
+    env: &'a Rc<RefCell<Env>>,
+}
+
+fn symbol(value: &Value) -> Option<Symbol> {
+    match value {
+        Value::Symbol(s) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+fn let_bindings(name: Symbol, list: &[Value]) -> Result<Vec<(Symbol, &Value)>, String> {
+    let bindings = match list.get(1) {
+        Some(Value::List(l)) => &l[..],
+        Some(Value::Nil) => &[],
+        _ => {
+            return Err(format!(
+                "First parameter to \"{}\" must be a list of bindings",
+                name
+            ))
+        }
+    };
+
+    let mut result = vec![];
+    for binding in bindings {
+        match binding {
+            Value::List(b) if b.len() == 2 => match &b[0] {
+                Value::Symbol(s) => result.push((s.clone(), &b[1])),
+                _ => {
+                    return Err(String::from(
+                        "The first parameter in each binding must be a symbol",
+                    ))
+                }
+            },
+            _ => return Err(String::from("Bindings need to be of the form (name value)")),
+        }
+    }
+
+    Ok(result)
+}
+
+impl Compiler<'_> {
+    fn current(&mut self) -> &mut FunctionCompiler {
+        self.functions.last_mut().unwrap()
+    }
+
+    fn emit(&mut self, op: Op) -> usize {
+        let code = &mut self.current().chunk.code;
+        code.push(op);
+        code.len() - 1
+    }
+
+    fn emit_constant(&mut self, value: Value) {
+        let i = self.current().chunk.add_constant(value);
+        self.emit(Op::Const(i));
+    }
+
+    fn emit_error(&mut self, message: &str) {
+        let i = self
+            .current()
+            .chunk
+            .add_constant(Value::String(Rc::from(message)));
+        self.emit(Op::Error(i));
+    }
+
+    fn next_index(&mut self) -> usize {
+        self.current().chunk.code.len()
+    }
+
+    // sets the target of the jump instruction at `at` to the next instruction
+    fn patch_jump(&mut self, at: usize) {
+        let target = self.next_index();
+        match &mut self.current().chunk.code[at] {
+            Op::Jump(t) | Op::JumpIfNil(t) | Op::JumpIfNilOrPop(t) | Op::JumpIfNotNilOrPop(t) => {
+                *t = target
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn compile_symbol(&mut self, name: Symbol) {
+        for (depth, function) in self.functions.iter().rev().enumerate() {
+            if let Some(slot) = function.resolve(&name) {
+                self.emit(Op::GetLocal { depth, slot });
+                return;
+            }
+        }
+
+        let i = self.current().chunk.add_constant(Value::Symbol(name));
+        self.emit(Op::GetGlobal(i));
+    }
+
+    fn compile_body(&mut self, forms: &[Value]) -> Result<(), String> {
+        if forms.is_empty() {
+            self.emit(Op::Nil);
+            return Ok(());
+        }
+
+        for (i, form) in forms.iter().enumerate() {
+            self.compile(form)?;
+            if i + 1 < forms.len() {
+                self.emit(Op::Pop);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn compile_args(&mut self, args: &[Value]) -> Result<usize, String> {
+        for arg in args {
+            self.compile(arg)?;
+        }
+
+        Ok(args.len())
+    }
+
+    fn compile_if(&mut self, list: &[Value]) -> Result<(), String> {
+        if list.len() != 3 && list.len() != 4 {
+            return Err(String::from("\"if\" requires 2 or 3 arguments"));
+        }
+
+        self.compile(&list[1])?;
+        let to_else = self.emit(Op::JumpIfNil(0));
+        self.compile(&list[2])?;
+        let to_end = self.emit(Op::Jump(0));
+
+        self.patch_jump(to_else);
+        if list.len() == 4 {
+            self.compile(&list[3])?;
+        } else {
+            self.emit_error("No else branch found");
+        }
+        self.patch_jump(to_end);
+
+        Ok(())
+    }
+
+    fn compile_logic(&mut self, name: Symbol, args: &[Value]) -> Result<(), String> {
+        if name == Symbol::NOT {
+            if args.len() != 1 {
+                return Err(String::from("\"not\" requires 1 argument"));
+            }
+            self.compile(&args[0])?;
+            self.emit(Op::Not);
+            return Ok(());
+        }
+
+        // and returns the first nil or the last value, or returns the first non-nil value
+        if args.is_empty() {
+            self.emit(if name == Symbol::AND { Op::T } else { Op::Nil });
+            return Ok(());
+        }
+
+        let mut jumps = vec![];
+        for (i, arg) in args.iter().enumerate() {
+            self.compile(arg)?;
+            if i + 1 < args.len() {
+                jumps.push(self.emit(if name == Symbol::AND {
+                    Op::JumpIfNilOrPop(0)
+                } else {
+                    Op::JumpIfNotNilOrPop(0)
+                }));
+            }
+        }
+
+        for jump in jumps {
+            self.patch_jump(jump);
+        }
+
+        Ok(())
+    }
+
+    fn compile_function(
+        &mut self,
+        name: String,
+        params: &Value,
+        body: &[Value],
+    ) -> Result<(), String> {
+        // the tree-walking evaluator already knows how to parse parameter lists
+        let lambda = eval_value(
+            &Value::List(vec![Value::Symbol(Symbol::LAMBDA), params.clone(), Value::Nil].into()),
+            &mut Env::new(),
+        )?;
+        let params = match lambda {
+            Value::Lambda { params, .. } => params,
+            _ => unreachable!(),
+        };
+
+        self.functions
+            .push(FunctionCompiler::new(name, Rc::unwrap_or_clone(params)));
+        self.compile_body(body)?;
+        self.emit(Op::Return);
+        let function = self.functions.pop().unwrap();
+
+        let chunk = &mut self.current().chunk;
+        chunk.functions.push(Rc::new(Function {
+            name: function.name,
+            params: function.params,
+            slot_count: function.slot_count,
+            chunk: function.chunk,
+        }));
+        let i = chunk.functions.len() - 1;
+        self.emit(Op::Closure(i));
+
+        Ok(())
+    }
+
+    fn compile_let(&mut self, name: Symbol, list: &[Value]) -> Result<(), String> {
+        let bindings = let_bindings(name.clone(), list)?;
+        let scope_start = self.current().locals.len();
+
+        match name {
+            // the values are evaluated before any of the names are in scope
+            Symbol::LET => {
+                for (_, value) in bindings.iter() {
+                    self.compile(value)?;
+                }
+                let slots: Vec<usize> = bindings
+                    .iter()
+                    .map(|(name, _)| self.current().declare_local(name.clone()))
+                    .collect();
+                for slot in slots.into_iter().rev() {
+                    self.emit(Op::SetLocal { depth: 0, slot });
+                }
+            }
+
+            Symbol::LET_STAR => {
+                for (name, value) in bindings {
+                    self.compile(value)?;
+                    let slot = self.current().declare_local(name);
+                    self.emit(Op::SetLocal { depth: 0, slot });
+                }
+            }
+
+            // letrec, all the names are in scope for all the values
+            _ => {
+                let slots: Vec<usize> = bindings
+                    .iter()
+                    .map(|(name, _)| self.current().declare_local(name.clone()))
+                    .collect();
+                for ((_, value), slot) in bindings.into_iter().zip(slots) {
+                    self.compile(value)?;
+                    self.emit(Op::SetLocal { depth: 0, slot });
+                }
+            }
+        }
+
+        self.compile_body(&list[2..])?;
+        self.current().locals.truncate(scope_start);
+        Ok(())
+    }
+
+    fn compile_labels(&mut self, list: &[Value]) -> Result<(), String> {
+        let definitions = match list.get(1) {
+            Some(Value::List(l)) => &l[..],
+            Some(Value::Nil) => &[],
+            _ => {
+                return Err(String::from(
+                    "First parameter to \"labels\" must be a list of function definitions",
+                ))
+            }
+        };
+
+        let scope_start = self.current().locals.len();
+        let mut functions = vec![];
+        for definition in definitions {
+            match definition {
+                Value::List(d) if d.len() == 3 => match &d[0] {
+                    Value::Symbol(name) => {
+                        let slot = self.current().declare_local(name.clone());
+                        functions.push((name, &d[1], &d[2], slot));
+                    }
+                    _ => return Err(String::from("Function names in \"labels\" must be symbols")),
+                },
+                _ => {
+                    return Err(String::from(
+                        "Function definitions need to be of the form (name (params) body)",
+                    ))
+                }
+            }
+        }
+
+        for (name, params, body, slot) in functions {
+            self.compile_function(name.to_string(), params, std::slice::from_ref(body))?;
+            self.emit(Op::SetLocal { depth: 0, slot });
+        }
+
+        self.compile_body(&list[2..])?;
+        self.current().locals.truncate(scope_start);
+        Ok(())
+    }
+
+    // pushes the quasiquoted value, evaluating the unquotes at depth 1 (see eval_quasiquote_value)
+    fn compile_quasiquote(&mut self, value: &Value, depth: usize) -> Result<(), String> {
+        let list = match value {
+            Value::List(l) => l,
+            _ => {
+                self.emit_constant(value.clone());
+                return Ok(());
+            }
+        };
+
+        let head = list.first().and_then(symbol);
+        let is_splice = |v: &Value| {
+            matches!(
+                symbol(v),
+                Some(Symbol::SPLICE_UNQUOTE | Symbol::UNQUOTE_SPLICING)
+            )
+        };
+
+        if list.len() == 2 {
+            match head {
+                Some(Symbol::UNQUOTE) if depth == 1 => return self.compile(&list[1]),
+                Some(
+                    Symbol::UNQUOTE
+                    | Symbol::SPLICE_UNQUOTE
+                    | Symbol::UNQUOTE_SPLICING
+                    | Symbol::QUASIQUOTE,
+                ) => {
+                    if is_splice(&list[0]) && depth == 1 {
+                        return Err(String::from(
+                            "\"splice-unquote\" can only be used inside of a list",
+                        ));
+                    }
+
+                    let depth = if head == Some(Symbol::QUASIQUOTE) {
+                        depth + 1
+                    } else {
+                        depth - 1
+                    };
+                    self.emit_constant(list[0].clone());
+                    self.compile_quasiquote(&list[1], depth)?;
+                    self.emit(Op::MakeList(2));
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        // consecutive items are collected into lists, which are concatenated with the spliced lists
+        let mut parts = 0;
+        let mut pending = 0;
+        let mut spliced = false;
+        for (i, item) in list.iter().enumerate() {
+            match item {
+                // a dotted tail is joined onto the list if it's one (see eval_quasiquote_value)
+                Value::Symbol(Symbol::DOT) if depth == 1 && i + 2 == list.len() => {
+                    if pending > 0 {
+                        self.emit(Op::MakeList(pending));
+                        parts += 1;
+                        pending = 0;
+                    }
+                    self.compile_quasiquote(&list[i + 1], depth)?;
+                    self.emit(Op::DottedTail);
+                    parts += 1;
+                    spliced = true;
+                    break;
+                }
+                Value::List(l) if depth == 1 && l.len() == 2 && is_splice(&l[0]) => {
+                    if pending > 0 {
+                        self.emit(Op::MakeList(pending));
+                        parts += 1;
+                        pending = 0;
+                    }
+                    self.compile(&l[1])?;
+                    parts += 1;
+                    spliced = true;
+                }
+                _ => {
+                    self.compile_quasiquote(item, depth)?;
+                    pending += 1;
+                }
+            }
+        }
+
+        if !spliced {
+            self.emit(Op::MakeList(pending));
+            return Ok(());
+        }
+
+        if pending > 0 {
+            self.emit(Op::MakeList(pending));
+            parts += 1;
+        }
+        self.emit(Op::Concat(parts));
+
+        Ok(())
+    }
+
+    fn compile_list(&mut self, list: &[Value]) -> Result<(), String> {
+        if list.is_empty() {
+            self.emit(Op::Nil);
+            return Ok(());
+        }
+
+        let head = match symbol(&list[0]) {
+            Some(head) => head,
+            None => return self.compile_call(list),
+        };
+        let args = &list[1..];
+
+        match head {
+            Symbol::QUOTE => {
+                if list.len() != 2 {
+                    return Err(String::from("\"quote\" requires 1 argument"));
+                }
+                self.emit_constant(list[1].clone());
+            }
+            Symbol::QUASIQUOTE => {
+                if list.len() != 2 {
+                    return Err(String::from("\"quasiquote\" requires 1 argument"));
+                }
+                self.compile_quasiquote(&list[1], 1)?;
+            }
+            Symbol::IF => self.compile_if(list)?,
+            Symbol::AND | Symbol::OR | Symbol::NOT => self.compile_logic(head, args)?,
+
+            Symbol::DEF | Symbol::DEFINE_SYNTAX => {
+                let name = match list.get(1) {
+                    Some(Value::Symbol(s)) if list.len() == 3 => s,
+                    _ => return Err(format!("\"{}\" requires a symbol and a value", head)),
+                };
+                self.compile(&list[2])?;
+                let i = self
+                    .current()
+                    .chunk
+                    .add_constant(Value::Symbol(name.clone()));
+                self.emit(Op::DefGlobal(i));
+            }
+
+            Symbol::LAMBDA => {
+                if list.len() < 3 {
+                    return Err(String::from(
+                        "\"lambda\" requires a parameter list and a body",
+                    ));
+                }
+                self.compile_function(String::from("lambda"), &list[1], &list[2..3])?;
+            }
+
+            // macros are expanded before compiling, so they are only kept around as values
+            Symbol::MACRO | Symbol::SYNTAX_RULES => {
+                let value = eval_value(&Value::List(List::from(list)), &mut self.env.clone())?;
+                self.emit_constant(value);
+            }
+
+            Symbol::LET | Symbol::LET_STAR | Symbol::LETREC => self.compile_let(head, list)?,
+            Symbol::LABELS => self.compile_labels(list)?,
+
+            Symbol::ADD | Symbol::SUB | Symbol::MUL | Symbol::DIV => {
+                let n = self.compile_args(args)?;
+                self.emit(match head {
+                    Symbol::ADD => Op::Add(n),
+                    Symbol::SUB => Op::Sub(n),
+                    Symbol::MUL => Op::Mul(n),
+                    _ => Op::Div(n),
+                });
+            }
+            Symbol::EQUAL
+            | Symbol::NOT_EQUAL
+            | Symbol::LESS
+            | Symbol::GREATER
+            | Symbol::LESS_EQUAL
+            | Symbol::GREATER_EQUAL => {
+                if args.len() < 2 {
+                    return Err(format!("\"{}\" requires at least 2 arguments", head));
+                }
+                let n = self.compile_args(args)?;
+                let comparison = match head {
+                    Symbol::EQUAL => Comparison::Equal,
+                    Symbol::NOT_EQUAL => Comparison::NotEqual,
+                    Symbol::LESS => Comparison::Less,
+                    Symbol::GREATER => Comparison::Greater,
+                    Symbol::LESS_EQUAL => Comparison::LessEqual,
+                    _ => Comparison::GreaterEqual,
+                };
+                self.emit(Op::Compare(comparison, n));
+            }
+            Symbol::CAR | Symbol::CDR | Symbol::LEN => {
+                if args.len() != 1 {
+                    return Err(format!("\"{}\" requires 1 argument", head));
+                }
+                self.compile(&args[0])?;
+                self.emit(match head {
+                    Symbol::CAR => Op::Car,
+                    Symbol::CDR => Op::Cdr,
+                    _ => Op::Len,
+                });
+            }
+
+            Symbol::MATCH
+            | Symbol::GENSYM
+            | Symbol::MACROEXPAND
+            | Symbol::MACROEXPAND_1
+            | Symbol::MACROEXPAND_ALL
+            | Symbol::SYMBOL_TO_STRING
+            | Symbol::STRING_TO_SYMBOL
+            | Symbol::INTERN
+            | Symbol::GC
+            | Symbol::GC_STATS
+            | Symbol::STRING_LENGTH
+            | Symbol::STRING_APPEND
+            | Symbol::SUBSTRING
+            | Symbol::NUMBER_TO_STRING
+            | Symbol::STRING_TO_NUMBER
+            | Symbol::PRINT
+            | Symbol::PRINTLN
+            | Symbol::PPRINT
+            | Symbol::WRITE
+            | Symbol::DISPLAY
+            | Symbol::READ_LINE
+            | Symbol::EXIT
+            | Symbol::GETENV
+            | Symbol::COMMAND_LINE
+            | Symbol::CURRENT_TIME
+            | Symbol::SLEEP
+            | Symbol::LOAD
+            | Symbol::MODULE
+            | Symbol::IMPORT
+            | Symbol::EQUAL_P
+            | Symbol::SEND
+            | Symbol::FOREIGN_TYPE
+            | Symbol::JSON_PARSE
+            | Symbol::JSON_STRINGIFY
+            | Symbol::READ
+            | Symbol::READ_CHAR
+            | Symbol::EOF_OBJECT_P
+            | Symbol::NEWLINE
+            | Symbol::OPEN_INPUT_FILE
+            | Symbol::OPEN_OUTPUT_FILE
+            | Symbol::CLOSE_PORT
+            | Symbol::CURRENT_INPUT_PORT
+            | Symbol::CURRENT_OUTPUT_PORT
+            | Symbol::CURRENT_ERROR_PORT
+            | Symbol::WITH_OUTPUT_TO_PORT
+            | Symbol::WITH_INPUT_FROM_PORT
+            | Symbol::WITH_OUTPUT_TO_STRING
+            | Symbol::CALL_WITH_INPUT_STRING
+            | Symbol::READ_FILE
+            | Symbol::WRITE_FILE
+            | Symbol::APPEND_FILE
+            | Symbol::FILE_EXISTS
+            | Symbol::DIRECTORY_LIST
+            | Symbol::MAKE_DIRECTORY
+            | Symbol::DELETE_FILE
+            | Symbol::RENAME_FILE
+            | Symbol::FILE_METADATA
+            | Symbol::PATH_JOIN
+            | Symbol::PATH_NORMALIZE
+            | Symbol::READ_FROM_STRING
+            | Symbol::READ_ALL
+            | Symbol::EVAL
+            | Symbol::CURRENT_ENV
+            | Symbol::MAKE_ENV => {
+                return Err(format!("\"{}\" isn't supported by the compiler yet", head))
+            }
+
+            _ => self.compile_call(list)?,
+        }
+
+        Ok(())
+    }
+
+    fn compile_call(&mut self, list: &[Value]) -> Result<(), String> {
+        self.compile(&list[0])?;
+        let n = self.compile_args(&list[1..])?;
+        self.emit(Op::Call(n));
+        Ok(())
+    }
+
+    fn compile(&mut self, form: &Value) -> Result<(), String> {
+        match form {
+            Value::Nil => {
+                self.emit(Op::Nil);
+            }
+            Value::T => {
+                self.emit(Op::T);
+            }
+            Value::Symbol(s) | Value::LocalRef { name: s, .. } => self.compile_symbol(s.clone()),
+            Value::List(l) => self.compile_list(l)?,
+            _ => self.emit_constant(form.clone()),
+        }
+
+        Ok(())
+    }
+}
+
+/// Compiles a top-level form into a function without parameters, which runs the form when it's called.
+///
+/// The form should already be macro-expanded (see [`crate::eval::expand_value`]) in `env`.
+///
+/// The compiler supports a subset of the language: quote, quasiquote, if, and, or, not, def, lambda, let, let*,
+/// letrec, labels, arithmetic, comparisons, car, cdr, len and calls to compiled and native functions. Macros
+/// and syntax-rules are expanded beforehand, so the prelude's macros (when, unless, ...) work too. Other special
+/// forms and builtins, like match, gensym, macroexpand, eval and the string, I/O and module functions, are
+/// reported as errors when compiling. The globals the compiled code defines aren't in `env`, so a free identifier
+/// in a syntax-rules template that names one of them can be shadowed by a local variable where the macro is used.
+pub fn compile(form: &Value, env: &Rc<RefCell<Env>>) -> Result<Rc<Function>, String> {
+    let mut compiler = Compiler {
+        env,
+        functions: vec![FunctionCompiler::new(
+            String::from("toplevel"),
+            LambdaParams {
+                required: vec![],
+                optional: vec![],
+                keyword: vec![],
+                rest: None,
+            },
+        )],
+    };
+
+    compiler.compile(form)?;
+    compiler.emit(Op::Return);
+
+    let function = compiler.functions.pop().unwrap();
+    Ok(Rc::new(Function {
+        name: function.name,
+        params: function.params,
+        slot_count: function.slot_count,
+        chunk: function.chunk,
+    }))
+}
@@ -0,0 +1,366 @@
+use crate::{
+    env::Env, eval::expand_value, parse::*, prelude, symbol::Symbol, util::value_to_string,
+};
+use std::{cell::RefCell, cmp::Ordering, collections::HashMap, fmt::Debug, rc::Rc};
+
+mod chunk;
+mod compile;
+
+use chunk::*;
+pub use chunk::{disassemble, Function};
+pub use compile::compile;
+
+/// The local variables of one call of a function. Closures keep the frame they were created in alive,
+/// so the frames form a chain from the innermost function to the top-level one.
+pub struct Frame {
+    slots: RefCell<Vec<Value>>,
+    parent: Option<Rc<Frame>>,
+}
+
+impl Frame {
+    fn ancestor(self: &Rc<Self>, depth: usize) -> Rc<Frame> {
+        let mut frame = self.clone();
+        for _ in 0..depth {
+            frame = frame.parent.clone().unwrap();
+        }
+        frame
+    }
+}
+
+pub struct Closure {
+    pub function: Rc<Function>,
+    frame: Rc<Frame>,
+}
+
+impl Debug for Closure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "#<closure {} {}>",
+            self.function.name, self.function.params
+        )
+    }
+}
+
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.function, &other.function) && Rc::ptr_eq(&self.frame, &other.frame)
+    }
+}
+
+struct CallFrame {
+    function: Rc<Function>,
+    ip: usize,
+    frame: Rc<Frame>,
+    // the index of the called function on the stack, everything from it onwards is removed when returning
+    stack_base: usize,
+}
+
+pub struct Vm {
+    stack: Vec<Value>,
+    globals: HashMap<Symbol, Value>,
+    // how many calls can be nested, None means unlimited. The calls don't use the Rust stack, so this only
+    // stops runaway recursion from using up all the memory
+    max_depth: Option<usize>,
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self {
+            stack: vec![],
+            globals: HashMap::new(),
+            max_depth: Some(100_000),
+        }
+    }
+}
+
+fn number(value: Value) -> Result<f64, String> {
+    match value {
+        Value::Number(n) => Ok(n),
+        _ => Err(String::from("All arguments must be numbers")),
+    }
+}
+
+fn bool_value(b: bool) -> Value {
+    if b {
+        Value::T
+    } else {
+        Value::Nil
+    }
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates a VM with the globals the prelude defines, compiled from its source.
+    pub fn with_prelude() -> Self {
+        let mut vm = Self::new();
+        let mut env = Env::new();
+        for form in prelude::forms() {
+            let function = expand_value(&form, &mut env)
+                .and_then(|expanded| compile(&expanded, &env))
+                .expect("Could not compile the prelude");
+            vm.run(function).expect("Could not run the prelude");
+        }
+        vm
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("VM stack underflow")
+    }
+
+    fn pop_n(&mut self, n: usize) -> Vec<Value> {
+        self.stack.split_off(self.stack.len() - n)
+    }
+
+    fn pop_numbers(&mut self, n: usize) -> Result<Vec<f64>, String> {
+        self.pop_n(n).into_iter().map(number).collect()
+    }
+
+    fn call_frame(closure: &Closure, args: &[Value], stack_base: usize) -> CallFrame {
+        let function = &closure.function;
+        let mut slots = function.params.bind(args);
+        slots.resize(function.slot_count, Value::Nil);
+
+        CallFrame {
+            function: function.clone(),
+            ip: 0,
+            frame: Rc::new(Frame {
+                slots: RefCell::new(slots),
+                parent: Some(closure.frame.clone()),
+            }),
+            stack_base,
+        }
+    }
+
+    /// Runs a compiled top-level form (see [`compile`]) and returns its value. Globals it defines are
+    /// kept in the VM, so they can be used by forms run after it.
+    pub fn run(&mut self, function: Rc<Function>) -> Result<Value, String> {
+        let result = self.execute(function);
+        if result.is_err() {
+            self.stack.clear();
+        }
+        result
+    }
+
+    fn execute(&mut self, function: Rc<Function>) -> Result<Value, String> {
+        let base = self.stack.len();
+        let mut frames = vec![CallFrame {
+            frame: Rc::new(Frame {
+                slots: RefCell::new(vec![Value::Nil; function.slot_count]),
+                parent: None,
+            }),
+            function,
+            ip: 0,
+            stack_base: base,
+        }];
+
+        loop {
+            let current = frames.last_mut().unwrap();
+            let op = current.function.chunk.code[current.ip];
+            current.ip += 1;
+
+            match op {
+                Op::Nil => self.stack.push(Value::Nil),
+                Op::T => self.stack.push(Value::T),
+                Op::Const(i) => self.stack.push(current.function.chunk.constants[i].clone()),
+
+                Op::GetLocal { depth, slot } => {
+                    let value = current.frame.ancestor(depth).slots.borrow()[slot].clone();
+                    self.stack.push(value);
+                }
+                Op::SetLocal { depth, slot } => {
+                    let frame = current.frame.ancestor(depth);
+                    frame.slots.borrow_mut()[slot] = self.pop();
+                }
+                Op::GetGlobal(i) => {
+                    let name = &current.function.chunk.constants[i];
+                    let value = match name {
+                        Value::Symbol(s) => self.globals.get(s),
+                        _ => None,
+                    };
+                    match value {
+                        Some(value) => self.stack.push(value.clone()),
+                        None => return Err(format!("Unbound symbol: {}", value_to_string(name))),
+                    }
+                }
+                Op::DefGlobal(i) => {
+                    if let Value::Symbol(s) = &current.function.chunk.constants[i] {
+                        let value = self.stack.last().unwrap().clone();
+                        self.globals.insert(s.clone(), value);
+                    }
+                }
+
+                Op::Pop => {
+                    self.pop();
+                }
+                Op::Jump(target) => current.ip = target,
+                Op::JumpIfNil(target) => match self.pop() {
+                    Value::Nil => current.ip = target,
+                    Value::T => {}
+                    _ => return Err(String::from("Condition must be a bool")),
+                },
+                Op::JumpIfNilOrPop(target) => {
+                    if *self.stack.last().unwrap() == Value::Nil {
+                        current.ip = target;
+                    } else {
+                        self.pop();
+                    }
+                }
+                Op::JumpIfNotNilOrPop(target) => {
+                    if *self.stack.last().unwrap() != Value::Nil {
+                        current.ip = target;
+                    } else {
+                        self.pop();
+                    }
+                }
+
+                Op::Closure(i) => {
+                    let closure = Closure {
+                        function: current.function.chunk.functions[i].clone(),
+                        frame: current.frame.clone(),
+                    };
+                    self.stack.push(Value::Closure(Rc::new(closure)));
+                }
+                Op::Call(n) => {
+                    let args = self.pop_n(n);
+                    let stack_base = self.stack.len() - 1;
+                    match &self.stack[stack_base] {
+                        Value::Closure(closure) => {
+                            // the top-level form's frame doesn't count
+                            if self.max_depth.is_some_and(|max| frames.len() > max) {
+                                return Err(String::from("Call depth limit exceeded"));
+                            }
+                            let frame = Self::call_frame(closure, &args, stack_base);
+                            frames.push(frame);
+                        }
+                        Value::Native(function) => {
+                            let value = function.call(&args)?;
+                            self.stack.truncate(stack_base);
+                            self.stack.push(value);
+                        }
+                        Value::Lambda { is_macro: true, .. } | Value::Syntax { .. } => {
+                            return Err(String::from(
+                                "Macros need to be expanded before the code is compiled",
+                            ))
+                        }
+                        Value::Lambda { .. } => {
+                            return Err(String::from(
+                                "Lambdas made by the evaluator can't be called by the VM",
+                            ))
+                        }
+                        _ => return Err(String::from("First parameter is not a function")),
+                    }
+                }
+                Op::Return => {
+                    let value = self.pop();
+                    let finished = frames.pop().unwrap();
+                    self.stack.truncate(finished.stack_base);
+                    if frames.is_empty() {
+                        return Ok(value);
+                    }
+                    self.stack.push(value);
+                }
+                Op::Error(i) => {
+                    return Err(match &current.function.chunk.constants[i] {
+                        Value::String(message) => message.to_string(),
+                        value => value_to_string(value),
+                    })
+                }
+
+                Op::Add(n) => {
+                    let numbers = self.pop_numbers(n)?;
+                    self.stack.push(Value::Number(numbers.iter().sum()));
+                }
+                Op::Mul(n) => {
+                    let numbers = self.pop_numbers(n)?;
+                    self.stack.push(Value::Number(numbers.iter().product()));
+                }
+                Op::Sub(n) | Op::Div(n) => {
+                    let numbers = self.pop_numbers(n)?;
+                    let is_sub = matches!(op, Op::Sub(_));
+                    let result = match numbers.split_first() {
+                        // return the negative if there's only 1 argument
+                        Some((first, [])) if is_sub => -first,
+                        Some((first, rest)) => {
+                            rest.iter()
+                                .fold(*first, |r, n| if is_sub { r - n } else { r / n })
+                        }
+                        None => return Err(String::from("At least 1 argument is required")),
+                    };
+                    self.stack.push(Value::Number(result));
+                }
+                Op::Compare(comparison, n) => {
+                    let numbers = self.pop_numbers(n)?;
+                    let result = numbers.windows(2).all(|pair| {
+                        let ordering = pair[0].partial_cmp(&pair[1]);
+                        match comparison {
+                            Comparison::Equal => pair[0] == pair[1],
+                            Comparison::NotEqual => pair[0] != pair[1],
+                            Comparison::Less => ordering == Some(Ordering::Less),
+                            Comparison::Greater => ordering == Some(Ordering::Greater),
+                            Comparison::LessEqual => {
+                                matches!(ordering, Some(Ordering::Less | Ordering::Equal))
+                            }
+                            Comparison::GreaterEqual => {
+                                matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+                            }
+                        }
+                    });
+                    self.stack.push(bool_value(result));
+                }
+                Op::Not => {
+                    let value = self.pop();
+                    self.stack.push(bool_value(value == Value::Nil));
+                }
+                Op::Car | Op::Cdr | Op::Len => {
+                    let list = match self.pop() {
+                        Value::List(l) => l,
+                        _ => return Err(String::from("Argument needs to be a list")),
+                    };
+                    self.stack.push(match op {
+                        Op::Car => list.first().cloned().unwrap_or(Value::Nil),
+                        Op::Cdr if list.len() < 2 => Value::Nil,
+                        Op::Cdr => Value::List(list.skip(1)),
+                        _ => Value::Number(list.len() as f64),
+                    });
+                }
+                Op::MakeList(n) => {
+                    let items = self.pop_n(n);
+                    self.stack.push(Value::List(items.into()));
+                }
+                Op::Concat(n) => {
+                    let mut items = vec![];
+                    for part in self.pop_n(n) {
+                        match part {
+                            Value::List(l) => items.extend(l.iter().cloned()),
+                            Value::Nil => {}
+                            value => {
+                                return Err(format!(
+                                    "\"splice-unquote\" requires a list, got {}",
+                                    value_to_string(&value)
+                                ))
+                            }
+                        }
+                    }
+                    self.stack.push(Value::List(items.into()));
+                }
+                Op::DottedTail => {
+                    let tail = self.stack.pop().unwrap();
+                    if !matches!(tail, Value::List(_) | Value::Nil) {
+                        self.stack
+                            .push(Value::List(vec![Value::Symbol(Symbol::DOT), tail].into()));
+                    } else {
+                        self.stack.push(tail);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+mod common;
+use common::{check, check_err};
+
+#[test]
+fn strings() {
+    check("(string-length \"héllo\")", "5");
+    check("(string-append \"a\" \"bc\" \"\")", "\"abc\"");
+    check("(string-append)", "\"\"");
+    // counted in characters
+    check("(substring \"héllo\" 1 3)", "\"él\"");
+    check("(substring \"abc\" 1)", "\"bc\"");
+    check("(substring \"abc\" 3)", "\"\"");
+    check("(number->string 1.5)", "\"1.5\"");
+    check("(string->number \" 12 \")", "12");
+    check("(string->number \"x\")", "nil");
+
+    check_err(
+        "(string-length 1)",
+        "Argument to \"string-length\" must be a string",
+    );
+    check_err(
+        "(string-append \"a\" 1)",
+        "Arguments to \"string-append\" must be strings",
+    );
+    check_err(
+        "(substring \"abc\" 4)",
+        "Indices passed to \"substring\" must be integers between 0 and 3",
+    );
+    check_err(
+        "(substring \"abc\" 1.5)",
+        "Indices passed to \"substring\" must be integers between 0 and 3",
+    );
+    check_err(
+        "(substring \"abc\" 2 1)",
+        "The start of a substring can't be after its end",
+    );
+    check_err(
+        "(number->string \"1\")",
+        "Argument to \"number->string\" must be a number",
+    );
+}
+
+#[test]
+fn output() {
+    // print separates its arguments with spaces and writes strings without quotes, display does too, and
+    // write quotes them
+    check(
+        "(with-output-to-string (lambda () (let () (print 1 \"a\" 'b) (newline) (display \"x\") (write \"x\") (println))))",
+        "\"1 a b\\nx\\\"x\\\"\\n\"",
+    );
+    check(
+        "(with-output-to-string (lambda () (println \"a\" \"b\")))",
+        "\"a b\\n\"",
+    );
+    check_err("(newline 1 2)", "\"newline\" requires 0 or 1 arguments");
+}
+
+#[test]
+fn process() {
+    std::env::set_var("EUPHIE_BUILTINS_TEST", "value");
+    check("(getenv \"EUPHIE_BUILTINS_TEST\")", "\"value\"");
+    check("(getenv \"EUPHIE_BUILTINS_TEST_UNSET\")", "nil");
+    check_err("(getenv 'HOME)", "Argument to \"getenv\" must be a string");
+    check("(> (len (command-line)) 0)", "t");
+    check_err(
+        "(command-line 1)",
+        "\"command-line\" doesn't take any arguments",
+    );
+    check_err("(exit 1.5)", "Exit code must be an integer");
+}
+
+#[test]
+fn time() {
+    check(
+        "(let ((start (current-time))) (sleep 0.01) (>= (- (current-time) start) 0.01))",
+        "t",
+    );
+    check("(> (current-time) 1600000000)", "t");
+    check_err(
+        "(sleep -1)",
+        "Argument to \"sleep\" must be a non-negative number of seconds",
+    );
+    check_err(
+        "(current-time 1)",
+        "\"current-time\" doesn't take any arguments",
+    );
+}
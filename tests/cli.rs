@@ -0,0 +1,72 @@
+use std::process::Command;
+
+mod common;
+use common::temp_dir;
+
+// runs the interpreter on a file with the code in it, returning what it printed
+fn run(name: &str, code: &str, flags: &[&str]) -> String {
+    let dir = temp_dir(name);
+    let path = dir.join("main.lisp");
+    std::fs::write(&path, code).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_euphie"))
+        .args(flags)
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn running() {
+    assert_eq!(run("cli-run", "(def x 2) (* x 3)", &[]), "Result: 6\n");
+    assert_eq!(
+        run("cli-run-error", "(car 1 2)", &[]),
+        "Error: \"car\" requires 1 argument\n"
+    );
+}
+
+#[test]
+fn expanding() {
+    assert_eq!(
+        run("cli-expand", "(when t 1)", &["--expand"]),
+        "(if t (let () 1) nil)\n"
+    );
+    assert_eq!(
+        run(
+            "cli-expand-error",
+            "(def m (macro (x) (car 1 2))) (m 1)",
+            &["--expand"]
+        ),
+        "(def m (macro (x) (car 1 2)))\nError: \"car\" requires 1 argument\n"
+    );
+}
+
+#[test]
+fn missing_files() {
+    let output = Command::new(env!("CARGO_BIN_EXE_euphie"))
+        .arg("/nonexistent/main.lisp")
+        .output()
+        .unwrap();
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with("Error: Could not read /nonexistent/main.lisp"));
+}
+
+#[test]
+fn running_on_the_vm() {
+    assert_eq!(
+        run("cli-vm", "(let* ((a 1)) (list a))", &["--vm"]),
+        "Result: (1)\n"
+    );
+    assert_eq!(
+        run("cli-vm-error", "(def x 1) (car nil) x", &["--vm"]),
+        "Error: Argument needs to be a list\n"
+    );
+    assert_eq!(
+        run("cli-vm-compile-error", "(gensym)", &["--vm"]),
+        "Error: \"gensym\" isn't supported by the compiler yet\n"
+    );
+}
@@ -0,0 +1,56 @@
+// shared by the integration tests, which don't all use everything in here
+#![allow(dead_code)]
+
+use euphie::{
+    interpreter::Interpreter,
+    sandbox::{EnvBuilder, Module},
+    util::value_to_string,
+};
+use std::path::{Path, PathBuf};
+
+/// Evaluates a top-level form, returning the value written out, or the error message.
+pub fn eval(interpreter: &mut Interpreter, code: &str) -> Result<String, String> {
+    interpreter
+        .eval_str(code)
+        .map(|value| value_to_string(&value))
+        .map_err(|e| e.to_string())
+}
+
+/// Evaluates top-level forms one after the other with a new interpreter, returning the value of the last one
+/// written out, or the first error message.
+pub fn eval_all(forms: &[&str]) -> Result<String, String> {
+    let mut interpreter = Interpreter::new();
+    let mut result = String::from("nil");
+    for code in forms {
+        result = eval(&mut interpreter, code)?;
+    }
+    Ok(result)
+}
+
+/// Checks that a form evaluates to the value that's written as `expected`.
+pub fn check(code: &str, expected: &str) {
+    assert_eq!(eval_all(&[code]), Ok(String::from(expected)), "{}", code);
+}
+
+/// Checks that evaluating a form fails with the error message `expected`.
+pub fn check_err(code: &str, expected: &str) {
+    assert_eq!(eval_all(&[code]), Err(String::from(expected)), "{}", code);
+}
+
+/// Makes an interpreter whose root environment only has the builtins of the given modules.
+pub fn sandboxed(modules: &[Module]) -> Interpreter {
+    Interpreter::with_env(EnvBuilder::new().modules(modules).build())
+}
+
+/// Like [`sandboxed`], with filesystem access restricted to `root`.
+pub fn sandboxed_in(modules: &[Module], root: &Path) -> Interpreter {
+    Interpreter::with_env(EnvBuilder::new().modules(modules).fs_root(root).build())
+}
+
+/// Makes an empty directory for a test to put files in.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("euphie-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,109 @@
+use euphie::{foreign::*, interpreter::*};
+use std::{cell::Cell, rc::Rc};
+
+mod common;
+use common::eval;
+
+struct Entity {
+    id: u32,
+    x: Cell<f64>,
+}
+
+fn interpreter() -> Interpreter {
+    let entity = Rc::new(
+        ForeignType::new("entity")
+            .typed_method("x", |e: Handle<Entity>| e.x.get())
+            .typed_method("move", |e: Handle<Entity>, dx: f64| {
+                e.x.set(e.x.get() + dx);
+                e
+            })
+            .equal(|a: &Entity, b: &Entity| a.id == b.id)
+            .show(|e: &Entity| e.id.to_string()),
+    );
+    let connection = Rc::new(ForeignType::new("connection"));
+
+    let mut interpreter = Interpreter::new();
+    {
+        let mut env = interpreter.env().borrow_mut();
+        env.register_typed_fn("make-entity", move |id: u32| {
+            entity.wrap(Entity {
+                id,
+                x: Cell::new(0.0),
+            })
+        });
+        env.register_typed_fn("connect", move || connection.wrap(()));
+        env.register_typed_fn("entity-id", |e: Handle<Entity>| e.id);
+    }
+    interpreter
+}
+
+#[test]
+fn printing() {
+    let mut interpreter = interpreter();
+    assert_eq!(
+        eval(&mut interpreter, "(make-entity 3)"),
+        Ok(String::from("#<entity 3>"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(connect)"),
+        Ok(String::from("#<connection>"))
+    );
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            "(list (foreign-type (connect)) (foreign-type 1))"
+        ),
+        Ok(String::from("(\"connection\" nil)"))
+    );
+}
+
+#[test]
+fn methods() {
+    let mut interpreter = interpreter();
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            "(let ((e (make-entity 1))) (send (send e :move 2) :move 3) (send e :x))"
+        ),
+        Ok(String::from("5"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(send (make-entity 1) :jump)"),
+        Err(String::from("entity has no method jump"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(send (connect) :x)"),
+        Err(String::from("connection has no method x"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(entity-id (make-entity 7))"),
+        Ok(String::from("7"))
+    );
+    assert!(eval(&mut interpreter, "(entity-id (connect))")
+        .unwrap_err()
+        .starts_with("Argument 1 to \"entity-id\": expected a "));
+}
+
+#[test]
+fn equality() {
+    let mut interpreter = interpreter();
+    assert_eq!(
+        eval(&mut interpreter, "(equal? (make-entity 1) (make-entity 1))"),
+        Ok(String::from("t"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(equal? (make-entity 1) (make-entity 2))"),
+        Ok(String::from("nil"))
+    );
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            "(let ((c (connect))) (list (equal? c c) (equal? c (connect))))"
+        ),
+        Ok(String::from("(t nil)"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(equal? '(1 \"a\" (b)) '(1 \"a\" (b)))"),
+        Ok(String::from("t"))
+    );
+}
@@ -0,0 +1,117 @@
+use euphie::{cst::*, formatter::format_code, parse::parse_all, tokenize::tokenize};
+
+const MESSY: &str = "\
+; a comment at the start
+
+
+(def fact (lambda (n)
+      (if (= n 0)
+  1
+                (* n (fact (- n 1))))))   ; trailing
+
+
+
+(def xs '(1 2
+  3 4)) (def y   `(a ,@b))
+(some-function arg1
+  arg2 ; why
+     arg3
+  )
+(let ((a 1)
+   (b 2))
+    ; comment in body
+
+     (+ a b)
+  ; last comment
+  )
+(f
+x y)
+(module m (export a)
+(def a 1))";
+
+const FORMATTED: &str = "\
+; a comment at the start
+
+(def fact (lambda (n)
+            (if (= n 0)
+                1
+                (* n (fact (- n 1)))))) ; trailing
+
+(def xs '(1 2
+          3 4))
+(def y `(a ,@b))
+(some-function arg1
+               arg2 ; why
+               arg3)
+(let ((a 1)
+      (b 2))
+  ; comment in body
+
+  (+ a b)
+  ; last comment
+  )
+(f
+ x y)
+(module m (export a)
+  (def a 1))
+";
+
+fn read(code: &str) -> String {
+    let mut tokens = tokenize(String::from(code)).unwrap();
+    tokens.reverse();
+    format!("{:?}", parse_all(&mut tokens).unwrap())
+}
+
+#[test]
+fn lossless() {
+    for code in [MESSY, FORMATTED, "", "  ; only a comment"] {
+        assert_eq!(parse_cst(code).unwrap().to_string(), code);
+    }
+}
+
+#[test]
+fn trivia() {
+    let cst = parse_cst("(a ; b\n 'c) ; d").unwrap();
+    assert_eq!(
+        cst.end,
+        vec![
+            Trivia::Whitespace(String::from(" ")),
+            Trivia::Comment(String::from("; d"))
+        ]
+    );
+    let Node::List { items, .. } = &cst.items[0].node else {
+        panic!("expected a list");
+    };
+    assert_eq!(items[1].node, Node::Atom(String::from("'c")));
+    assert_eq!(
+        items[1].trivia,
+        vec![
+            Trivia::Whitespace(String::from(" ")),
+            Trivia::Comment(String::from("; b")),
+            Trivia::Whitespace(String::from("\n ")),
+        ]
+    );
+}
+
+#[test]
+fn formatting() {
+    assert_eq!(format_code(MESSY), Ok(String::from(FORMATTED)));
+    assert_eq!(format_code(FORMATTED), Ok(String::from(FORMATTED)));
+    // the code means the same thing
+    assert_eq!(read(MESSY), read(FORMATTED));
+
+    assert_eq!(format_code(""), Ok(String::new()));
+    assert_eq!(
+        format_code("(a"),
+        Err(String::from("Expected ')' at end of file"))
+    );
+    assert_eq!(format_code("a)"), Err(String::from("Unexpected ')'")));
+    assert_eq!(
+        format_code("(a ')"),
+        Err(String::from("Expected a form after the prefix '"))
+    );
+    assert_eq!(
+        format_code("\"unterminated"),
+        Err(String::from("Unterminated string"))
+    );
+}
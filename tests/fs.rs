@@ -0,0 +1,105 @@
+use euphie::{interpreter::*, sandbox::*};
+
+mod common;
+use common::{eval, sandboxed, sandboxed_in, temp_dir};
+
+#[test]
+fn files() {
+    let dir = temp_dir("fs-files");
+    let mut interpreter = sandboxed_in(&[Module::Core, Module::Fs], &dir);
+
+    eval(&mut interpreter, r#"(write-file "a.txt" "one\n")"#).unwrap();
+    eval(&mut interpreter, r#"(append-file "a.txt" "two")"#).unwrap();
+    assert_eq!(
+        eval(&mut interpreter, r#"(read-file "a.txt")"#),
+        Ok(String::from("\"one\\ntwo\""))
+    );
+    assert_eq!(
+        eval(&mut interpreter, r#"(file-exists? "a.txt")"#),
+        Ok(String::from("t"))
+    );
+
+    eval(&mut interpreter, r#"(make-directory "sub/dir")"#).unwrap();
+    eval(&mut interpreter, r#"(rename-file "a.txt" "sub/b.txt")"#).unwrap();
+    assert_eq!(
+        eval(&mut interpreter, r#"(directory-list ".")"#),
+        Ok(String::from("(\"sub\")"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, r#"(directory-list "sub")"#),
+        Ok(String::from("(\"b.txt\" \"dir\")"))
+    );
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            r#"(list (car (file-metadata "sub/b.txt")) (car (cdr (file-metadata "sub/b.txt"))) (car (cdr (cdr (cdr (file-metadata "sub/b.txt"))))))"#
+        ),
+        Ok(String::from("(:type :file 7)"))
+    );
+
+    eval(&mut interpreter, r#"(delete-file "sub/b.txt")"#).unwrap();
+    eval(&mut interpreter, r#"(delete-file "sub/dir")"#).unwrap();
+    assert_eq!(
+        eval(&mut interpreter, r#"(file-exists? "sub/b.txt")"#),
+        Ok(String::from("nil"))
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn errors() {
+    let dir = temp_dir("fs-errors");
+    let mut interpreter = sandboxed_in(&[Module::Core, Module::Fs], &dir);
+
+    assert_eq!(
+        eval(&mut interpreter, r#"(read-file "../secret")"#),
+        Err(String::from(
+            "Path \"../secret\" is outside of the filesystem root"
+        ))
+    );
+    assert!(eval(&mut interpreter, r#"(read-file "missing.txt")"#)
+        .unwrap_err()
+        .starts_with("Could not read"));
+    assert!(eval(&mut interpreter, r#"(delete-file "missing.txt")"#)
+        .unwrap_err()
+        .starts_with("Could not delete"));
+    assert_eq!(
+        eval(&mut interpreter, r#"(write-file "a.txt" 1)"#),
+        Err(String::from("Arguments to \"write-file\" must be strings"))
+    );
+
+    // without the fs module, the builtins are unbound
+    let mut interpreter = sandboxed(&[Module::Core]);
+    assert_eq!(
+        eval(&mut interpreter, r#"(file-exists? "a.txt")"#),
+        Err(String::from("Unbound symbol: file-exists?"))
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn paths() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        eval(&mut interpreter, r#"(path-join "a" "b/c" "d.txt")"#),
+        Ok(String::from("\"a/b/c/d.txt\""))
+    );
+    assert_eq!(
+        eval(&mut interpreter, r#"(path-join "a" "/b")"#),
+        Ok(String::from("\"/b\""))
+    );
+    assert_eq!(
+        eval(&mut interpreter, r#"(path-normalize "a/./b/../../../c")"#),
+        Ok(String::from("\"../c\""))
+    );
+    assert_eq!(
+        eval(&mut interpreter, r#"(path-normalize "/../a/.")"#),
+        Ok(String::from("\"/a\""))
+    );
+    assert_eq!(
+        eval(&mut interpreter, r#"(path-normalize "a/..")"#),
+        Ok(String::from("\".\""))
+    );
+}
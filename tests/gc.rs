@@ -0,0 +1,43 @@
+use euphie::{env::Env, eval::eval_toplevel, gc, parse::*, tokenize::tokenize};
+use std::{cell::RefCell, rc::Rc};
+
+fn eval(env: &mut Rc<RefCell<Env>>, code: &str) -> Result<Value, String> {
+    let mut tokens = tokenize(String::from(code))?;
+    tokens.reverse();
+    eval_toplevel(&parse(&mut tokens)?, env)
+}
+
+#[test]
+fn cycles_are_collected() {
+    let mut env = Env::new();
+    gc::collect();
+    let baseline = gc::stats().live;
+
+    // the lambda is stored in the environment it captures, so neither of them is ever freed by reference
+    // counting alone
+    for _ in 0..1000 {
+        eval(&mut env, "(let () (def f (lambda (x) (+ x 1))) (f 1))").unwrap();
+    }
+    assert!(gc::stats().live >= baseline + 1000);
+
+    let freed = gc::collect();
+    assert!(freed >= 1000);
+    assert_eq!(gc::stats().live, baseline);
+}
+
+#[test]
+fn reachable_environments_are_kept() {
+    let mut env = Env::new();
+    eval(
+        &mut env,
+        "(def counter (let ((n 41)) (def get (lambda () (+ n 1))) get))",
+    )
+    .unwrap();
+    for _ in 0..100 {
+        eval(&mut env, "(let () (def f (lambda () f)) nil)").unwrap();
+    }
+
+    gc::collect();
+    assert!(gc::stats().live <= 3);
+    assert_eq!(eval(&mut env, "(counter)"), Ok(Value::Number(42.0)));
+}
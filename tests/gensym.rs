@@ -0,0 +1,97 @@
+mod common;
+use common::{check, check_err, eval_all};
+
+#[test]
+fn gensym() {
+    // every symbol is new, so two of them never have the same name
+    let names = eval_all(&["`(,(gensym) ,(gensym))"]).unwrap();
+    let names: Vec<&str> = names
+        .trim_matches(|c| c == '(' || c == ')')
+        .split(' ')
+        .collect();
+    assert_eq!(names.len(), 2);
+    assert_ne!(names[0], names[1]);
+    assert!(names[0].starts_with("#:g"), "{}", names[0]);
+
+    for (code, prefix) in [("(gensym \"tmp\")", "#:tmp"), ("(gensym 'x)", "#:x")] {
+        let name = eval_all(&[code]).unwrap();
+        assert!(name.starts_with(prefix), "{}", name);
+    }
+
+    // and they can't be read back
+    let error = eval_all(&["'#:g0"]).unwrap_err();
+    assert!(error.contains("#:g0"), "{}", error);
+
+    check_err(
+        "(gensym 1)",
+        "The prefix for \"gensym\" must be a string or symbol",
+    );
+    check_err(
+        "(gensym \"a\" \"b\")",
+        "\"gensym\" requires 0 or 1 arguments",
+    );
+}
+
+#[test]
+fn with_gensyms() {
+    // the macro's variable can't capture the caller's
+    let my_or = "(def my-or (macro (a b) (with-gensyms (v) `(let ((,v ,a)) (if ,v ,v ,b)))))";
+    check(
+        &format!("(let () {} (let ((v 5)) (my-or nil v)))", my_or),
+        "5",
+    );
+    let expansion =
+        eval_all(&[&format!("(let () {} (macroexpand-1 '(my-or 1 2)))", my_or)]).unwrap();
+    assert!(expansion.starts_with("(let ((#:v"), "{}", expansion);
+
+    check("(with-gensyms () 1)", "1");
+    // it's a macro in the prelude, which binds the names with let
+    check(
+        "(macroexpand '(with-gensyms (a) a))",
+        "(let ((a (gensym (quote a)))) a)",
+    );
+    check_err(
+        "(with-gensyms (1) 1)",
+        "The first parameter in each binding must be a symbol",
+    );
+    check_err("(with-gensyms a 1)", "Argument needs to be a list");
+}
+
+#[test]
+fn macroexpanding() {
+    let expand = |code: &str| {
+        eval_all(&[&format!(
+            "(let ()
+               (def inner (macro (x) `(+ ,x 1)))
+               (def outer (macro (x) `(inner (inner ,x))))
+               {})",
+            code
+        )])
+    };
+
+    // one step, until the head isn't a macro, or everywhere but in quoted forms
+    assert_eq!(
+        expand("(macroexpand-1 '(outer 1))"),
+        Ok(String::from("(inner (inner 1))"))
+    );
+    assert_eq!(
+        expand("(macroexpand '(outer 1))"),
+        Ok(String::from("(+ (inner 1) 1)"))
+    );
+    assert_eq!(
+        expand("(macroexpand-all '(outer 1))"),
+        Ok(String::from("(+ (+ 1 1) 1)"))
+    );
+    assert_eq!(
+        expand("(macroexpand-all '(car (outer 1) '(outer 2)))"),
+        Ok(String::from("(car (+ (+ 1 1) 1) (quote (outer 2)))"))
+    );
+
+    // forms that aren't macro calls are returned as they are
+    assert_eq!(
+        expand("(macroexpand-1 '(car 1))"),
+        Ok(String::from("(car 1)"))
+    );
+    assert_eq!(expand("(macroexpand-all 5)"), Ok(String::from("5")));
+    check_err("(macroexpand-1)", "\"macroexpand-1\" requires 1 argument");
+}
@@ -0,0 +1,188 @@
+use euphie::{
+    interpreter::*,
+    json::{self, Keys},
+    parse::Value,
+    symbol::Symbol,
+    util::value_to_string,
+};
+
+fn parse(text: &str) -> Result<String, String> {
+    json::parse(text, Keys::Keyword).map(|value| value_to_string(&value))
+}
+
+#[test]
+fn parsing() {
+    assert_eq!(
+        parse(r#" {"a": [1, -2.5e1, true, false, null], "b": {"c": "é😀\n"}, "d": {}} "#),
+        Ok(String::from(
+            "(:object :a (1 -25 t :false nil) :b (:object :c \"é😀\\n\") :d (:object))"
+        ))
+    );
+    assert_eq!(
+        json::parse(r#"{"a b": 1}"#, Keys::String).map(|value| value_to_string(&value)),
+        Ok(String::from("(:object \"a b\" 1)"))
+    );
+}
+
+#[test]
+fn errors() {
+    assert_eq!(
+        parse("[1,\n  2,,]"),
+        Err(String::from(
+            "JSON error at line 2, column 5: expected a value, found ','"
+        ))
+    );
+    assert_eq!(
+        parse(r#"{"a" 1}"#),
+        Err(String::from(
+            "JSON error at line 1, column 6: expected ':', found '1'"
+        ))
+    );
+    assert_eq!(
+        parse("01"),
+        Err(String::from(
+            "JSON error at line 1, column 2: unexpected characters after the value"
+        ))
+    );
+    assert_eq!(
+        parse(r#""abc"#),
+        Err(String::from(
+            "JSON error at line 1, column 5: expected '\"', found the end of the input"
+        ))
+    );
+    assert_eq!(
+        parse("[tru]"),
+        Err(String::from(
+            "JSON error at line 1, column 5: expected \"true\", found ']'"
+        ))
+    );
+    assert!(parse(&"[".repeat(10000)).is_err());
+}
+
+#[test]
+fn stringifying() {
+    let value = json::parse(
+        r#"{"a": [1, 2.5, true, null], "b": {"c": "q\"\n\u0001"}}"#,
+        Keys::Keyword,
+    )
+    .unwrap();
+    assert_eq!(
+        json::stringify(&value, Keys::Keyword, false),
+        Ok(String::from(
+            r#"{"a":[1,2.5,true,null],"b":{"c":"q\"\n\u0001"}}"#
+        ))
+    );
+    assert_eq!(
+        json::stringify(&value, Keys::Keyword, true),
+        Ok(String::from(
+            "{\n  \"a\": [\n    1,\n    2.5,\n    true,\n    null\n  ],\n  \"b\": {\n    \"c\": \"q\\\"\\n\\u0001\"\n  }\n}"
+        ))
+    );
+    assert_eq!(
+        json::stringify(&Value::Number(f64::NAN), Keys::Keyword, false),
+        Err(String::from("NaN can't be converted to JSON"))
+    );
+
+    // objects need keys of the kind they're converted with, and a value for each of them
+    let object = json::parse(r#"{"a":1}"#, Keys::Keyword).unwrap();
+    assert_eq!(
+        json::stringify(&object, Keys::String, false),
+        Err(String::from("Keys of JSON objects must be strings"))
+    );
+    let object = Value::List(vec![Value::Keyword(Symbol::new("object")), Value::T].into());
+    assert_eq!(
+        json::stringify(&object, Keys::Keyword, false),
+        Err(String::from("Every key of a JSON object needs a value"))
+    );
+
+    // empty arrays and objects stay on one line
+    let empty = json::parse("[{},[]]", Keys::Keyword).unwrap();
+    assert_eq!(
+        json::stringify(&empty, Keys::Keyword, true),
+        Ok(String::from("[\n  {},\n  []\n]"))
+    );
+}
+
+#[test]
+fn round_trips() {
+    for (text, keys) in [
+        ("{}", Keys::Keyword),
+        ("[]", Keys::Keyword),
+        ("[[],{},[{}]]", Keys::Keyword),
+        (r#"{"a":false,"b":null,"c":true}"#, Keys::Keyword),
+        (r#"[false,null,"false","{}"]"#, Keys::Keyword),
+        (r#"{"a":1,"b":{}}"#, Keys::String),
+        (r#"{"false":[]}"#, Keys::String),
+        // arrays that look like property lists stay arrays
+        ("[false,1]", Keys::Keyword),
+        ("[{},1]", Keys::Keyword),
+        (r#"["x",1]"#, Keys::String),
+        (r#"[":object",1]"#, Keys::Keyword),
+        (r#"{"object":{"object":[]}}"#, Keys::Keyword),
+    ] {
+        let value = json::parse(text, keys).unwrap();
+        assert_eq!(
+            json::stringify(&value, keys, false),
+            Ok(String::from(text)),
+            "{}",
+            value_to_string(&value)
+        );
+    }
+}
+
+#[test]
+fn builtins() {
+    let mut interpreter = Interpreter::new();
+    interpreter.env().borrow_mut().set(
+        Symbol::new("text"),
+        Value::String(r#"{"name": "x", "tags": ["a", "b"]}"#.into()),
+    );
+
+    let eval = |interpreter: &mut Interpreter, code: &str| {
+        interpreter
+            .eval_str(code)
+            .map(|value| value_to_string(&value))
+            .map_err(|e| e.to_string())
+    };
+    assert_eq!(
+        eval(&mut interpreter, "(json-parse text)"),
+        Ok(String::from("(:object :name \"x\" :tags (\"a\" \"b\"))"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(cadr (json-parse text :keys :string))"),
+        Ok(String::from("\"name\""))
+    );
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            "(json-stringify (list :object :ok t :items (list 1 'two :three)))"
+        ),
+        Ok(String::from(
+            r#""{\"ok\":true,\"items\":[1,\"two\",\"three\"]}""#
+        ))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(json-stringify (list 1 2) :pretty t)"),
+        Ok(String::from("\"[\\n  1,\\n  2\\n]\""))
+    );
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            r#"(json-stringify (json-parse "{\"a\": false, \"b\": {}}"))"#
+        ),
+        Ok(String::from(r#""{\"a\":false,\"b\":{}}""#))
+    );
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            r#"(json-stringify (json-parse "{\"a\": 1}" :keys :string) :keys :string)"#
+        ),
+        Ok(String::from(r#""{\"a\":1}""#))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(json-parse text :keys :symbol)"),
+        Err(String::from(
+            "Option :keys of \"json-parse\" must be :keyword or :string"
+        ))
+    );
+}
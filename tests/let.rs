@@ -0,0 +1,102 @@
+mod common;
+use common::{check, check_err};
+
+#[test]
+fn let_evaluates_in_order() {
+    // each value defines x in the outer environment, so the last one defined wins
+    check("(let ((a (def x 1)) (b (def x 2)) (c (def x 3))) x)", "3");
+    check("(let ((a 1) (b 2)) `(,a ,b))", "(1 2)");
+    check("(let () 1)", "1");
+    check("(let ((a 1)))", "nil");
+}
+
+#[test]
+fn let_values_see_the_outer_environment() {
+    check("(let ((a 10)) (let ((a 1) (b a)) b))", "10");
+    check("(let ((a 10)) (let ((a 1)) a))", "1");
+    // the binding doesn't leak out
+    check("(let ((a 10)) (let ((a 1)) a) a)", "10");
+}
+
+#[test]
+fn let_star_is_sequential() {
+    check(
+        "(let* ((a 1) (b (+ a 1)) (c (* b 10))) `(,a ,b ,c))",
+        "(1 2 20)",
+    );
+    check("(let* ((a 1) (a (+ a 1))) a)", "2");
+    check("(let* ((a (def x 1)) (b (def x 2))) x)", "2");
+    check_err("(let* ((a b) (b 1)) a)", "Unbound symbol: b");
+}
+
+#[test]
+fn letrec_is_recursive() {
+    check(
+        "(letrec ((fact (lambda (n) (if (= n 0) 1 (* n (fact (- n 1))))))) (fact 5))",
+        "120",
+    );
+    check(
+        "(letrec ((even? (lambda (n) (if (= n 0) t (odd? (- n 1)))))
+                  (odd? (lambda (n) (if (= n 0) nil (even? (- n 1))))))
+           `(,(even? 10) ,(odd? 7) ,(even? 3)))",
+        "(t t nil)",
+    );
+    // the names are bound (to nil) before any value is evaluated
+    check("(letrec ((a b) (b 1)) a)", "nil");
+}
+
+#[test]
+fn labels_are_mutually_recursive() {
+    check(
+        "(labels ((even? (n) (if (= n 0) t (odd? (- n 1))))
+                  (odd? (n) (if (= n 0) nil (even? (- n 1)))))
+           `(,(even? 10) ,(odd? 7)))",
+        "(t t)",
+    );
+    check(
+        "(labels ((sum (n) (if (= n 0) 0 (+ n (sum (- n 1)))))) (sum 4))",
+        "10",
+    );
+    check("(labels () 1)", "1");
+}
+
+#[test]
+fn local_functions_dont_leak() {
+    check_err(
+        "(let () (labels ((helper (x) x)) (helper 1)) (helper 1))",
+        "Unbound symbol: helper",
+    );
+    check_err(
+        "(let () (letrec ((other (lambda (x) x))) (other 1)) (other 1))",
+        "Unbound symbol: other",
+    );
+}
+
+#[test]
+fn malformed_bindings() {
+    check_err("(let)", "\"let\" requires a list of bindings");
+    check_err(
+        "(let* 1 2)",
+        "First parameter to \"let*\" must be a list of bindings",
+    );
+    check_err(
+        "(letrec ((a)) a)",
+        "Bindings need to be of the form (name value)",
+    );
+    check_err(
+        "(let ((1 2)) 1)",
+        "The first parameter in each binding must be a symbol",
+    );
+    check_err(
+        "(labels)",
+        "\"labels\" requires a list of function definitions",
+    );
+    check_err(
+        "(labels ((f)) 1)",
+        "Function definitions need to be of the form (name (params) body)",
+    );
+    check_err(
+        "(labels ((1 () 1)) 1)",
+        "Function names in \"labels\" must be symbols",
+    );
+}
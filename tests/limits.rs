@@ -0,0 +1,197 @@
+use euphie::{interpreter::*, parse::Value};
+use std::{
+    sync::atomic::Ordering,
+    thread,
+    time::{Duration, Instant},
+};
+
+fn limited(limits: Limits) -> Interpreter {
+    Interpreter::with_limits(limits)
+}
+
+fn nested(depth: usize) -> String {
+    format!("{}0{}", "(+ 1 ".repeat(depth), ")".repeat(depth))
+}
+
+#[test]
+fn steps() {
+    let mut interpreter = limited(Limits {
+        max_steps: Some(100),
+        ..Limits::default()
+    });
+    interpreter
+        .eval_str("(def loop (lambda (n) (loop (+ n 1))))")
+        .unwrap();
+
+    assert_eq!(
+        interpreter.eval_str("(loop 0)"),
+        Err(EvalError::LimitExceeded(Limit::Steps))
+    );
+    // every evaluation gets the whole budget again
+    assert!(interpreter.eval_str("(+ 1 2)").is_ok());
+}
+
+#[test]
+fn depth() {
+    let mut interpreter = limited(Limits {
+        max_depth: Some(50),
+        ..Limits::default()
+    });
+    interpreter
+        .eval_str("(def count (lambda (n) (if (= n 0) 0 (+ 1 (count (- n 1))))))")
+        .unwrap();
+
+    assert!(interpreter.eval_str("(count 5)").is_ok());
+    assert_eq!(
+        interpreter.eval_str("(count 50)"),
+        Err(EvalError::LimitExceeded(Limit::Depth))
+    );
+    // forms count too, not just calls, and so does reading and expanding them
+    assert!(interpreter.eval_str(&nested(20)).is_ok());
+    assert_eq!(
+        interpreter.eval_str(&nested(60)),
+        Err(EvalError::LimitExceeded(Limit::Depth))
+    );
+    assert_eq!(
+        interpreter.parse_all(&nested(60)),
+        Err(EvalError::LimitExceeded(Limit::Depth))
+    );
+    let form = interpreter.parse_all(&nested(40)).unwrap().remove(0);
+    assert_eq!(interpreter.expand(&form), Ok(form));
+    interpreter
+        .eval_str("(def deep (macro (n) (if (= n 0) 0 `(+ 1 (deep ,(- n 1))))))")
+        .unwrap();
+    let form = interpreter.parse_all("(deep 60)").unwrap().remove(0);
+    assert_eq!(
+        interpreter.expand(&form),
+        Err(EvalError::LimitExceeded(Limit::Depth))
+    );
+}
+
+#[test]
+fn stack() {
+    // with the default limits, deeply nested code runs out of stack it's allowed to use long before it runs out
+    // of the stack the test's thread has
+    let mut interpreter = Interpreter::new();
+    for depth in [2_000, 20_000, 200_000] {
+        assert_eq!(
+            interpreter.eval_str(&nested(depth)),
+            Err(EvalError::LimitExceeded(Limit::Stack))
+        );
+    }
+
+    let items = "1 ".repeat(990);
+    assert_eq!(
+        interpreter.eval_str(&format!("(map (lambda (x) x) '({}))", items)),
+        Err(EvalError::LimitExceeded(Limit::Stack))
+    );
+    assert_eq!(
+        interpreter.eval_str(&format!("(read-from-string \"{}\")", "(".repeat(100_000))),
+        Err(EvalError::LimitExceeded(Limit::Stack))
+    );
+
+    // it's an error like any other, so the interpreter can still be used
+    assert!(interpreter
+        .eval_str("(map (lambda (x) x) '(1 2 3))")
+        .is_ok());
+    assert!(interpreter.eval_str(&nested(50)).is_ok());
+}
+
+#[test]
+fn cells() {
+    let mut interpreter = limited(Limits {
+        max_cells: Some(10),
+        ..Limits::default()
+    });
+
+    assert!(interpreter.eval_str("(list 1 2 3)").is_ok());
+    assert_eq!(
+        interpreter.eval_str("(list 1 2 3 4 5 6 7 8 9 10 11)"),
+        Err(EvalError::LimitExceeded(Limit::Cells))
+    );
+    assert_eq!(
+        interpreter.eval_str("(let ((a (list 1 2 3 4 5 6))) `(,@a ,@a))"),
+        Err(EvalError::LimitExceeded(Limit::Cells))
+    );
+}
+
+#[test]
+fn string_length() {
+    let mut interpreter = limited(Limits {
+        max_string_length: Some(5),
+        ..Limits::default()
+    });
+
+    assert!(interpreter
+        .eval_str("(string-append \"ab\" \"cd\")")
+        .is_ok());
+    assert_eq!(
+        interpreter.eval_str("(string-append \"abc\" \"def\")"),
+        Err(EvalError::LimitExceeded(Limit::StringLength))
+    );
+    assert_eq!(
+        interpreter.eval_str("(number->string 1234567)"),
+        Err(EvalError::LimitExceeded(Limit::StringLength))
+    );
+
+    // strings that are read count too, wherever they are in the form
+    for code in [
+        "(read-from-string \"\\\"abcdefghij\\\"\")",
+        "(read-from-string \"(a (b \\\"abcdefghij\\\"))\")",
+        "(read-all \"1 \\\"abcdefghij\\\"\")",
+        "(call-with-input-string \"\\\"abcdefghij\\\"\" (lambda (p) (read p)))",
+    ] {
+        assert_eq!(
+            interpreter.eval_str(code),
+            Err(EvalError::LimitExceeded(Limit::StringLength)),
+            "{}",
+            code
+        );
+    }
+    assert_eq!(
+        interpreter.eval_str("(read-from-string \"\\\"abc\\\"\")"),
+        Ok(Value::String("abc".into()))
+    );
+}
+
+#[test]
+fn errors_cant_be_ignored_once_stopped() {
+    let mut interpreter = limited(Limits {
+        max_steps: Some(20),
+        ..Limits::default()
+    });
+
+    // the limit error goes up through everything, even though or only looks at the values
+    assert_eq!(
+        interpreter.eval_str("(or (+ 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20) 1)"),
+        Err(EvalError::LimitExceeded(Limit::Steps))
+    );
+}
+
+#[test]
+fn interrupting() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval_str("(def spin (lambda (n) (if (= n 0) 0 (spin (- n 1)))))")
+        .unwrap();
+
+    // a flag that's set before evaluating stops the next evaluation right away, and is cleared afterwards
+    let interrupt = interpreter.interrupt_handle();
+    interrupt.store(true, Ordering::Relaxed);
+    assert_eq!(interpreter.eval_str("(+ 1 2)"), Err(EvalError::Interrupted));
+    assert!(!interrupt.load(Ordering::Relaxed));
+    assert_eq!(interpreter.eval_str("(+ 1 2)"), Ok(Value::Number(3.0)));
+
+    // and one set from another thread stops the evaluation that's running
+    let setter = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        interrupt.store(true, Ordering::Relaxed);
+    });
+    let started = Instant::now();
+    let mut result = Ok(Value::Nil);
+    while result.is_ok() && started.elapsed() < Duration::from_secs(10) {
+        result = interpreter.eval_str("(spin 100)");
+    }
+    setter.join().unwrap();
+    assert_eq!(result, Err(EvalError::Interrupted));
+}
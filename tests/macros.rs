@@ -0,0 +1,121 @@
+use euphie::{
+    env::Env, eval::expand_value, parse::parse, tokenize::tokenize, util::value_to_string,
+};
+
+mod common;
+use common::eval_all;
+
+fn expand(forms: &[&str]) -> Result<String, String> {
+    let mut env = Env::new();
+    let mut result = String::new();
+    for code in forms {
+        let mut tokens = tokenize(String::from(*code))?;
+        tokens.reverse();
+        let form = expand_value(&parse(&mut tokens)?, &mut env)?;
+        result = value_to_string(&form);
+    }
+    Ok(result)
+}
+
+#[test]
+fn macros_are_expanded_before_evaluation() {
+    assert_eq!(
+        eval_all(&[
+            "(def twice (macro (x) `(+ ,x ,x)))",
+            "(def f (lambda (n) (twice n)))",
+            "(f 4)",
+        ]),
+        Ok(String::from("8"))
+    );
+
+    // the lambda's body has no macro calls left in it
+    assert_eq!(
+        expand(&[
+            "(def twice (macro (x) `(+ ,x ,x)))",
+            "(def f (lambda (n) (twice (twice n))))",
+        ]),
+        Ok(String::from("(def f (lambda (n) (+ (+ n n) (+ n n))))"))
+    );
+}
+
+#[test]
+fn only_code_is_expanded() {
+    let twice = "(def twice (macro (x) `(+ ,x ,x)))";
+    // not quoted data, parameter lists or binding names
+    assert_eq!(
+        expand(&[twice, "'(twice 1)"]),
+        Ok(String::from("(quote (twice 1))"))
+    );
+    assert_eq!(
+        expand(&[twice, "(let ((twice 1)) twice)"]),
+        Ok(String::from("(let ((twice 1)) twice)"))
+    );
+    // and only the unquoted parts of quasiquotes
+    assert_eq!(
+        expand(&[twice, "`((twice 1) ,(twice 2))"]),
+        Ok(String::from("(quasiquote ((twice 1) (unquote (+ 2 2))))"))
+    );
+}
+
+#[test]
+fn only_top_level_macros_are_defined_while_expanding() {
+    assert_eq!(
+        eval_all(&[
+            "(def foo (lambda (x) (* x 100)))",
+            "(if nil (def foo (macro (x) 1)) nil)",
+            "(foo 5)",
+        ]),
+        Ok(String::from("500"))
+    );
+    assert_eq!(
+        eval_all(&[
+            "(def foo (lambda (x) (* x 100)))",
+            "(def g (lambda () (define-syntax foo (syntax-rules () ((_ x) 1)))))",
+            "(foo 5)",
+        ]),
+        Ok(String::from("500"))
+    );
+
+    // a macro defined inside of another form still works once that form runs
+    assert_eq!(
+        eval_all(&["(let ((x 1)) (def foo (macro (y) x)) (foo 2))"]),
+        Ok(String::from("1"))
+    );
+    assert_eq!(
+        eval_all(&[
+            "(if t (define-syntax one (syntax-rules () ((_) 1))) nil)",
+            "(one)",
+        ]),
+        Ok(String::from("1"))
+    );
+}
+
+#[test]
+fn locals_shadow_macros() {
+    assert_eq!(
+        eval_all(&["(let ((when (lambda (a b) 42))) (when nil 1))"]),
+        Ok(String::from("42"))
+    );
+    assert_eq!(
+        eval_all(&["((lambda (unless) (unless t 1)) (lambda (a b) 2))"]),
+        Ok(String::from("2"))
+    );
+    assert_eq!(
+        eval_all(&["(labels ((when (a b) b)) (when nil 3))"]),
+        Ok(String::from("3"))
+    );
+    assert_eq!(
+        eval_all(&["(match (list (lambda (a b) 4)) ((when) (when nil 1)))"]),
+        Ok(String::from("4"))
+    );
+    assert_eq!(
+        eval_all(&["(let* ((a 1) (when (lambda (x y) a))) (when nil 5))"]),
+        Ok(String::from("1"))
+    );
+
+    // only inside of the form that binds them
+    assert_eq!(
+        eval_all(&["(list (let ((when 1)) when) (when t 2))"]),
+        Ok(String::from("(1 2)"))
+    );
+}
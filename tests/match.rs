@@ -0,0 +1,133 @@
+use euphie::{interpreter::Interpreter, util::value_to_string};
+
+mod common;
+use common::{check, check_err, eval_all};
+
+#[test]
+fn literals() {
+    check("(match 1 (1 \"one\") (2 \"two\"))", "\"one\"");
+    check("(match 2 (1 \"one\") (2 \"two\"))", "\"two\"");
+    check("(match \"a\" (\"a\" 1) (_ 2))", "1");
+    check("(match :k (:j 1) (:k 2))", "2");
+    check("(match 'x ('y 1) ('x 2))", "2");
+    check("(match t (nil 1) (t 2))", "2");
+    check("(match nil (nil 1) (_ 2))", "1");
+    check("(match '() (nil 1) (_ 2))", "1");
+}
+
+#[test]
+fn bindings() {
+    check("(match 5 (x (+ x 1)))", "6");
+    check("(match 5 (_ 1))", "1");
+    check("(match '(1 2) ((a b) (+ a b)))", "3");
+    check("(match '(1 (2 3)) ((a (b c)) `(,c ,b ,a)))", "(3 2 1)");
+    check("(match '(1 2 3) ((a b) 1) ((a b c) 2))", "2");
+    check("(match '(1 x) ((1 'x) 1) (_ 2))", "1");
+}
+
+#[test]
+fn rest() {
+    check("(match '(1 2 3) ((a . r) r))", "(2 3)");
+    check("(match '(1) ((a . r) r))", "()");
+    check("(match '(1 2 3) ((a b . _) b))", "2");
+    check("(match nil ((a . r) 1) (_ 2))", "2");
+}
+
+#[test]
+fn guards() {
+    check(
+        "(match 5 (x when (> x 3) \"big\") (x \"small\"))",
+        "\"big\"",
+    );
+    check(
+        "(match 1 (x when (> x 3) \"big\") (x \"small\"))",
+        "\"small\"",
+    );
+    check("(match '(1 2) ((a b) when (= a b) 1) ((a b) 2))", "2");
+}
+
+#[test]
+fn maps() {
+    check(
+        "(match '(:a 1 :b 2) ((&key :b y :a x) (list x y)))",
+        "(1 2)",
+    );
+    check("(match '(:a 1 :b 2 :c 3) ((&key :c z) z))", "3");
+    check("(match '(:a 1) ((&key :b y) 1) (_ 2))", "2");
+    check("(match '(:a (1 2)) ((&key :a (x y)) y))", "2");
+    check("(match '(\"a\" 1) ((&key \"a\" x) x))", "1");
+    check("(match '(:a 1 :b) ((&key :a x) x) (_ 2))", "2");
+    check("(match 5 ((&key :a x) x) (_ 2))", "2");
+}
+
+#[test]
+fn no_match() {
+    check_err("(match 3 (1 1) (2 2))", "No matching clause for value: 3");
+    check_err(
+        "(match 5 (x when (< x 3) 1))",
+        "No matching clause for value: 5",
+    );
+    check_err(
+        "(match '(1 2) ((a) 1))",
+        "No matching clause for value: (1 2)",
+    );
+}
+
+#[test]
+fn malformed() {
+    // even when an earlier clause matches
+    check_err(
+        "(match 1 (1 1) ((a .) 2))",
+        "\".\" in a pattern must be followed by exactly one pattern",
+    );
+    check_err(
+        "(match 1 (x when))",
+        "\"when\" in a match clause requires a guard",
+    );
+    check_err(
+        "(match 1 5)",
+        "Match clauses need to be of the form (pattern body...)",
+    );
+    check_err("(match)", "\"match\" requires a value to match on");
+    check_err(
+        "(match 1 ((&key :a) 1))",
+        "\"&key\" in a pattern must be followed by keys and patterns",
+    );
+    check_err(
+        "(match 1 ((&key a x) 1))",
+        "Keys in a \"&key\" pattern must be keywords or strings",
+    );
+}
+
+#[test]
+fn repeated_evaluation() {
+    // the patterns are compiled the first time the match runs, and reused by every call after that
+    assert_eq!(
+        eval_all(&[
+            "(def f (lambda (l) (match l ((a . r) (+ a (f r))) (nil 0))))",
+            "(list (f '(1 2 3 4)) (f '(5 6)))",
+        ]),
+        Ok(String::from("(10 11)"))
+    );
+}
+
+#[test]
+fn expanded_code() {
+    // the compiled patterns aren't part of the code, so it can still be printed and read back
+    let mut interpreter = Interpreter::new();
+    let form = interpreter
+        .parse_all("(match (list 1 2) ((a . r) (list a r)) ((&key :a x) x))")
+        .unwrap();
+    let expanded = interpreter.expand(&form[0]).unwrap();
+    assert_eq!(
+        value_to_string(&expanded),
+        "(match (list 1 2) ((a . r) (list a r)) ((&key :a x) x))"
+    );
+    assert_eq!(
+        interpreter
+            .eval(&expanded)
+            .map(|value| value_to_string(&value))
+            .map_err(|e| e.to_string()),
+        Ok(String::from("(1 (2))"))
+    );
+}
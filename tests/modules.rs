@@ -0,0 +1,179 @@
+use euphie::{interpreter::*, sandbox::*};
+use std::{fs, path::Path};
+
+mod common;
+use common::{eval, temp_dir};
+
+fn write(path: &Path, code: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, code).unwrap();
+}
+
+fn with_search_path(dir: &Path) -> Interpreter {
+    Interpreter::with_env(EnvBuilder::new().all_modules().search_path(dir).build())
+}
+
+#[test]
+fn loading() {
+    let dir = temp_dir("modules-load");
+    write(&dir.join("code.lisp"), "(def a 1) (def b (+ a 1)) b");
+    let mut interpreter = with_search_path(&dir);
+    let path = dir.join("code.lisp");
+
+    // the value of the last form
+    assert_eq!(
+        eval(&mut interpreter, &format!("(load \"{}\")", path.display())),
+        Ok(String::from("2"))
+    );
+    assert_eq!(eval(&mut interpreter, "a"), Ok(String::from("1")));
+    assert!(eval(&mut interpreter, "(load \"missing.lisp\")")
+        .unwrap_err()
+        .starts_with("Could not read missing.lisp"));
+    assert_eq!(
+        eval(&mut interpreter, "(load 'code)"),
+        Err(String::from("Argument to \"load\" must be a string"))
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn importing() {
+    let dir = temp_dir("modules-import");
+    write(
+        &dir.join("util/math.lisp"),
+        "(module util.math (export double triple) (def double (lambda (x) (* x 2))) (def triple (lambda (x) (* x 3))))",
+    );
+    let mut interpreter = with_search_path(&dir);
+
+    eval(&mut interpreter, "(import util.math)").unwrap();
+    assert_eq!(eval(&mut interpreter, "(double 2)"), Ok(String::from("4")));
+    eval(
+        &mut interpreter,
+        "(import (prefix (only util.math triple) m:))",
+    )
+    .unwrap();
+    assert_eq!(
+        eval(&mut interpreter, "(m:triple 2)"),
+        Ok(String::from("6"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(import (only util.math square))"),
+        Err(String::from("square isn't exported by the module"))
+    );
+
+    // modules are only loaded once
+    fs::remove_file(dir.join("util/math.lisp")).unwrap();
+    assert_eq!(
+        eval(&mut interpreter, "(import util.math)"),
+        Ok(String::from("nil"))
+    );
+    assert!(eval(&mut interpreter, "(import missing)")
+        .unwrap_err()
+        .starts_with("Module missing not found"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn import_cycles() {
+    let dir = temp_dir("modules-cycles");
+    write(&dir.join("a.lisp"), "(module a (export) (import b))");
+    write(&dir.join("b.lisp"), "(module b (export) (import a))");
+    let mut interpreter = with_search_path(&dir);
+
+    assert!(eval(&mut interpreter, "(import a)")
+        .unwrap_err()
+        .ends_with("Import cycle: a -> b -> a"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+// an interpreter that can import modules, but has nothing else that touches the outside world
+fn restricted(builder: EnvBuilder) -> Interpreter {
+    Interpreter::with_env(
+        builder
+            .modules(&[Module::Core, Module::Math, Module::IoRead])
+            .build(),
+    )
+}
+
+#[test]
+fn module_names_stay_in_the_search_path() {
+    let dir = temp_dir("modules-names");
+    write(
+        &dir.join("evil.lisp"),
+        "(module evil (export secret) (def secret 42))",
+    );
+    let evil = dir.join("evil");
+    let absolute = evil.to_string_lossy().replace('/', ".");
+    let mut interpreter = restricted(
+        EnvBuilder::new()
+            .fs_root(dir.join("root"))
+            .search_path(dir.join("root/lib")),
+    );
+
+    // a leading dot would make an absolute path, which would replace the search path directory
+    for name in [
+        absolute.as_str(),
+        "..evil",
+        "a..evil",
+        "|../evil|",
+        "|lib/../../evil|",
+        "evil.",
+    ] {
+        assert_eq!(
+            eval(&mut interpreter, &format!("(import {})", name)),
+            Err(format!("Invalid module name: {}", name.trim_matches('|')))
+        );
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn imports_are_sandboxed() {
+    let dir = temp_dir("modules-sandbox");
+    write(&dir.join("lib/m.lisp"), "(module m (export x) (def x 1))");
+
+    // the search path has to be inside the filesystem root
+    let mut interpreter = restricted(
+        EnvBuilder::new()
+            .fs_root(dir.join("root"))
+            .search_path(dir.join("lib")),
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(import m)"),
+        Err(format!(
+            "Path \"{}\" is outside of the filesystem root",
+            dir.join("lib/m.lisp").display()
+        ))
+    );
+
+    // and relative search paths are relative to the root
+    write(
+        &dir.join("root/lib/m.lisp"),
+        "(module m (export x) (def x 2))",
+    );
+    let mut interpreter = restricted(
+        EnvBuilder::new()
+            .fs_root(dir.join("root"))
+            .search_path("lib"),
+    );
+    eval(&mut interpreter, "(import m)").unwrap();
+    assert_eq!(eval(&mut interpreter, "x"), Ok(String::from("2")));
+
+    // importing reads files, so it needs io.read
+    let mut interpreter = Interpreter::with_env(
+        EnvBuilder::new()
+            .module(Module::Math)
+            .search_path(dir.join("lib"))
+            .build(),
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(import m)"),
+        Err(String::from("Unbound symbol: import"))
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,117 @@
+use euphie::{interpreter::*, native::*, parse::Value, util::value_to_string};
+use std::collections::HashMap;
+
+mod common;
+use common::eval;
+
+#[test]
+fn typed_functions() {
+    let mut interpreter = Interpreter::new();
+    {
+        let mut env = interpreter.env().borrow_mut();
+        env.register_typed_fn("add", |a: f64, b: f64| a + b);
+        env.register_typed_fn("shout", |s: String| s.to_uppercase());
+        env.register_typed_fn("sum", |l: Vec<i64>| l.iter().sum::<i64>());
+        env.register_typed_fn("first-or", |l: Vec<Value>, default: Value| {
+            l.first().cloned().unwrap_or(default)
+        });
+        env.register_typed_fn("swap", |pair: (String, f64)| (pair.1, pair.0));
+        env.register_typed_fn("checked-div", |a: f64, b: f64| {
+            if b == 0.0 {
+                Err("Division by zero")
+            } else {
+                Ok(a / b)
+            }
+        });
+        env.register_typed_fn("lookup", |m: HashMap<String, f64>, key: String| {
+            m.get(&key).copied()
+        });
+        env.register_typed_fn("answer", || 42u8);
+    }
+
+    assert_eq!(eval(&mut interpreter, "(add 1 2)"), Ok(String::from("3")));
+    assert_eq!(
+        eval(&mut interpreter, "(shout \"hi\")"),
+        Ok(String::from("\"HI\""))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(sum (list 1 2 3))"),
+        Ok(String::from("6"))
+    );
+    assert_eq!(eval(&mut interpreter, "(sum nil)"), Ok(String::from("0")));
+    assert_eq!(
+        eval(&mut interpreter, "(first-or nil :none)"),
+        Ok(String::from(":none"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(swap (list \"a\" 1))"),
+        Ok(String::from("(1 \"a\")"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(lookup (list :a 1 :b 2) \"b\")"),
+        Ok(String::from("2"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(lookup (list :a 1) \"c\")"),
+        Ok(String::from("nil"))
+    );
+    assert_eq!(eval(&mut interpreter, "(answer)"), Ok(String::from("42")));
+    assert_eq!(
+        eval(&mut interpreter, "(map (lambda (x) (add x 1)) (list 1 2))"),
+        Ok(String::from("(2 3)"))
+    );
+
+    assert_eq!(
+        eval(&mut interpreter, "(add 1)"),
+        Err(String::from("\"add\" requires 2 arguments"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(add 1 \"2\")"),
+        Err(String::from(
+            "Argument 2 to \"add\": expected a number, got \"2\""
+        ))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(sum (list 1 1.5))"),
+        Err(String::from(
+            "Argument 1 to \"sum\": expected an integer that fits in i64, got 1.5"
+        ))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(checked-div 1 0)"),
+        Err(String::from("Division by zero"))
+    );
+}
+
+#[test]
+fn untyped_functions() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .env()
+        .borrow_mut()
+        .register_fn("count-args", |args| Ok(Value::Number(args.len() as f64)));
+
+    assert_eq!(
+        eval(&mut interpreter, "(count-args 1 2 3)"),
+        Ok(String::from("3"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "count-args"),
+        Ok(String::from("#<native count-args>"))
+    );
+}
+
+#[test]
+fn conversions() {
+    assert!(u8::from_value(&Value::Number(300.0)).is_err());
+    assert_eq!(Option::<f64>::from_value(&Value::Nil), Ok(None));
+    assert_eq!(bool::from_value(&Value::T), Ok(true));
+    assert_eq!(
+        value_to_string(&vec![(1.0, true), (2.0, false)].into_value()),
+        "((1 t) (2 nil))"
+    );
+    assert_eq!(
+        value_to_string(&HashMap::from([(String::from("a"), 1.0)]).into_value()),
+        "(:a 1)"
+    );
+}
@@ -0,0 +1,156 @@
+use euphie::{
+    interpreter::*,
+    parse::Value,
+    port::{self, Current, Port},
+    sandbox::*,
+};
+
+mod common;
+use common::{eval, sandboxed, sandboxed_in, temp_dir};
+
+#[test]
+fn capturing_output() {
+    let mut interpreter = Interpreter::new();
+    let (result, output) = port::capture_output(|| {
+        eval(&mut interpreter, "(println \"a\" 'b \"c\\nd\")").unwrap();
+        eval(&mut interpreter, "(write \"a\\\"b\")").unwrap();
+        eval(&mut interpreter, "(newline)").unwrap();
+        eval(&mut interpreter, "(pprint '(1 2))")
+    });
+    assert_eq!(result, Ok(String::from("nil")));
+    assert_eq!(output, "a b c\nd\n\"a\\\"b\"\n(1 2)\n");
+
+    // the output goes back to stdout afterwards, even if the code failed
+    let (result, _) = port::capture_output(|| eval(&mut interpreter, "(write)"));
+    assert!(result.is_err());
+    assert_eq!(
+        eval(&mut interpreter, "(current-output-port)"),
+        Ok(String::from("#<port stdout>"))
+    );
+}
+
+#[test]
+fn string_ports() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            "(with-output-to-string (lambda () (display '(\"a\" b))))"
+        ),
+        Ok(String::from("\"(a b)\""))
+    );
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            "(call-with-input-string \"(a\n b) \\\"c\nd\\\" e\" (lambda (p) (list (read p) (read p) (read-char p) (read-line p) (read p))))"
+        ),
+        Ok(String::from("((a b) \"c\\nd\" \" \" \"e\" #<eof>)"))
+    );
+
+    // a port made by the host can be made the current input port
+    let input = Port::input_string("first line\nsecond");
+    let result = port::with_current(Current::Input, input, || {
+        eval(
+            &mut interpreter,
+            "(list (read-line) (read-char) (read-line) (read-line))",
+        )
+    });
+    assert_eq!(
+        result,
+        Ok(String::from("(\"first line\" \"s\" \"econd\" #<eof>)"))
+    );
+
+    // the end of file object can't be mistaken for anything that was read
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            "(call-with-input-string \":eof nil\" (lambda (p) (list (eof-object? (read p)) (eof-object? (read p)) (eof-object? (read p)) (eof-object? (read-char p)) (eof-object? (read-line p)))))"
+        ),
+        Ok(String::from("(nil nil t t t)"))
+    );
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            "(call-with-input-string \"\" (lambda (p) (equal? (read p) (read-char p))))"
+        ),
+        Ok(String::from("t"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(eof-object? :eof)"),
+        Ok(String::from("nil"))
+    );
+
+    assert_eq!(
+        eval(&mut interpreter, "(read-line (current-output-port))"),
+        Err(String::from("Port stdout isn't an input port"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(display 1 2)"),
+        Err(String::from("Port argument to \"display\" must be a port"))
+    );
+}
+
+#[test]
+fn file_ports() {
+    let dir = temp_dir("ports");
+    let mut interpreter = sandboxed_in(&[Module::Core, Module::IoRead, Module::IoWrite], &dir);
+
+    eval(&mut interpreter, "(def out (open-output-file \"out.txt\"))").unwrap();
+    eval(
+        &mut interpreter,
+        "(with-output-to-port out (lambda () (println 1 \"two\")))",
+    )
+    .unwrap();
+    eval(&mut interpreter, "(close-port out)").unwrap();
+    assert_eq!(
+        eval(&mut interpreter, "(write 3 out)"),
+        Err(format!("Port {} is closed", dir.join("out.txt").display()))
+    );
+
+    eval(
+        &mut interpreter,
+        "(def out (open-output-file \"out.txt\" :append t))",
+    )
+    .unwrap();
+    eval(&mut interpreter, "(write '(three) out)").unwrap();
+    assert_eq!(
+        std::fs::read_to_string(dir.join("out.txt")).unwrap(),
+        "1 two\n(three)"
+    );
+
+    eval(&mut interpreter, "(def in (open-input-file \"out.txt\"))").unwrap();
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            "(list (read in) (read in) (read in) (read in))"
+        ),
+        Ok(String::from("(1 two (three) #<eof>)"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(open-input-file \"../out.txt\")"),
+        Err(String::from(
+            "Path \"../out.txt\" is outside of the filesystem root"
+        ))
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn ports_need_io_modules() {
+    let mut interpreter = sandboxed(&[Module::Core]);
+    assert_eq!(
+        eval(&mut interpreter, "(with-output-to-string (lambda () 1))"),
+        Err(String::from("Unbound symbol: with-output-to-string"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(open-input-file \"a\")"),
+        Err(String::from("Unbound symbol: open-input-file"))
+    );
+    assert_eq!(
+        Interpreter::new()
+            .eval_str("(foreign-type (current-input-port))")
+            .ok(),
+        Some(Value::String("port".into()))
+    );
+}
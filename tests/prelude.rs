@@ -0,0 +1,46 @@
+use euphie::{interpreter::*, parse::Value, sandbox::*};
+
+mod common;
+use common::check;
+
+#[test]
+fn list_accessors() {
+    check("(list 1 2 3)", "(1 2 3)");
+    check("(list)", "()");
+    check("(cadr (list 1 2 3))", "2");
+    check("(cddr (list 1 2 3))", "(3)");
+    check("(caddr (list 1 2 3))", "3");
+}
+
+#[test]
+fn when_and_unless() {
+    check("(when (> 2 1) 1 2)", "2");
+    check("(when (< 2 1) 1 2)", "nil");
+    check("(unless (< 2 1) 1 2)", "2");
+    check("(unless (> 2 1) 1 2)", "nil");
+}
+
+#[test]
+fn map_and_filter() {
+    check("(map (lambda (x) (* x 2)) (list 1 2 3))", "(2 4 6)");
+    check("(map (lambda (x) x) nil)", "nil");
+    check("(filter (lambda (x) (> x 1)) (list 1 2 3))", "(2 3)");
+    check("(filter (lambda (x) (> x 5)) (list 1 2 3))", "nil");
+}
+
+#[test]
+fn redefining_doesnt_break_the_prelude() {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval_str("(def cddr (lambda (l) 0))").unwrap();
+    assert_eq!(
+        interpreter.eval_str("(caddr (list 1 2 3))"),
+        Ok(Value::Number(3.0))
+    );
+}
+
+#[test]
+fn opting_out() {
+    let mut interpreter =
+        Interpreter::with_env(EnvBuilder::new().all_modules().prelude(false).build());
+    assert!(interpreter.eval_str("(list 1 2)").is_err());
+}
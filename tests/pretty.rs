@@ -0,0 +1,96 @@
+use euphie::{interpreter::*, parse::*, pretty::*, tokenize::tokenize, util::value_to_string};
+
+fn read(code: &str) -> Value {
+    let mut tokens = tokenize(String::from(code)).unwrap();
+    tokens.reverse();
+    parse(&mut tokens).unwrap()
+}
+
+fn pprint(code: &str, width: usize) -> String {
+    let options = PrintOptions {
+        width,
+        ..PrintOptions::default()
+    };
+    pretty_print(&read(code), &options)
+}
+
+#[test]
+fn fits_on_one_line() {
+    let code = "(def f (lambda (x &rest ys) (if (= x 0) '(a \"b c\" :d) (f (- x 1)))))";
+    assert_eq!(pprint(code, 80), value_to_string(&read(code)));
+}
+
+#[test]
+fn special_forms() {
+    assert_eq!(
+        pprint(
+            "(def f (lambda (a b) (let ((sum (+ a b)) (product (* a b))) (if (> sum product) sum product))))",
+            41
+        ),
+        "\
+(def f
+  (lambda (a b)
+    (let ((sum (+ a b))
+          (product (* a b)))
+      (if (> sum product) sum product))))"
+    );
+    assert_eq!(
+        pprint(
+            "(match l ((x . rest) (first-thing x)) (_ (second-thing)))",
+            30
+        ),
+        "\
+(match l
+  ((x . rest) (first-thing x))
+  (_ (second-thing)))"
+    );
+}
+
+#[test]
+fn calls_and_data() {
+    assert_eq!(
+        pprint("(some-function argument-one argument-two)", 30),
+        "\
+(some-function argument-one
+               argument-two)"
+    );
+    assert_eq!(
+        pprint("((1 2 3) (4 5 6) (7 8 9))", 12),
+        "\
+((1 2 3)
+ (4 5 6)
+ (7 8 9))"
+    );
+}
+
+#[test]
+fn limits() {
+    let options = PrintOptions {
+        length: Some(2),
+        depth: Some(2),
+        ..PrintOptions::default()
+    };
+    assert_eq!(
+        pretty_print(&read("(1 (2 (3 4)) 5 6)"), &options),
+        "(1 (2 #) ...)"
+    );
+}
+
+#[test]
+fn printer_variables() {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval_str("(def *print-width* 40)").unwrap();
+    interpreter.eval_str("(def *print-length* 3)").unwrap();
+    assert_eq!(
+        PrintOptions::from_env(&interpreter.env().borrow()),
+        PrintOptions {
+            width: 40,
+            length: Some(3),
+            depth: None
+        }
+    );
+    assert_eq!(
+        PrintOptions::from_env(&Interpreter::new().env().borrow()),
+        PrintOptions::default()
+    );
+}
@@ -0,0 +1,108 @@
+use euphie::{
+    interpreter::*,
+    parse::*,
+    symbol::Symbol,
+    tokenize::tokenize,
+    util::{display_to_string, value_to_string},
+};
+
+fn read(code: &str) -> Result<Value, String> {
+    let mut tokens = tokenize(String::from(code))?;
+    tokens.reverse();
+    parse(&mut tokens)
+}
+
+// a xorshift generator, so the values are random but the same every time
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+
+    fn name(&mut self) -> String {
+        const CHARS: &[char] = &[
+            'a', 'b', 'z', '0', '7', '-', '+', '.', ':', '\'', '`', ',', '@', '#', '<', '|', '\\',
+            '"', '(', ')', ';', ' ', '\n', '\t', 'é', '😀', '\u{a0}',
+        ];
+        let length = 1 + self.next(6);
+        (0..length).map(|_| CHARS[self.next(CHARS.len())]).collect()
+    }
+
+    fn value(&mut self, depth: usize) -> Value {
+        match self.next(if depth < 3 { 9 } else { 7 }) {
+            0 => Value::Nil,
+            1 => Value::T,
+            2 => Value::Number(self.next(2001) as f64 - 1000.0),
+            3 => Value::Number(
+                (self.next(1 << 20) as f64 - 1e5) / 7.0 * 10f64.powi(self.next(40) as i32 - 20),
+            ),
+            4 => Value::String(self.name().into()),
+            5 => Value::Symbol(Symbol::new(&self.name())),
+            6 => Value::Keyword(Symbol::new(&self.name())),
+            _ => {
+                let length = self.next(5);
+                Value::List((0..length).map(|_| self.value(depth + 1)).collect())
+            }
+        }
+    }
+}
+
+#[test]
+fn write_round_trips() {
+    let mut random = Random(0x2545f4914f6cdd1d);
+    for _ in 0..5000 {
+        let value = random.value(0);
+        let written = value_to_string(&value);
+        assert_eq!(read(&written), Ok(value), "{}", written);
+    }
+}
+
+#[test]
+fn write_and_display() {
+    let value = read(r#"("a \"b\"\n" |c d| :|e f| |12| nil)"#).unwrap();
+    assert_eq!(
+        value_to_string(&value),
+        r#"("a \"b\"\n" |c d| :|e f| |12| nil)"#
+    );
+    assert_eq!(display_to_string(&value), "(a \"b\"\n c d :e f 12 nil)");
+}
+
+#[test]
+fn prefixes() {
+    assert_eq!(
+        read("''a").map(|value| value_to_string(&value)),
+        Ok(String::from("(quote (quote a))"))
+    );
+    assert_eq!(
+        read("`(a ,@b ',c)").map(|value| value_to_string(&value)),
+        Ok(String::from(
+            "(quasiquote (a (splice-unquote b) (quote (unquote c))))"
+        ))
+    );
+    assert_eq!(
+        read("@a"),
+        Err(String::from("@ can only come right after a comma"))
+    );
+}
+
+#[test]
+fn unreadable_objects() {
+    let mut interpreter = Interpreter::new();
+    let lambda = interpreter
+        .eval_str("(lambda (a &optional b &rest c) a)")
+        .unwrap();
+    assert_eq!(
+        value_to_string(&lambda),
+        "#<lambda (a &optional b &rest c)>"
+    );
+    assert_eq!(
+        read(&format!("(f {})", value_to_string(&lambda))),
+        Err(String::from(
+            "Objects printed as #<...> can't be read: #<lambda"
+        ))
+    );
+}
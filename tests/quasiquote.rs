@@ -0,0 +1,89 @@
+mod common;
+use common::{check, check_err};
+
+// evaluates the form with x bound to (2 3) and n to 5
+fn with_x(code: &str) -> String {
+    format!("(let ((x '(2 3)) (n 5)) {})", code)
+}
+
+#[test]
+fn unquoting() {
+    check(&with_x("`(1 ,n)"), "(1 5)");
+    check(&with_x("`(1 (2 ,n) ,(+ n 1))"), "(1 (2 5) 6)");
+    check(&with_x("`,n"), "5");
+    check(&with_x("`n"), "n");
+    check(&with_x("`()"), "()");
+    check(&with_x("`(1 'n ,'n)"), "(1 (quote n) n)");
+}
+
+#[test]
+fn splicing() {
+    // at the head, in the middle and at the tail
+    check(&with_x("`(,@x 4)"), "(2 3 4)");
+    check(&with_x("`(1 ,@x 4)"), "(1 2 3 4)");
+    check(&with_x("`(1 ,@x)"), "(1 2 3)");
+    check(&with_x("`(,@x)"), "(2 3)");
+    check(&with_x("`(,@x ,@x)"), "(2 3 2 3)");
+    check(&with_x("`((,@x) ,@x)"), "((2 3) 2 3)");
+
+    // splicing nothing
+    check(&with_x("`(1 ,@nil 2)"), "(1 2)");
+    check(&with_x("`(1 ,@'() 2)"), "(1 2)");
+    check(&with_x("`(,@nil)"), "()");
+
+    // unquote-splicing is the same as splice-unquote
+    check(&with_x("`(1 (unquote-splicing x))"), "(1 2 3)");
+
+    check_err(
+        &with_x("`(1 ,@n)"),
+        "\"splice-unquote\" requires a list, got 5",
+    );
+    check_err(
+        &with_x("`(1 ,@\"ab\")"),
+        "\"splice-unquote\" requires a list, got \"ab\"",
+    );
+    check_err(
+        &with_x("`,@x"),
+        "\"splice-unquote\" can only be used inside of a list",
+    );
+}
+
+#[test]
+fn dotted_tails() {
+    check(&with_x("`(1 . ,x)"), "(1 2 3)");
+    check(&with_x("`(1 . ,n)"), "(1 . 5)");
+    check(&with_x("`(1 . ,nil)"), "(1)");
+    check(&with_x("`(1 . 2)"), "(1 . 2)");
+    check(&with_x("`(,@x . ,x)"), "(2 3 2 3)");
+    check(&with_x("`((1 . ,n) . ,x)"), "((1 . 5) 2 3)");
+}
+
+#[test]
+fn nesting() {
+    // only unquotes as deep as the outermost quasiquote are evaluated
+    check(
+        &with_x("`(1 `(2 ,(3 ,n)))"),
+        "(1 (quasiquote (2 (unquote (3 5)))))",
+    );
+    check(&with_x("`(1 `(2 ,n))"), "(1 (quasiquote (2 (unquote n))))");
+    check(&with_x("`(1 `(2 ,,n))"), "(1 (quasiquote (2 (unquote 5))))");
+    check(
+        &with_x("`(1 `(2 ,',n))"),
+        "(1 (quasiquote (2 (unquote (quote 5)))))",
+    );
+    check(
+        &with_x("`(1 `(2 ,@,x))"),
+        "(1 (quasiquote (2 (splice-unquote (2 3)))))",
+    );
+    check(
+        &with_x("`(1 `(2 ,@x))"),
+        "(1 (quasiquote (2 (splice-unquote x))))",
+    );
+    check(
+        &with_x("`(1 `(2 `(3 ,,,n)))"),
+        "(1 (quasiquote (2 (quasiquote (3 (unquote (unquote 5)))))))",
+    );
+
+    // evaluating the inner quasiquote finishes it
+    check(&with_x("(eval `(list `(1 ,,n)))"), "((1 5))");
+}
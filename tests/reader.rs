@@ -0,0 +1,114 @@
+use euphie::{interpreter::*, parse::read_form, sandbox::*, util::value_to_string};
+
+mod common;
+use common::{eval, sandboxed};
+
+#[test]
+fn reading_strings() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        eval(&mut interpreter, r#"(read-from-string "(a 'b) c")"#),
+        Ok(String::from("(a (quote b))"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, r#"(read-all " 1 :k \"s\" ; comment")"#),
+        Ok(String::from("(1 :k \"s\")"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, r#"(read-from-string "  ")"#),
+        Err(String::from("There's no form in the string to read"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, r#"(read-all "(a")"#),
+        Err(String::from("Expected ')' at end of file"))
+    );
+
+    // the offset is right after the form
+    let (form, end) = read_form(" '(a)  (b)").unwrap().unwrap();
+    assert_eq!(value_to_string(&form), "(quote (a))");
+    assert_eq!(end, 5);
+}
+
+#[test]
+fn malformed_tokens() {
+    let mut interpreter = Interpreter::new();
+    for (code, error) in [
+        (r#""\"abc""#, "Unterminated string"),
+        (r#""\"abc\\""#, "Unterminated string"),
+        (r#""|""#, "Unterminated |symbol|"),
+        (r#"":|k""#, "Unterminated |symbol|"),
+        (r#""'""#, "Expected a form after the prefix '"),
+        (r#""(a ,@)""#, "Expected a form after the prefix ,@"),
+        (r#""' a""#, "Expected a form after the prefix '"),
+        (r#""'; comment""#, "Expected a form after the prefix '"),
+    ] {
+        assert_eq!(
+            eval(&mut interpreter, &format!("(read-from-string {})", code)),
+            Err(String::from(error)),
+            "{}",
+            code
+        );
+    }
+
+    // closed ones are fine, and so is a prefix right before what it applies to
+    assert_eq!(
+        eval(&mut interpreter, r#"(read-all "\"a\\\"b\" |c d| '`e")"#),
+        Ok(String::from("(\"a\\\"b\" |c d| (quote (quasiquote e)))"))
+    );
+}
+
+#[test]
+fn eval_in_environments() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        eval(&mut interpreter, r#"(eval (read-from-string "(+ 1 2)"))"#),
+        Ok(String::from("3"))
+    );
+
+    // a child environment sees its parent's definitions, but its own don't leak into it
+    eval(&mut interpreter, "(def child (make-env (current-env)))").unwrap();
+    eval(&mut interpreter, "(def x 1)").unwrap();
+    assert_eq!(
+        eval(&mut interpreter, "(eval '(def y (+ x 1)) child)"),
+        Ok(String::from("2"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "y"),
+        Err(String::from("Unbound symbol: y"))
+    );
+
+    // a new root environment doesn't see anything defined here
+    eval(&mut interpreter, "(def fresh (make-env))").unwrap();
+    assert_eq!(
+        eval(&mut interpreter, "(eval 'x fresh)"),
+        Err(String::from("Unbound symbol: x"))
+    );
+
+    // current-env inside a lambda is the lambda's frame
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            "((lambda (a) (eval '(+ a 1) (current-env))) 41)"
+        ),
+        Ok(String::from("42"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(eval 1 2)"),
+        Err(String::from(
+            "Second argument to \"eval\" must be an environment"
+        ))
+    );
+}
+
+#[test]
+fn environments_keep_capabilities() {
+    let mut interpreter = sandboxed(&[Module::Core]);
+    assert_eq!(
+        eval(&mut interpreter, "(eval '(println 1) (make-env))"),
+        Err(String::from("Unbound symbol: println"))
+    );
+    assert_eq!(
+        eval(&mut interpreter, r#"(read-from-string "a")"#),
+        Err(String::from("Unbound symbol: read-from-string"))
+    );
+}
@@ -0,0 +1,143 @@
+use euphie::{env::Env, eval::eval_toplevel, parse::*, symbol::Symbol, tokenize::tokenize};
+
+mod common;
+use common::{check, eval_all};
+
+// the local references in a value, as (name depth slot), in the order they're in the code
+fn local_refs(value: &Value, refs: &mut Vec<(String, usize, usize)>) {
+    match value {
+        Value::LocalRef { name, depth, slot } => {
+            refs.push((name.as_str().to_string(), *depth, *slot))
+        }
+        Value::List(l) => l.iter().for_each(|item| local_refs(item, refs)),
+        _ => {}
+    }
+}
+
+// defines f with the code and returns the references in its body
+fn resolved(code: &str) -> Vec<(String, usize, usize)> {
+    let mut env = Env::new();
+    let mut tokens = tokenize(format!("(def f {})", code)).unwrap();
+    tokens.reverse();
+    eval_toplevel(&parse(&mut tokens).unwrap(), &mut env).unwrap();
+    let Some(Value::Lambda { body, .. }) = env.borrow().get(Symbol::new("f")) else {
+        panic!("f isn't a lambda");
+    };
+
+    let mut refs = vec![];
+    local_refs(&body, &mut refs);
+    refs
+}
+
+fn local(name: &str, depth: usize, slot: usize) -> (String, usize, usize) {
+    (String::from(name), depth, slot)
+}
+
+#[test]
+fn locals_are_resolved_to_slots() {
+    assert_eq!(
+        resolved("(lambda (a b) (+ a b global))"),
+        vec![local("a", 0, 0), local("b", 0, 1)]
+    );
+    // a nested lambda's frame comes before the one it's in
+    assert_eq!(
+        resolved("(lambda (a) (lambda (b) `(,a ,b)))"),
+        vec![local("a", 1, 0), local("b", 0, 0)]
+    );
+    assert_eq!(
+        resolved("(lambda (a) (let ((b a) (c 1)) `(,a ,b ,c)))"),
+        vec![
+            local("a", 0, 0),
+            local("a", 1, 0),
+            local("b", 0, 0),
+            local("c", 0, 1)
+        ]
+    );
+    // the variables of the environment the lambda is made in are resolved too, but not globals
+    assert_eq!(
+        resolved("(let ((x 1)) (lambda (a) `(,a ,x)))"),
+        vec![local("a", 0, 0), local("x", 1, 0)]
+    );
+}
+
+#[test]
+fn some_names_stay_symbols() {
+    // quoted symbols and names that are defined with def (which adds them at runtime)
+    assert_eq!(resolved("(lambda (a) '(a b))"), vec![]);
+    assert_eq!(
+        resolved("(lambda (a) (let () (def b 1) `(,a ,b)))"),
+        vec![local("a", 1, 0)]
+    );
+    assert_eq!(resolved("(lambda (a) `(a ,a))"), vec![local("a", 0, 0)]);
+}
+
+#[test]
+fn resolved_code_behaves_the_same() {
+    // closures keep their own frames
+    assert_eq!(
+        eval_all(&[
+            "(def adder (lambda (n) (lambda (x) (+ x n))))",
+            "`(,((adder 1) 10) ,((adder 2) 10))",
+        ]),
+        Ok(String::from("(11 12)"))
+    );
+
+    // shadowing, with the innermost binding winning
+    check(
+        "((lambda (x) (let ((x (+ x 1))) (let* ((x (* x 2))) x))) 1)",
+        "4",
+    );
+    check("((lambda (x) (match '(5) ((x) x))) 1)", "5");
+    check(
+        "((lambda (n) (labels ((down (k) (if (= k 0) n (down (- k 1))))) (down 3))) 7)",
+        "7",
+    );
+
+    // globals are looked up when they're used, so redefining one is seen by lambdas defined before
+    assert_eq!(
+        eval_all(&[
+            "(def g 1)",
+            "(def get-g (lambda () g))",
+            "(def g 2)",
+            "(get-g)"
+        ]),
+        Ok(String::from("2"))
+    );
+
+    // a def inside of a body shadows from then on
+    check(
+        "((lambda (x) (let () (def y (+ x 1)) `(,x ,y))) 1)",
+        "(1 2)",
+    );
+}
+
+#[test]
+fn macros_defined_later_get_names() {
+    // f's body was resolved before m was a macro, so m gets the code with x resolved to a slot
+    let f_then_m = [
+        "(def f (lambda (x) (m x)))",
+        "(def m (macro (y) (list 'quote y)))",
+    ];
+    let with = |code: &str| {
+        let mut forms = f_then_m.to_vec();
+        forms.push(code);
+        eval_all(&forms)
+    };
+
+    assert_eq!(with("(equal? (f 1) 'x)"), Ok(String::from("t")));
+    assert_eq!(with("(eval (f 1))"), Err(String::from("Unbound symbol: x")));
+    // the code the macro returns still runs where the call was
+    assert_eq!(
+        eval_all(&[
+            "(def g (lambda (x) (n x)))",
+            "(define-syntax n (syntax-rules () ((_ e) (let ((x 0)) (+ e 1)))))",
+            "(g 5)",
+        ]),
+        Ok(String::from("6"))
+    );
+
+    // and a slot that isn't there is an error rather than a crash
+    let env = Env::new();
+    assert_eq!(env.borrow().get_at(0, 0), None);
+    assert_eq!(Env::extend(&env).borrow().get_at(2, 0), None);
+}
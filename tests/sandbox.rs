@@ -0,0 +1,196 @@
+use euphie::sandbox::*;
+
+mod common;
+use common::{eval, sandboxed, sandboxed_in, temp_dir};
+
+fn unbound(name: &str) -> Result<String, String> {
+    Err(format!("Unbound symbol: {}", name))
+}
+
+// a call to a builtin from each module
+const CALLS: [(Module, &str, &str); 8] = [
+    (Module::Core, "car", "(car '(1 2))"),
+    (Module::Math, "+", "(+ 1 2)"),
+    (Module::String, "string-length", "(string-length \"abc\")"),
+    (Module::IoRead, "read-line", "(read-line)"),
+    (Module::IoWrite, "print", "(print)"),
+    (Module::Process, "getenv", "(getenv \"HOME\")"),
+    (Module::Time, "current-time", "(current-time)"),
+    (Module::Fs, "file-exists?", "(file-exists? \"x\")"),
+];
+
+#[test]
+fn empty_environments_only_have_the_language() {
+    let mut interpreter = sandboxed(&[]);
+    for (_, name, call) in CALLS {
+        assert_eq!(eval(&mut interpreter, call), unbound(name), "{}", call);
+    }
+
+    // special forms are always there
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            "(let ((f (lambda (x) (if x 'yes 'no)))) `(,(f t) ,(f nil) (a b)))"
+        ),
+        Ok(String::from("(yes no (a b))"))
+    );
+}
+
+#[test]
+fn modules_only_give_their_own_builtins() {
+    for (module, _, call) in CALLS {
+        let mut interpreter = sandboxed(&[module]);
+        for (other, other_name, other_call) in CALLS {
+            if other != module {
+                assert_eq!(
+                    eval(&mut interpreter, other_call),
+                    unbound(other_name),
+                    "{} with {}",
+                    other_call,
+                    module.name()
+                );
+            }
+        }
+        // reading from stdin would wait for input
+        if module != Module::IoRead {
+            assert!(
+                eval(&mut interpreter, call).is_ok(),
+                "{} with {}",
+                call,
+                module.name()
+            );
+        }
+    }
+}
+
+#[test]
+fn restricted_builtins_cant_be_reached_another_way() {
+    let mut interpreter = sandboxed(&[Module::Core, Module::Math]);
+
+    // not through macros or lambdas
+    eval(&mut interpreter, "(def say (macro (x) `(println ,x)))").unwrap();
+    assert_eq!(eval(&mut interpreter, "(say 1)"), unbound("println"));
+    assert_eq!(
+        eval(&mut interpreter, "((lambda (x) (getenv x)) \"HOME\")"),
+        unbound("getenv")
+    );
+
+    // or eval and new environments
+    assert_eq!(
+        eval(&mut interpreter, "(eval '(println 1))"),
+        unbound("println")
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(eval '(println 1) (make-env))"),
+        unbound("println")
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(eval '(getenv \"HOME\") (current-env))"),
+        unbound("getenv")
+    );
+
+    // a name that's defined is just a name
+    eval(&mut interpreter, "(def println (lambda (x) (+ x 1)))").unwrap();
+    assert_eq!(eval(&mut interpreter, "(println 1)"), Ok(String::from("2")));
+}
+
+#[test]
+fn module_names() {
+    for module in Module::ALL {
+        assert_eq!(Module::from_name(module.name()), Some(module));
+    }
+    assert_eq!(Module::from_name("io"), None);
+
+    let all = Capabilities::all();
+    let none = Capabilities::default();
+    for module in Module::ALL {
+        assert!(all.allows(module));
+        assert!(!none.allows(module));
+    }
+}
+
+#[test]
+fn filesystem_roots() {
+    let dir = temp_dir("sandbox-root");
+    std::fs::write(dir.join("inside.txt"), "in").unwrap();
+    let mut interpreter = sandboxed_in(&[Module::Fs, Module::IoRead], &dir);
+
+    assert_eq!(
+        eval(&mut interpreter, "(read-file \"inside.txt\")"),
+        Ok(String::from("\"in\""))
+    );
+    assert_eq!(
+        eval(&mut interpreter, "(read-file \"a/../inside.txt\")"),
+        Ok(String::from("\"in\""))
+    );
+    for path in ["../x", "/etc/passwd", "a/../../x"] {
+        assert_eq!(
+            eval(&mut interpreter, &format!("(read-file \"{}\")", path)),
+            Err(format!(
+                "Path \"{}\" is outside of the filesystem root",
+                path
+            )),
+            "{}",
+            path
+        );
+        assert!(eval(&mut interpreter, &format!("(open-input-file \"{}\")", path)).is_err());
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_cant_lead_out_of_the_root() {
+    use std::os::unix::fs::symlink;
+
+    let dir = temp_dir("sandbox-symlinks");
+    let root = dir.join("root");
+    let outside = dir.join("outside");
+    std::fs::create_dir_all(root.join("sub")).unwrap();
+    std::fs::create_dir_all(&outside).unwrap();
+    std::fs::write(outside.join("secret.txt"), "secret").unwrap();
+
+    // to a file, a directory, or something that doesn't exist yet, which writing would create
+    symlink(outside.join("secret.txt"), root.join("file")).unwrap();
+    symlink(&outside, root.join("dir")).unwrap();
+    symlink(outside.join("new.txt"), root.join("dangling")).unwrap();
+    symlink("../../outside/new.txt", root.join("sub/relative")).unwrap();
+    symlink("dangling", root.join("chained")).unwrap();
+    let mut interpreter = sandboxed_in(&[Module::Fs, Module::IoRead], &root);
+
+    for (code, path) in [
+        ("(read-file \"file\")", "file"),
+        ("(read-file \"dir/secret.txt\")", "dir/secret.txt"),
+        ("(write-file \"dangling\" \"x\")", "dangling"),
+        ("(write-file \"sub/relative\" \"x\")", "sub/relative"),
+        ("(write-file \"chained\" \"x\")", "chained"),
+        ("(write-file \"dir/new.txt\" \"x\")", "dir/new.txt"),
+    ] {
+        assert_eq!(
+            eval(&mut interpreter, code),
+            Err(format!(
+                "Path \"{}\" is outside of the filesystem root",
+                path
+            )),
+            "{}",
+            code
+        );
+    }
+    assert!(!outside.join("new.txt").exists());
+
+    // links that stay inside of the root work like the file they point to
+    symlink("sub/../inside.txt", root.join("inside-link")).unwrap();
+    eval(&mut interpreter, "(write-file \"inside-link\" \"in\")").unwrap();
+    assert_eq!(
+        std::fs::read_to_string(root.join("inside.txt")).unwrap(),
+        "in"
+    );
+
+    // and links that point at each other are an error rather than a loop
+    symlink("b", root.join("a")).unwrap();
+    symlink("a", root.join("b")).unwrap();
+    assert!(eval(&mut interpreter, "(read-file \"a\")")
+        .unwrap_err()
+        .starts_with("Too many symlinks"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
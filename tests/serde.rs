@@ -0,0 +1,102 @@
+#![cfg(feature = "serde")]
+
+use euphie::{from_value, interpreter::Interpreter, parse::Value, to_value, util::value_to_string};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Shape {
+    Point,
+    Circle(f64),
+    Rect(f64, f64),
+    Named { name: String, sides: u32 },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Config {
+    name: String,
+    retries: u8,
+    verbose: bool,
+    timeout: Option<f64>,
+    tags: Vec<String>,
+    shapes: Vec<Shape>,
+}
+
+fn config() -> Config {
+    Config {
+        name: String::from("test"),
+        retries: 3,
+        verbose: false,
+        timeout: None,
+        tags: vec![String::from("a"), String::from("b")],
+        shapes: vec![
+            Shape::Point,
+            Shape::Circle(1.5),
+            Shape::Rect(1.0, 2.0),
+            Shape::Named {
+                name: String::from("tri"),
+                sides: 3,
+            },
+        ],
+    }
+}
+
+#[test]
+fn structs_become_plists() {
+    assert_eq!(
+        value_to_string(&to_value(&config()).unwrap()),
+        "(:name \"test\" :retries 3 :verbose nil :timeout nil :tags (\"a\" \"b\") \
+         :shapes (:Point (:Circle 1.5) (:Rect 1 2) (:Named :name \"tri\" :sides 3)))"
+    );
+}
+
+#[test]
+fn round_trip() {
+    let value = to_value(&config()).unwrap();
+    assert_eq!(from_value::<Config>(&value).unwrap(), config());
+
+    let map = BTreeMap::from([(String::from("a"), 1), (String::from("b"), 2)]);
+    let value = to_value(&map).unwrap();
+    assert_eq!(value_to_string(&value), "(:a 1 :b 2)");
+    assert_eq!(from_value::<BTreeMap<String, i32>>(&value).unwrap(), map);
+}
+
+#[test]
+fn through_lisp_code() {
+    let mut interpreter = Interpreter::new();
+    interpreter.env().borrow_mut().set(
+        euphie::symbol::Symbol::new("config"),
+        to_value(&config()).unwrap(),
+    );
+    let value = interpreter
+        .eval_str(
+            "(list :name (cadr config) :retries 5 :verbose t :tags nil :shapes (list :Point))",
+        )
+        .unwrap();
+
+    let config: Config = from_value(&value).unwrap();
+    assert_eq!(config.name, "test");
+    assert_eq!(config.retries, 5);
+    assert!(config.verbose);
+    assert_eq!(config.timeout, None);
+    assert!(config.tags.is_empty());
+    assert_eq!(config.shapes, vec![Shape::Point]);
+}
+
+#[test]
+fn values_round_trip() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .eval_str("(quote (1 \"two\" three :four (:a 1)))")
+        .unwrap();
+    assert_eq!(to_value(&value).unwrap(), value);
+}
+
+#[test]
+fn errors() {
+    assert!(from_value::<u8>(&Value::String("x".into())).is_err());
+    assert!(from_value::<Shape>(&Value::Number(1.0)).is_err());
+    let mut interpreter = Interpreter::new();
+    let lambda = interpreter.eval_str("(lambda (x) x)").unwrap();
+    assert!(to_value(&lambda).is_err());
+}
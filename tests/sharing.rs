@@ -0,0 +1,88 @@
+use euphie::{env::Env, eval::eval_toplevel, parse::*, symbol::Symbol, tokenize::tokenize};
+use std::{cell::RefCell, rc::Rc};
+
+// whether the two values share the same list or string, instead of one being a copy of the other
+fn shared(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::List(a), Value::List(b)) => List::ptr_eq(a, b),
+        (Value::String(a), Value::String(b)) => Rc::ptr_eq(a, b),
+        _ => false,
+    }
+}
+
+fn eval(env: &mut Rc<RefCell<Env>>, code: &str) -> Value {
+    let mut tokens = tokenize(String::from(code)).unwrap();
+    tokens.reverse();
+    eval_toplevel(&parse(&mut tokens).unwrap(), env).unwrap()
+}
+
+fn setup() -> Rc<RefCell<Env>> {
+    let mut env = Env::new();
+    eval(&mut env, "(def data '((1 2) (3 4) 5))");
+    eval(&mut env, "(def text \"some text\")");
+    env
+}
+
+fn global(env: &Rc<RefCell<Env>>, name: &str) -> Value {
+    env.borrow().get(Symbol::new(name)).unwrap()
+}
+
+#[test]
+fn variables_share_their_values() {
+    let mut env = setup();
+    let data = global(&env, "data");
+    let text = global(&env, "text");
+
+    assert!(shared(&eval(&mut env, "data"), &data));
+    assert!(shared(&eval(&mut env, "text"), &text));
+    // through locals, closures and function calls too
+    assert!(shared(&eval(&mut env, "(let ((d data)) d)"), &data));
+    assert!(shared(
+        &eval(&mut env, "((lambda (x) ((lambda () x))) text)"),
+        &text
+    ));
+}
+
+#[test]
+fn quoted_data_is_shared() {
+    let mut env = setup();
+    eval(&mut env, "(def f (lambda () '(a b c)))");
+    let first = eval(&mut env, "(f)");
+    assert!(shared(&first, &eval(&mut env, "(f)")));
+}
+
+#[test]
+fn list_items_are_shared() {
+    let mut env = setup();
+    let Value::List(items) = global(&env, "data") else {
+        panic!("data isn't a list");
+    };
+
+    // car gives back the items themselves, and building a list around them doesn't copy them
+    assert!(shared(&eval(&mut env, "(car data)"), &items[0]));
+    assert!(shared(&eval(&mut env, "(car (cdr data))"), &items[1]));
+    let Value::List(built) = eval(&mut env, "`(,(car data) ,text)") else {
+        panic!("quasiquote didn't return a list");
+    };
+    assert!(shared(&built[0], &items[0]));
+    assert!(shared(&built[1], &global(&env, "text")));
+}
+
+#[test]
+fn cdr_shares_the_rest() {
+    let mut env = setup();
+    let Value::List(items) = global(&env, "data") else {
+        panic!("data isn't a list");
+    };
+
+    // the rest of a list is the same items, not a copy of them
+    let Value::List(rest) = eval(&mut env, "(cdr data)") else {
+        panic!("cdr didn't return a list");
+    };
+    assert!(std::ptr::eq(&rest[0], &items[1]));
+    assert!(shared(
+        &eval(&mut env, "(cdr data)"),
+        &Value::List(rest.clone())
+    ));
+    assert!(std::ptr::eq(&rest.skip(1)[0], &items[2]));
+}
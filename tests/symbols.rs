@@ -0,0 +1,60 @@
+use euphie::symbol::Symbol;
+
+mod common;
+use common::{check, check_err};
+
+#[test]
+fn interning() {
+    // the same name is always the same symbol
+    let a = Symbol::new("some-name");
+    assert_eq!(a, Symbol::new("some-name"));
+    assert_ne!(a, Symbol::new("some-other-name"));
+    assert_eq!(a.as_str(), "some-name");
+    assert!(a.is_interned());
+
+    // but an uninterned one is different from every other symbol
+    let b = Symbol::uninterned("some-name");
+    assert_ne!(a, b);
+    assert_ne!(b, Symbol::uninterned("some-name"));
+    assert_eq!(b.clone(), b);
+    assert_eq!(b.as_str(), "some-name");
+    assert!(!b.is_interned());
+    assert_eq!(Symbol::new("some-name"), a);
+}
+
+#[test]
+fn keywords() {
+    // keywords evaluate to themselves, and are a different kind of value than the symbols with their names
+    check(":k", ":k");
+    check("`(a :a)", "(a :a)");
+    check("(match :a ('a 1) (:a 2))", "2");
+}
+
+#[test]
+fn builtins() {
+    check("(symbol->string 'abc)", "\"abc\"");
+    // keywords keep their colon
+    check("(symbol->string :k)", "\":k\"");
+    check("(match (string->symbol \"a\") ('a t))", "t");
+    check("(match (intern \":a\") (:a t))", "t");
+    check("(intern (symbol->string :k))", ":k");
+
+    // string->symbol takes the name as is, intern reads it like the reader does
+    check("(string->symbol \":a\")", "|:a|");
+    check("(string->symbol \"a b\")", "|a b|");
+    check("(intern \":a\")", ":a");
+    check_err(
+        "(intern \"#:g1\")",
+        "Uninterned symbols can't be read: #:g1",
+    );
+
+    check_err(
+        "(symbol->string \"a\")",
+        "Argument to \"symbol->string\" must be a symbol or keyword",
+    );
+    check_err(
+        "(string->symbol 'a)",
+        "Argument to \"string->symbol\" must be a string",
+    );
+    check_err("(intern)", "\"intern\" requires 1 argument");
+}
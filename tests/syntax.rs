@@ -0,0 +1,148 @@
+mod common;
+use common::{check, eval_all};
+
+#[test]
+fn syntax_rules() {
+    check(
+        "(let ()
+           (define-syntax my-or (syntax-rules () ((_) nil) ((_ e) e) ((_ e rest ...) (let ((x e)) (if x x (my-or rest ...))))))
+           `(,(my-or) ,(my-or nil 2) ,(my-or nil nil 3)))",
+        "(nil 2 3)",
+    );
+    // literals have to be there as they are
+    check(
+        "(let ()
+           (define-syntax arrow (syntax-rules (=>) ((_ a => b) `(,a ,b)) ((_ a b) 'no-arrow)))
+           `(,(arrow 1 => 2) ,(arrow 1 2)))",
+        "((1 2) no-arrow)",
+    );
+    check(
+        "(let ()
+           (define-syntax pairs (syntax-rules () ((_ (a b) ...) '((b a) ...))))
+           (pairs (1 2) (3 4)))",
+        "((2 1) (4 3))",
+    );
+    check(
+        "(let ()
+           (define-syntax second (syntax-rules () ((_ a b . rest) 'b)))
+           (second 1 2 3 4))",
+        "2",
+    );
+
+    let error =
+        eval_all(&["(let () (define-syntax one (syntax-rules () ((_ x) x))) (one))"]).unwrap_err();
+    assert!(
+        error.starts_with("No syntax-rules pattern matches"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn introduced_names_dont_capture() {
+    // the template's tmp is renamed, so it's not the tmp that's passed in
+    check(
+        "(let ()
+           (define-syntax swap (syntax-rules () ((_ a b) (let ((tmp a)) `(,b ,tmp)))))
+           (let ((tmp 1) (y 2)) (swap tmp y)))",
+        "(2 1)",
+    );
+    check(
+        "(let ()
+           (define-syntax call-with (syntax-rules () ((_ e body) ((lambda (v) body) e))))
+           (let ((v 1)) (call-with 2 v)))",
+        "1",
+    );
+    // including the variables of match patterns, names made by with-gensyms and names defined with def
+    check(
+        "(let ()
+           (define-syntax m (syntax-rules () ((_ e) (match (list 1) ((tmp) e)))))
+           (let ((tmp 10)) (m tmp)))",
+        "10",
+    );
+    check(
+        "(let ()
+           (define-syntax m (syntax-rules () ((_ e) (with-gensyms (tmp) e))))
+           (let ((tmp 10)) (m tmp)))",
+        "10",
+    );
+    assert_eq!(
+        eval_all(&[
+            "(define-syntax m (syntax-rules () ((_ e) (let () (def tmp 1) (+ tmp e)))))",
+            "(def tmp 10)",
+            "(list (m tmp) tmp)",
+        ]),
+        Ok(String::from("(11 10)"))
+    );
+    check(
+        "(let ()
+           (define-syntax twice (syntax-rules () ((_ e) (let* ((n e) (m n)) (+ n m)))))
+           (let ((n 10)) (twice n)))",
+        "20",
+    );
+}
+
+#[test]
+fn free_identifiers_refer_to_the_definition() {
+    let helper = [
+        "(def helper (lambda (x) (* x 10)))",
+        "(define-syntax m (syntax-rules () ((_ e) (helper e))))",
+    ];
+    let with = |code: &str| {
+        let mut forms = helper.to_vec();
+        forms.push(code);
+        eval_all(&forms)
+    };
+
+    // free identifiers in the template refer to what they're bound to where the macro was defined
+    assert_eq!(
+        with("(let ((helper (lambda (x) 0))) (m 2))"),
+        Ok(String::from("20"))
+    );
+    assert_eq!(
+        with("((lambda (helper) (m 3)) (lambda (x) 0))"),
+        Ok(String::from("30"))
+    );
+    assert_eq!(
+        with("(labels ((helper (x) 0)) (m 4))"),
+        Ok(String::from("40"))
+    );
+    assert_eq!(
+        eval_all(&[
+            helper[0],
+            helper[1],
+            "(def f (lambda (helper) (m 5)))",
+            "(f 0)"
+        ]),
+        Ok(String::from("50"))
+    );
+
+    // but globals are still looked up when the code runs, so redefining them works
+    assert_eq!(
+        eval_all(&[
+            helper[0],
+            helper[1],
+            "(def f (lambda () (m 1)))",
+            "(def helper (lambda (x) (* x 100)))",
+            "(f)",
+        ]),
+        Ok(String::from("100"))
+    );
+
+    // including locals, for macros defined inside of other forms
+    assert_eq!(
+        eval_all(&[
+            "(let ((y 5)) (define-syntax get-y (syntax-rules () ((_) y))) (let ((y 0)) (get-y)))"
+        ]),
+        Ok(String::from("5"))
+    );
+
+    // quoted symbols are just data
+    assert_eq!(
+        eval_all(&[
+            "(define-syntax name (syntax-rules () ((_) 'helper)))",
+            "(let ((helper 1)) (name))",
+        ]),
+        Ok(String::from("helper"))
+    );
+}
@@ -0,0 +1,155 @@
+use euphie::{interpreter::*, parse::Value, util::value_to_string, vm::*};
+use std::rc::Rc;
+
+mod common;
+use common::eval_all;
+
+// runs the forms on the VM, returning the value of the last one written out, or the first error
+fn run_vm(forms: &[&str]) -> Result<String, String> {
+    let mut interpreter = Interpreter::new();
+    let mut vm = Vm::with_prelude();
+    let mut result = String::from("nil");
+    for code in forms {
+        let form = interpreter.parse_all(code).map_err(|e| e.to_string())?;
+        let expanded = interpreter.expand(&form[0]).map_err(|e| e.to_string())?;
+        result = value_to_string(&vm.run(compile(&expanded, interpreter.env())?)?);
+    }
+    Ok(result)
+}
+
+// the evaluator and the VM have to agree on the value, or on failing with the same message
+fn check(forms: &[&str]) {
+    assert_eq!(run_vm(forms), eval_all(forms), "{:?}", forms);
+}
+
+#[test]
+fn same_results() {
+    for code in [
+        "(+ 1 2 3)",
+        "(- 5)",
+        "(/ 12 2 3)",
+        "(< 1 2 3)",
+        "(>= 3 3 1)",
+        "(!= 1 2)",
+        "(if (> 2 1) 'yes 'no)",
+        "(and t (+ 1 2))",
+        "(and t nil 1)",
+        "(and)",
+        "(or nil 2)",
+        "(or nil nil)",
+        "(not nil)",
+        "(not (car (list 1)))",
+        "(car (list 1 2))",
+        "(cdr (list 1 2))",
+        "(cdr (list 1))",
+        "(len (list 1 2 3))",
+        "'(a b)",
+        "\"text\"",
+        ":key",
+        "(let ((a 1) (b 2)) (+ a b))",
+        "(let* ((a 1) (b (+ a 1))) (list a b))",
+        "(letrec ((even? (lambda (n) (if (= n 0) t (odd? (- n 1))))) (odd? (lambda (n) (if (= n 0) nil (even? (- n 1)))))) (even? 10))",
+        "(labels ((fact (n) (if (= n 0) 1 (* n (fact (- n 1)))))) (fact 10))",
+        "((lambda (a &optional b &rest c) (list a b c)) 1 2 3 4)",
+        "((lambda (a &key b) (list a b)) 1 :b 2)",
+        "(let ((x (list 2 3))) `(1 ,@x ,(car x) . ,x))",
+        "(def x (+ 2 3))",
+        "`(1 `(2 ,(3 ,(+ 1 3))))",
+        "(map (lambda (x) (* x x)) (list 1 2 3))",
+        "(filter (lambda (x) (> x 1)) (list 1 2 3))",
+        "(map (lambda (x) x) nil)",
+        "(caddr (list 1 2 3))",
+        "(when (> 2 1) 1 2)",
+        "(unless (> 2 1) 1 2)",
+    ] {
+        check(&[code]);
+    }
+
+    check(&[
+        "(def make-counter (lambda () (let ((n 0)) (lambda () (def n (+ n 1)) n))))",
+        "(def c (make-counter))",
+        "(c)",
+    ]);
+    check(&[
+        "(def twice (macro (x) `(+ ,x ,x)))",
+        "(def f (lambda (n) (twice n)))",
+        "(f 4)",
+    ]);
+    check(&[
+        "(def helper (lambda (x) (* x 10)))",
+        "(define-syntax m (syntax-rules () ((_ e) (helper e))))",
+        "(let ((x 0)) (m 2))",
+    ]);
+}
+
+#[test]
+fn macros_are_made_where_the_code_was_expanded() {
+    let mut interpreter = Interpreter::new();
+    let form = interpreter
+        .parse_all("(syntax-rules () ((_ e) e))")
+        .unwrap();
+    let function = compile(&form[0], interpreter.env()).unwrap();
+    match Vm::new().run(function) {
+        Ok(Value::Syntax { env, .. }) => assert!(Rc::ptr_eq(&env.0, interpreter.env())),
+        other => panic!("expected syntax rules, got {:?}", other),
+    }
+}
+
+#[test]
+fn same_errors() {
+    for code in [
+        "(car nil)",
+        "(+ 1 \"a\")",
+        "(if 1 2 3)",
+        "(undefined 1)",
+        "(1 2)",
+        "(car 1 2)",
+        "(-)",
+        "(/)",
+    ] {
+        check(&[code]);
+    }
+}
+
+#[test]
+fn runaway_recursion() {
+    assert_eq!(
+        run_vm(&["((lambda (f) (f f)) (lambda (f) (f f)))"]),
+        Err(String::from("Call depth limit exceeded"))
+    );
+
+    let mut vm = Vm::new();
+    vm.set_max_depth(Some(10));
+    let mut interpreter = Interpreter::new();
+    let form = interpreter
+        .parse_all("(labels ((count (n) (if (= n 0) 0 (+ 1 (count (- n 1)))))) (count 20))")
+        .unwrap();
+    assert_eq!(
+        vm.run(compile(&form[0], interpreter.env()).unwrap()),
+        Err(String::from("Call depth limit exceeded"))
+    );
+    // the VM can still be used afterwards
+    let form = interpreter.parse_all("(+ 1 2)").unwrap();
+    assert!(vm
+        .run(compile(&form[0], interpreter.env()).unwrap())
+        .is_ok());
+}
+
+#[test]
+fn unsupported_forms() {
+    for (code, head) in [
+        ("(match 1 (x x))", "match"),
+        ("(gensym)", "gensym"),
+        ("(with-gensyms (a) a)", "gensym"),
+        ("(macroexpand '(f 1))", "macroexpand"),
+        ("(string-append \"a\" \"b\")", "string-append"),
+        ("(eval 1)", "eval"),
+    ] {
+        assert_eq!(
+            run_vm(&[code]),
+            Err(format!("\"{}\" isn't supported by the compiler yet", head)),
+            "{}",
+            code
+        );
+    }
+}